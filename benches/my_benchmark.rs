@@ -3,12 +3,9 @@ extern crate criterion;
 
 extern crate aes_s;
 
-use aes_s::cipher::Cipher;
+use aes_s::cipher::{Cipher, Decryptor, Encryptor};
 use criterion::black_box;
 use criterion::Criterion;
-use std::fs::File;
-use std::io;
-use std::io::prelude::*;
 
 fn criterion_benchmark(c: &mut Criterion) {
     //// 128 ////
@@ -22,10 +19,8 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     c.bench_function("cipher 128", |b| {
         b.iter(|| {
-            black_box({
-                cipher.cipher(&mut content);
-                cipher.decipher(&mut content);
-            })
+            cipher.cipher(black_box(&mut content));
+            cipher.decipher(black_box(&mut content));
         })
     });
 
@@ -43,10 +38,8 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     c.bench_function("cipher 192", |b| {
         b.iter(|| {
-            black_box({
-                cipher.cipher(&mut content);
-                cipher.decipher(&mut content);
-            })
+            cipher.cipher(black_box(&mut content));
+            cipher.decipher(black_box(&mut content));
         })
     });
 
@@ -65,16 +58,30 @@ fn criterion_benchmark(c: &mut Criterion) {
     let cipher = Cipher::new_256(&password);
     c.bench_function("cipher 256", |b| {
         b.iter(|| {
-            black_box({
-                cipher.cipher(&mut content);
-                cipher.decipher(&mut content);
-            })
+            cipher.cipher(black_box(&mut content));
+            cipher.decipher(black_box(&mut content));
         })
     });
 
     //// -256- ////
+}
 
+fn key_setup_benchmark(c: &mut Criterion) {
+    let password: [u32; 8] = [
+        0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+        0x1c1d1e1f,
+    ];
+
+    c.bench_function("key setup 256 cipher", |b| {
+        b.iter(|| Cipher::new_256(black_box(&password)))
+    });
+    c.bench_function("key setup 256 encryptor", |b| {
+        b.iter(|| Encryptor::new_256(black_box(&password)))
+    });
+    c.bench_function("key setup 256 decryptor", |b| {
+        b.iter(|| Decryptor::new_256(black_box(&password)))
+    });
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(benches, criterion_benchmark, key_setup_benchmark);
 criterion_main!(benches);
@@ -1,15 +1,15 @@
 #[cfg(test)]
-
+#[allow(clippy::module_inception)]
 mod aes_matrix_test {
     use crate::aes_matrix::AesMatrix;
-    use crate::math::Math;
+    use crate::math::S_BOX;
     #[test]
     fn it_substitutes_bytes() {
         let mut content: [u8; 16] = [
             0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
             0x0e, 0x0f,
         ];
-        AesMatrix::substitute_bytes_8(&mut content);
+        AesMatrix::substitute_bytes_with(&mut content, &S_BOX);
         assert_eq!(
             content,
             [
@@ -21,7 +21,7 @@ mod aes_matrix_test {
             0x10, 0x21, 0x32, 0x43, 0x54, 0x65, 0x76, 0x87, 0x98, 0xa9, 0xba, 0xcb, 0xdc, 0xed,
             0xfe, 0x0f,
         ];
-        AesMatrix::substitute_bytes_8(&mut content);
+        AesMatrix::substitute_bytes_with(&mut content, &S_BOX);
         assert_eq!(
             content,
             [
@@ -88,7 +88,7 @@ mod aes_matrix_test {
             0x00, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xa0, 0xb0, 0xc0, 0xd0,
             0xe0, 0xf0,
         ];
-        AesMatrix::substitute_bytes_8(&mut content);
+        AesMatrix::substitute_bytes_with(&mut content, &S_BOX);
         AesMatrix::shift_rows_8(&mut content);
         AesMatrix::mix_columns_8(&mut content);
         AesMatrix::add_round_key_8(
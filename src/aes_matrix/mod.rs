@@ -1,10 +1,22 @@
-use crate::math::{Math, INV_S_BOX, S_BOX};
+use crate::math::Gf256;
 
 mod aes_matrix_test;
 
 #[derive(Debug)]
 pub struct AesMatrix {}
 
+/**
+ * First row of the matrix used by mix columns, the other rows are its rotations
+ */
+pub static MIX_COLUMNS_COEFFICIENTS: [Gf256; 4] =
+    [Gf256(0x02), Gf256(0x03), Gf256(0x01), Gf256(0x01)];
+
+/**
+ * First row of the matrix used by the inverse of mix columns
+ */
+pub static INV_MIX_COLUMNS_COEFFICIENTS: [Gf256; 4] =
+    [Gf256(0x0e), Gf256(0x0b), Gf256(0x0d), Gf256(0x09)];
+
 /*
     Every bytes:[u8;16] array taken as argument in the functions of this module
     represents a bidimensional 4x4 array of bites structured like this:
@@ -31,21 +43,12 @@ pub struct AesMatrix {}
 impl AesMatrix {
     /**
      * Substitutes each byte of the matrix with the equivalent byte in
-     * the secure box as described in the AES standard
+     * the given table, which is the secure box or its inverse as described
+     * in the AES standard unless the cipher was built with a different one.
      */
-    pub fn substitute_bytes_8(bytes: &mut [u8; 16]) {
-        for i in 0..16 {
-            bytes[i] = S_BOX[bytes[i] as usize];
-        }
-    }
-
-    /**
-     * Substitutes each byte of the matrix with the equivalent byte in
-     * the inverse secure box as described in the AES standard
-     */
-    pub fn inv_substitute_bytes_8(bytes: &mut [u8; 16]) {
-        for i in 0..16 {
-            bytes[i] = INV_S_BOX[bytes[i] as usize];
+    pub fn substitute_bytes_with(bytes: &mut [u8; 16], table: &[u8; 256]) {
+        for byte in bytes.iter_mut() {
+            *byte = table[*byte as usize];
         }
     }
 
@@ -99,86 +102,70 @@ impl AesMatrix {
     }
 
     /**
-     * Applies the inverse_mix_column transformation to eache column in the matrix.
-     * Since the matrix is represented as an array of columns each column
-     * starts at the next multiple of 4.
+     * Applies the mix_column transformation to eache column in the matrix.
      */
     pub fn mix_columns_8(bytes: &mut [u8; 16]) {
-        Self::mix_column_8(bytes, 0x00);
-        Self::mix_column_8(bytes, 0x04);
-        Self::mix_column_8(bytes, 0x08);
-        Self::mix_column_8(bytes, 0x0C);
+        Self::mix_columns_with(bytes, &MIX_COLUMNS_COEFFICIENTS);
     }
 
     /**
-     * Basically the inverse_mix_columns sets the bytes in each column to:
-     *
-     * col[0] = (col[0] * 0x02) xor (col[1] * 0x03) xor col[2] xor col[3]
-     * col[1] = col[0] xor (col[1] * 0x02) xor (col[2] * 0x03) xor col[3]
-     * col[2] = col[0] xor col[1] xor (col[2] * 0x02) xor (col[3] * 0x03)
-     * col[3] = (col[0] * 0x03) xor col[1] xor col[2] xor (col[3] * 0x02)
-     *
-     * With '*' meaning the multiplication in the GF2 field as described by the AES standard
+     * Applies the inverse_mix_column transformation to eache column in the matrix.
      */
-    fn mix_column_8(bytes: &mut [u8; 16], offset: usize) {
-        let mut column_bytes = [
-            bytes[offset],
-            bytes[offset + 1],
-            bytes[offset + 2],
-            bytes[offset + 3],
-        ];
-        for index in 0..4 {
-            bytes[offset + index] = Math::x_time(column_bytes[0])
-                ^ Math::multiplication_by_03(column_bytes[1])
-                ^ column_bytes[2]
-                ^ column_bytes[3];
-            let temp = column_bytes[0];
-            column_bytes[0] = column_bytes[1];
-            column_bytes[1] = column_bytes[2];
-            column_bytes[2] = column_bytes[3];
-            column_bytes[3] = temp;
-        }
+    pub fn inv_mix_columns_8(bytes: &mut [u8; 16]) {
+        Self::mix_columns_with(bytes, &INV_MIX_COLUMNS_COEFFICIENTS);
     }
 
     /**
-     * Applies the inverse_mix_column transformation to eache column in the matrix.
+     * Multiplies each column of the matrix by the circulant matrix whose first
+     * row is coefficients, so both mix columns and its inverse, as well as any
+     * variant of them, are expressed by their coefficients.
      * Since the matrix is represented as an array of columns each column
      * starts at the next multiple of 4.
      */
-    pub fn inv_mix_columns_8(bytes: &mut [u8; 16]) {
-        Self::inv_mix_column_8(bytes, 0x00);
-        Self::inv_mix_column_8(bytes, 0x04);
-        Self::inv_mix_column_8(bytes, 0x08);
-        Self::inv_mix_column_8(bytes, 0x0C);
+    pub fn mix_columns_with(bytes: &mut [u8; 16], coefficients: &[Gf256; 4]) {
+        Self::mix_column_with(bytes, 0x00, coefficients);
+        Self::mix_column_with(bytes, 0x04, coefficients);
+        Self::mix_column_with(bytes, 0x08, coefficients);
+        Self::mix_column_with(bytes, 0x0C, coefficients);
     }
 
     /**
-     * Basically the inverse_mix_columns sets the bytes in each column to:
+     * Basically with coefficients [c0, c1, c2, c3] the bytes in each column are set to:
      *
-     * col[0] = (col[0] * 0x0e) xor (col[1] * 0x0b) xor (col[2] * 0x0d) xor (col[3] * 0x09)
-     * col[1] = (col[0] * 0x09) xor (col[1] * 0x0e) xor (col[2] * 0x0b) xor (col[3] * 0x0d)
-     * col[2] = (col[0] * 0x0d) xor (col[1] * 0x09) xor (col[2] * 0x0e) xor (col[3] * 0x0b)
-     * col[3] = (col[0] * 0x0b) xor (col[1] * 0x0d) xor (col[2] * 0x09) xor (col[3] * 0x0e)
+     * col[0] = (col[0] * c0) xor (col[1] * c1) xor (col[2] * c2) xor (col[3] * c3)
+     * col[1] = (col[1] * c0) xor (col[2] * c1) xor (col[3] * c2) xor (col[0] * c3)
+     * col[2] = (col[2] * c0) xor (col[3] * c1) xor (col[0] * c2) xor (col[1] * c3)
+     * col[3] = (col[3] * c0) xor (col[0] * c1) xor (col[1] * c2) xor (col[2] * c3)
      *
-     * With '*' meaning the multiplication in the GF2 field as described by the AES standard
+     * With '*' meaning the multiplication in the GF2 field as described by the AES standard.
+     * For mix columns the coefficients are [0x02, 0x03, 0x01, 0x01] and for its inverse
+     * they are [0x0e, 0x0b, 0x0d, 0x09].
      */
-    fn inv_mix_column_8(bytes: &mut [u8; 16], offset: usize) {
-        let mut column_bytes = [
-            bytes[offset],
-            bytes[offset + 1],
-            bytes[offset + 2],
-            bytes[offset + 3],
+    fn mix_column_with(bytes: &mut [u8; 16], offset: usize, coefficients: &[Gf256; 4]) {
+        let mut column = [0; 4];
+        column.copy_from_slice(&bytes[offset..offset + 4]);
+        Self::mix_column_bytes(&mut column, coefficients);
+        bytes[offset..offset + 4].copy_from_slice(&column);
+    }
+
+    /**
+     * The products go through Gf256::ct_mul and not through the tables of the
+     * Mul operator, so that the state of the cipher is never used as an index
+     * nor in a branch.
+     */
+    fn mix_column_bytes(column: &mut [u8; 4], coefficients: &[Gf256; 4]) {
+        let column_bytes = [
+            Gf256(column[0]),
+            Gf256(column[1]),
+            Gf256(column[2]),
+            Gf256(column[3]),
         ];
-        for index in 0..4 {
-            bytes[offset + index] = Math::multiplication_by_0e(column_bytes[0])
-                ^ Math::multiplication_by_0b(column_bytes[1])
-                ^ Math::multiplication_by_0d(column_bytes[2])
-                ^ Math::multiplication_by_09(column_bytes[3]);
-            let temp = column_bytes[0];
-            column_bytes[0] = column_bytes[1];
-            column_bytes[1] = column_bytes[2];
-            column_bytes[2] = column_bytes[3];
-            column_bytes[3] = temp;
+        for (row, byte) in column.iter_mut().enumerate() {
+            let mut value = Gf256(0);
+            for (index, coefficient) in coefficients.iter().enumerate() {
+                value += coefficient.ct_mul(column_bytes[(row + index) % 4]);
+            }
+            *byte = value.0;
         }
     }
 
@@ -193,10 +180,10 @@ impl AesMatrix {
     pub fn add_round_key_8(bytes: &mut [u8; 16], key_words: &[u32]) {
         for i in (0..16).step_by(4) {
             let key_bytes = key_words[i / 4].to_be_bytes();
-            bytes[i] = bytes[i] ^ key_bytes[0];
-            bytes[i + 1] = bytes[i + 1] ^ key_bytes[1];
-            bytes[i + 2] = bytes[i + 2] ^ key_bytes[2];
-            bytes[i + 3] = bytes[i + 3] ^ key_bytes[3];
+            bytes[i] ^= key_bytes[0];
+            bytes[i + 1] ^= key_bytes[1];
+            bytes[i + 2] ^= key_bytes[2];
+            bytes[i + 3] ^= key_bytes[3];
         }
     }
 
@@ -206,25 +193,8 @@ impl AesMatrix {
      * array of bites I gave it its own function
      */
     pub fn inv_mix_column_a(columns: &mut [u32; 4], index: usize) {
-        let mut column_bytes: [u8; 4] = [
-            ((columns[index] & 0xff000000) >> 24) as u8,
-            ((columns[index] & 0x00ff0000) >> 16) as u8,
-            ((columns[index] & 0x0000ff00) >> 8) as u8,
-            (columns[index] & 0x000000ff) as u8,
-        ];
-        let mut new_column: u32 = 0;
-        for index in 0..4 {
-            let curr_byte = Math::multiplication_by_0e(column_bytes[0])
-                ^ Math::multiplication_by_0b(column_bytes[1])
-                ^ Math::multiplication_by_0d(column_bytes[2])
-                ^ Math::multiplication_by_09(column_bytes[3]);
-            let temp = column_bytes[0];
-            column_bytes[0] = column_bytes[1];
-            column_bytes[1] = column_bytes[2];
-            column_bytes[2] = column_bytes[3];
-            column_bytes[3] = temp;
-            new_column = new_column | ((curr_byte as u32) << ((3 - index) * 8));
-        }
-        columns[index] = new_column;
+        let mut column_bytes = columns[index].to_be_bytes();
+        Self::mix_column_bytes(&mut column_bytes, &INV_MIX_COLUMNS_COEFFICIENTS);
+        columns[index] = u32::from_be_bytes(column_bytes);
     }
 }
@@ -0,0 +1,76 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod analysis_test {
+    use crate::analysis::{self, Properties};
+    use crate::math::{SBoxParams, INV_S_BOX, S_BOX};
+
+    #[test]
+    fn it_analyzes_aes_s_box() {
+        let expected = Properties {
+            differential_uniformity: 4,
+            nonlinearity: 112,
+            algebraic_degree: 7,
+            fixed_points: 0,
+        };
+        assert_eq!(analysis::analyze(&S_BOX), expected);
+        assert_eq!(analysis::analyze(&INV_S_BOX), expected);
+        assert_eq!(analysis::opposite_fixed_points(&S_BOX).count(), 0);
+    }
+
+    #[test]
+    fn it_analyzes_identity() {
+        let mut identity = [0u8; 256];
+        for (i, byte) in identity.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let properties = analysis::analyze(&identity);
+        assert_eq!(properties.differential_uniformity, 256);
+        assert_eq!(properties.nonlinearity, 0);
+        assert_eq!(properties.algebraic_degree, 1);
+        assert_eq!(properties.fixed_points, 256);
+        assert_eq!(analysis::fixed_points(&identity).nth(7), Some(7));
+    }
+
+    #[test]
+    fn it_computes_difference_distribution_table() {
+        let mut table = [[0; 256]; 256];
+        analysis::difference_distribution_table(&S_BOX, &mut table);
+        assert_eq!(table[0][0], 256);
+        for row in table.iter() {
+            assert_eq!(row.iter().sum::<u16>(), 256);
+        }
+        // every non zero input difference of the inversion has one output
+        // difference reached 4 times and 126 reached twice
+        for row in table.iter().skip(1) {
+            assert_eq!(row.iter().filter(|&&count| count == 4).count(), 1);
+            assert_eq!(row.iter().filter(|&&count| count == 2).count(), 126);
+        }
+    }
+
+    #[test]
+    fn it_computes_linear_approximation_table() {
+        let mut table = [[0; 256]; 256];
+        analysis::linear_approximation_table(&S_BOX, &mut table);
+        assert_eq!(table[0][0], 128);
+        for (output_mask, row) in table.iter().enumerate().skip(1) {
+            assert_eq!(row[0], 0, "output mask {}", output_mask);
+            // Parseval: the sum of the squares of the walsh coefficients is 2^16
+            let squares: i32 = row.iter().map(|&value| 4 * (value as i32).pow(2)).sum();
+            assert_eq!(squares, 1 << 16);
+        }
+    }
+
+    #[test]
+    fn it_analyzes_custom_s_box() {
+        let s_box = SBoxParams {
+            polynomial: 0x11d,
+            affine_constant: 0x00,
+        }
+        .s_box();
+        let properties = analysis::analyze(&s_box);
+        assert_eq!(properties.differential_uniformity, 4);
+        assert_eq!(properties.nonlinearity, 112);
+        assert_eq!(properties.algebraic_degree, 7);
+        assert!(analysis::fixed_points(&s_box).any(|x| x == 0));
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+ * Cryptanalytic properties of 8-bit secure boxes, like S_BOX or the ones built
+ * through SBoxParams for the hazmat ciphers.
+ *
+ * The full difference distribution and linear approximation tables take 128KB
+ * each, more than the stack of a small target, so they are filled in a table
+ * given by the caller, which can be static or on the heap. The other functions
+ * work one row at a time and only need the stack space of a row.
+ */
+
+mod analysis_test;
+
+/**
+ * Summary of the properties computed by this module. For the AES secure box they are
+ * differential uniformity 4, nonlinearity 112, algebraic degree 7 and no fixed points.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Properties {
+    pub differential_uniformity: u16,
+    pub nonlinearity: u16,
+    pub algebraic_degree: u32,
+    pub fixed_points: usize,
+}
+
+/**
+ * Computes all of the properties of the secure box
+ */
+pub fn analyze(s_box: &[u8; 256]) -> Properties {
+    Properties {
+        differential_uniformity: differential_uniformity(s_box),
+        nonlinearity: nonlinearity(s_box),
+        algebraic_degree: algebraic_degree(s_box),
+        fixed_points: fixed_points(s_box).count(),
+    }
+}
+
+/**
+ * Row input_difference of the difference distribution table: the entry in position
+ * output_difference is the number of x such that
+ * S(x) xor S(x xor input_difference) = output_difference
+ */
+pub fn difference_distribution_row(s_box: &[u8; 256], input_difference: u8) -> [u16; 256] {
+    let mut row = [0; 256];
+    for x in 0..256 {
+        let output_difference = s_box[x] ^ s_box[x ^ input_difference as usize];
+        row[output_difference as usize] += 1;
+    }
+    row
+}
+
+/**
+ * Fills table with the whole difference distribution table, indexed by input
+ * and then output difference
+ */
+pub fn difference_distribution_table(s_box: &[u8; 256], table: &mut [[u16; 256]; 256]) {
+    for (input_difference, row) in table.iter_mut().enumerate() {
+        *row = difference_distribution_row(s_box, input_difference as u8);
+    }
+}
+
+/**
+ * The highest entry of the difference distribution table outside of the first row,
+ * which is always 256 in position 0. The lower it is the harder differential
+ * attacks are, 2 is the minimum and is reached by no 8-bit permutation known so far.
+ */
+pub fn differential_uniformity(s_box: &[u8; 256]) -> u16 {
+    (1..=255u8)
+        .map(|input_difference| {
+            let row = difference_distribution_row(s_box, input_difference);
+            *row.iter().max().unwrap()
+        })
+        .max()
+        .unwrap()
+}
+
+/**
+ * Row output_mask of the linear approximation table: the entry in position
+ * input_mask is the number of x for which input_mask . x = output_mask . S(x),
+ * minus 128, with '.' being the scalar product of the bits.
+ * Note that the table is indexed by output mask first, since the nonlinearity
+ * only looks at the rows with a non zero output mask.
+ */
+pub fn linear_approximation_row(s_box: &[u8; 256], output_mask: u8) -> [i16; 256] {
+    // the walsh transform of the component function output_mask . S(x), computed in place
+    let mut row = [0i16; 256];
+    for (x, value) in row.iter_mut().enumerate() {
+        *value = if parity(output_mask & s_box[x]) {
+            -1
+        } else {
+            1
+        };
+    }
+    let mut length = 1;
+    while length < 256 {
+        for start in (0..256).step_by(2 * length) {
+            for i in start..start + length {
+                let (a, b) = (row[i], row[i + length]);
+                row[i] = a + b;
+                row[i + length] = a - b;
+            }
+        }
+        length *= 2;
+    }
+    // the transform counts agreements minus disagreements, which is twice the bias
+    for value in row.iter_mut() {
+        *value /= 2;
+    }
+    row
+}
+
+/**
+ * Fills table with the whole linear approximation table, indexed by output mask
+ * and then input mask
+ */
+pub fn linear_approximation_table(s_box: &[u8; 256], table: &mut [[i16; 256]; 256]) {
+    for (output_mask, row) in table.iter_mut().enumerate() {
+        *row = linear_approximation_row(s_box, output_mask as u8);
+    }
+}
+
+/**
+ * Distance of the closest component function from an affine function, that is
+ * 128 minus the highest absolute value in the linear approximation table with
+ * a non zero output mask. The higher it is the harder linear attacks are.
+ */
+pub fn nonlinearity(s_box: &[u8; 256]) -> u16 {
+    let highest_bias = (1..=255u8)
+        .map(|output_mask| {
+            let row = linear_approximation_row(s_box, output_mask);
+            row.iter().map(|value| value.unsigned_abs()).max().unwrap()
+        })
+        .max()
+        .unwrap();
+    128 - highest_bias
+}
+
+/**
+ * The highest degree of the algebraic normal form of the 8 coordinate functions.
+ * The normal form is computed with the binary moebius transform, the degree of
+ * a monomial is the number of bits set in its index.
+ */
+pub fn algebraic_degree(s_box: &[u8; 256]) -> u32 {
+    let mut degree = 0;
+    for bit in 0..8 {
+        let mut normal_form = [0u8; 256];
+        for (x, coefficient) in normal_form.iter_mut().enumerate() {
+            *coefficient = (s_box[x] >> bit) & 1;
+        }
+        let mut length = 1;
+        while length < 256 {
+            for start in (0..256).step_by(2 * length) {
+                for i in start..start + length {
+                    normal_form[i + length] ^= normal_form[i];
+                }
+            }
+            length *= 2;
+        }
+        for (monomial, coefficient) in normal_form.iter().enumerate() {
+            if *coefficient == 1 {
+                degree = degree.max(monomial.count_ones());
+            }
+        }
+    }
+    degree
+}
+
+/**
+ * The bytes that the secure box leaves unchanged
+ */
+pub fn fixed_points(s_box: &[u8; 256]) -> impl Iterator<Item = u8> + '_ {
+    (0..=255u8).filter(move |x| s_box[*x as usize] == *x)
+}
+
+/**
+ * The bytes that the secure box maps to their complement
+ */
+pub fn opposite_fixed_points(s_box: &[u8; 256]) -> impl Iterator<Item = u8> + '_ {
+    (0..=255u8).filter(move |x| s_box[*x as usize] == !*x)
+}
+
+fn parity(byte: u8) -> bool {
+    byte.count_ones() % 2 == 1
+}
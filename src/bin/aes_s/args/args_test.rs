@@ -0,0 +1,97 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod args_test {
+    use crate::args::{parse, Command, Files, KeySource, Mode};
+    use aes_s::pbkdf2::DEFAULT_ITERATIONS;
+
+    fn parse_line(line: &str) -> Result<Command, String> {
+        parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn it_parses_the_commands() {
+        assert_eq!(
+            parse_line("encrypt --key 00ff --in a.txt"),
+            Ok(Command::Encrypt {
+                mode: Mode::Gcm,
+                key: KeySource::Hex("00ff".to_string()),
+                iterations: DEFAULT_ITERATIONS,
+                files: Files {
+                    input: Some("a.txt".into()),
+                    output: None,
+                },
+            })
+        );
+        assert_eq!(
+            parse_line("decrypt --out b --mode xts --key-file k"),
+            Ok(Command::Decrypt {
+                mode: Mode::Xts,
+                key: KeySource::File("k".into()),
+                files: Files {
+                    input: None,
+                    output: Some("b".into()),
+                },
+            })
+        );
+        assert_eq!(
+            parse_line("keygen --xts --bits 128"),
+            Ok(Command::Keygen {
+                bits: 128,
+                xts: true,
+                output: None,
+            })
+        );
+        assert_eq!(
+            parse_line("unwrap --key 00"),
+            Ok(Command::Unwrap {
+                kek: KeySource::Hex("00".to_string()),
+                files: Files::default(),
+            })
+        );
+        assert_eq!(parse_line("wrap --help"), Ok(Command::Help));
+    }
+
+    #[test]
+    fn it_parses_passwords() {
+        assert_eq!(
+            parse_line("encrypt --password hunter2 --iterations 1000"),
+            Ok(Command::Encrypt {
+                mode: Mode::Gcm,
+                key: KeySource::Password("hunter2".to_string()),
+                iterations: 1000,
+                files: Files::default(),
+            })
+        );
+        assert_eq!(
+            parse_line("decrypt --mode gcm --password-file p"),
+            Ok(Command::Decrypt {
+                mode: Mode::Gcm,
+                key: KeySource::PasswordFile("p".into()),
+                files: Files::default(),
+            })
+        );
+        assert!(parse_line("encrypt --password a --key 00").is_err());
+        assert!(parse_line("encrypt --password a --mode cbc").is_err());
+        assert!(parse_line("encrypt --password a --iterations 0").is_err());
+        assert!(parse_line("encrypt --password a --iterations 999").is_err());
+        assert!(parse_line("encrypt --password a --iterations 10000001").is_err());
+        assert!(parse_line("encrypt --key 00 --iterations 1000").is_err());
+        assert!(parse_line("decrypt --password a --iterations 1000").is_err());
+        assert!(parse_line("wrap --password a").is_err());
+    }
+
+    #[test]
+    fn it_refuses_bad_arguments() {
+        assert!(parse_line("").is_err());
+        assert!(parse_line("shred --key 00").is_err());
+        assert!(parse_line("encrypt").is_err());
+        assert!(parse_line("encrypt --key").is_err());
+        assert!(parse_line("encrypt --key 00 --key-file k").is_err());
+        assert!(parse_line("encrypt --key 00 --mode ecb").is_err());
+        assert!(parse_line("encrypt --key 00 --bits 128").is_err());
+        assert!(parse_line("keygen --bits 512").is_err());
+        assert!(parse_line("keygen --key 00").is_err());
+        assert!(parse_line("wrap --key 00 --mode cbc").is_err());
+        assert!(parse_line("decrypt --key 00 --verbose 1").is_err());
+    }
+}
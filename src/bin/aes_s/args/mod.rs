@@ -0,0 +1,255 @@
+/*!
+ * Parsing of the command line, without any dependency: a subcommand followed
+ * by options, each with its value as the next argument.
+ */
+use aes_s::container::{MAX_PBKDF2_ITERATIONS, MIN_PBKDF2_ITERATIONS};
+use aes_s::pbkdf2::DEFAULT_ITERATIONS;
+use std::path::PathBuf;
+
+mod args_test;
+
+pub const USAGE: &str = "\
+usage:
+    aes_s encrypt [--mode MODE] KEY [--iterations N] [--in FILE] [--out FILE]
+    aes_s decrypt [--mode MODE] KEY [--in FILE] [--out FILE]
+    aes_s keygen [--bits 128|192|256] [--xts] [--out FILE]
+    aes_s wrap KEY [--in FILE] [--out FILE]
+    aes_s unwrap KEY [--in FILE] [--out FILE]
+
+MODE is gcm (the default), cbc, ctr or xts.
+KEY is --key HEX or --key-file FILE, with the key written in hexadecimal as
+keygen does; for wrap and unwrap it is the key encryption key, and the key
+to wrap or unwrap is read in hexadecimal from the input.
+With the gcm mode KEY can also be --password TEXT or --password-file FILE,
+the first line of the file: the key is derived with PBKDF2-HMAC-SHA256 and
+--iterations N of it, 1000 to 10000000, 600000 by default.
+Without --in and --out the input is stdin and the output stdout.
+
+exit codes: 0 success, 1 usage error, 2 I/O error, 3 authentication failed,
+4 invalid key or input";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /**
+     * The container format of aes_s, STREAM over GCM with a key derived for
+     * every file
+     */
+    Gcm,
+    /**
+     * A random IV followed by the ciphertext with PKCS #7 padding
+     */
+    Cbc,
+    /**
+     * A random IV followed by the ciphertext
+     */
+    Ctr,
+    /**
+     * Data units of XTS_UNIT_LEN bytes numbered from 0, with a double key
+     */
+    Xts,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySource {
+    Hex(String),
+    File(PathBuf),
+    Password(String),
+    PasswordFile(PathBuf),
+}
+
+impl KeySource {
+    pub fn is_password(&self) -> bool {
+        matches!(self, KeySource::Password(_) | KeySource::PasswordFile(_))
+    }
+}
+
+/**
+ * Where the data comes from and goes to, None for stdin and stdout
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Files {
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Encrypt {
+        mode: Mode,
+        key: KeySource,
+        /**
+         * PBKDF2 iterations, only with a password
+         */
+        iterations: u32,
+        files: Files,
+    },
+    Decrypt {
+        mode: Mode,
+        key: KeySource,
+        files: Files,
+    },
+    Keygen {
+        bits: usize,
+        xts: bool,
+        output: Option<PathBuf>,
+    },
+    Wrap {
+        kek: KeySource,
+        files: Files,
+    },
+    Unwrap {
+        kek: KeySource,
+        files: Files,
+    },
+    Help,
+}
+
+/**
+ * The command given by the arguments, without the name of the program.
+ * The error is the message to print before the usage.
+ */
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let name = match args.next() {
+        Some(name) => name,
+        None => return Err("missing command".to_string()),
+    };
+    let mut mode = None;
+    let mut key = None;
+    let mut files = Files::default();
+    let mut bits = None;
+    let mut iterations = None;
+    let mut xts = false;
+    while let Some(option) = args.next() {
+        if option == "--xts" {
+            xts = true;
+            continue;
+        }
+        if option == "--help" || option == "-h" {
+            return Ok(Command::Help);
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(format!("missing value for {}", option)),
+        };
+        let previous = match option.as_str() {
+            "--mode" => mode.replace(parse_mode(&value)?).map(|_| ()),
+            "--key" => key.replace(KeySource::Hex(value)).map(|_| ()),
+            "--key-file" => key.replace(KeySource::File(value.into())).map(|_| ()),
+            "--password" => key.replace(KeySource::Password(value)).map(|_| ()),
+            "--password-file" => key
+                .replace(KeySource::PasswordFile(value.into()))
+                .map(|_| ()),
+            "--iterations" => iterations.replace(parse_iterations(&value)?).map(|_| ()),
+            "--in" => files.input.replace(value.into()).map(|_| ()),
+            "--out" => files.output.replace(value.into()).map(|_| ()),
+            "--bits" => bits.replace(parse_bits(&value)?).map(|_| ()),
+            _ => return Err(format!("unknown option {}", option)),
+        };
+        if previous.is_some() {
+            return Err(format!("{} given more than once", option));
+        }
+    }
+    let command = match name.as_str() {
+        "encrypt" | "decrypt" => {
+            check_unused(&[("--bits", bits.is_some()), ("--xts", xts)])?;
+            let mode = mode.unwrap_or(Mode::Gcm);
+            let key = required_key(key)?;
+            if key.is_password() && mode != Mode::Gcm {
+                return Err("passwords can only be used with the gcm mode".to_string());
+            }
+            if name == "encrypt" {
+                if iterations.is_some() && !key.is_password() {
+                    return Err("--iterations needs a password".to_string());
+                }
+                Command::Encrypt {
+                    mode,
+                    key,
+                    iterations: iterations.unwrap_or(DEFAULT_ITERATIONS),
+                    files,
+                }
+            } else {
+                check_unused(&[("--iterations", iterations.is_some())])?;
+                Command::Decrypt { mode, key, files }
+            }
+        }
+        "keygen" => {
+            check_unused(&[
+                ("--mode", mode.is_some()),
+                ("--key", key.is_some()),
+                ("--iterations", iterations.is_some()),
+                ("--in", files.input.is_some()),
+            ])?;
+            Command::Keygen {
+                bits: bits.unwrap_or(256),
+                xts,
+                output: files.output,
+            }
+        }
+        "wrap" | "unwrap" => {
+            check_unused(&[
+                ("--mode", mode.is_some()),
+                ("--bits", bits.is_some()),
+                ("--iterations", iterations.is_some()),
+                ("--xts", xts),
+            ])?;
+            let kek = required_key(key)?;
+            if kek.is_password() {
+                return Err("the key encryption key can't be a password".to_string());
+            }
+            if name == "wrap" {
+                Command::Wrap { kek, files }
+            } else {
+                Command::Unwrap { kek, files }
+            }
+        }
+        "help" | "--help" | "-h" => Command::Help,
+        _ => return Err(format!("unknown command {}", name)),
+    };
+    Ok(command)
+}
+
+fn parse_mode(value: &str) -> Result<Mode, String> {
+    match value {
+        "gcm" => Ok(Mode::Gcm),
+        "cbc" => Ok(Mode::Cbc),
+        "ctr" => Ok(Mode::Ctr),
+        "xts" => Ok(Mode::Xts),
+        _ => Err(format!("unknown mode {}", value)),
+    }
+}
+
+fn parse_bits(value: &str) -> Result<usize, String> {
+    match value {
+        "128" => Ok(128),
+        "192" => Ok(192),
+        "256" => Ok(256),
+        _ => Err(format!("--bits must be 128, 192 or 256, not {}", value)),
+    }
+}
+
+fn parse_iterations(value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(iterations) if (MIN_PBKDF2_ITERATIONS..=MAX_PBKDF2_ITERATIONS).contains(&iterations) => {
+            Ok(iterations)
+        }
+        _ => Err(format!(
+            "invalid number of iterations {}, expected {} to {}",
+            value, MIN_PBKDF2_ITERATIONS, MAX_PBKDF2_ITERATIONS
+        )),
+    }
+}
+
+fn required_key(key: Option<KeySource>) -> Result<KeySource, String> {
+    key.ok_or_else(|| "missing --key, --key-file, --password or --password-file".to_string())
+}
+
+/**
+ * Refuses the options that the command doesn't take
+ */
+fn check_unused(options: &[(&str, bool)]) -> Result<(), String> {
+    match options.iter().find(|(_, given)| *given) {
+        Some((option, _)) => Err(format!("{} can't be used with this command", option)),
+        None => Ok(()),
+    }
+}
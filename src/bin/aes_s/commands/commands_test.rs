@@ -0,0 +1,127 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod commands_test {
+    use crate::args::Mode;
+    use crate::commands::{
+        decrypt, decrypt_with_password, encrypt, encrypt_with_password, keygen, unwrap, wrap,
+        Failure, XTS_UNIT_LEN,
+    };
+    use crate::hex;
+    use aes_s::error::Error;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    /**
+     * KEY with the last byte changed, so that its halves differ as XTS requires
+     */
+    fn xts_key() -> [u8; 32] {
+        let mut key = KEY;
+        key[31] = 8;
+        key
+    }
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i % 253) as u8).collect()
+    }
+
+    fn round_trip(mode: Mode, key: &[u8], message: &[u8]) -> Result<Vec<u8>, Failure> {
+        let mut ciphertext = Vec::new();
+        encrypt(mode, key, message, &mut ciphertext)?;
+        let mut plaintext = Vec::new();
+        decrypt(mode, key, &ciphertext[..], &mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn it_round_trips_every_mode() {
+        for &length in [
+            0,
+            16,
+            100,
+            XTS_UNIT_LEN,
+            XTS_UNIT_LEN + 15,
+            3 * XTS_UNIT_LEN + 17,
+        ]
+        .iter()
+        {
+            let message = message(length);
+            for &mode in [Mode::Gcm, Mode::Cbc, Mode::Ctr].iter() {
+                assert_eq!(round_trip(mode, &KEY, &message).unwrap(), message);
+                assert_eq!(round_trip(mode, &KEY[..16], &message).unwrap(), message);
+            }
+            assert_eq!(
+                round_trip(Mode::Xts, &xts_key(), &message).unwrap(),
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_xts_length() {
+        let mut ciphertext = Vec::new();
+        encrypt(Mode::Xts, &xts_key(), &message(5000)[..], &mut ciphertext).unwrap();
+        assert_eq!(ciphertext.len(), 5000);
+        let failure =
+            encrypt(Mode::Xts, &xts_key(), &message(10)[..], &mut Vec::new()).unwrap_err();
+        assert_eq!(failure.exit_code(), 4);
+        let failure = encrypt(Mode::Xts, &KEY, &message(100)[..], &mut Vec::new()).unwrap_err();
+        assert!(matches!(failure, Failure::Crypto(Error::WeakKey)));
+    }
+
+    #[test]
+    fn it_has_distinct_exit_codes() {
+        let mut ciphertext = Vec::new();
+        encrypt(Mode::Gcm, &KEY, &message(100)[..], &mut ciphertext).unwrap();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+        let mut plaintext = Vec::new();
+        let failure = decrypt(Mode::Gcm, &KEY, &ciphertext[..], &mut plaintext).unwrap_err();
+        assert_eq!(failure.exit_code(), 3);
+        assert!(plaintext.is_empty());
+        let failure = encrypt(Mode::Cbc, &KEY[..20], &[][..], &mut Vec::new()).unwrap_err();
+        assert_eq!(failure.exit_code(), 4);
+        assert_eq!(
+            Failure::Crypto(Error::Io(std::io::ErrorKind::BrokenPipe)).exit_code(),
+            2
+        );
+        assert_eq!(Failure::Usage(String::new()).exit_code(), 1);
+    }
+
+    #[test]
+    fn it_wraps_generated_keys() {
+        let mut key = Vec::new();
+        keygen(192, false, &mut key).unwrap();
+        assert_eq!(key.len(), 49);
+        let mut wrapped = Vec::new();
+        wrap(&KEY, &key[..], &mut wrapped).unwrap();
+        let mut unwrapped = Vec::new();
+        unwrap(&KEY, &wrapped[..], &mut unwrapped).unwrap();
+        assert_eq!(unwrapped, key);
+
+        let mut bytes = hex::decode(std::str::from_utf8(&wrapped).unwrap()).unwrap();
+        bytes[0] ^= 1;
+        let failure = unwrap(&KEY, hex::encode(&bytes).as_bytes(), &mut Vec::new()).unwrap_err();
+        assert_eq!(failure.exit_code(), 3);
+
+        let mut xts_key = Vec::new();
+        keygen(256, true, &mut xts_key).unwrap();
+        assert_eq!(xts_key.len(), 129);
+        assert!(keygen(192, true, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn it_encrypts_with_passwords() {
+        let message = message(1000);
+        let mut ciphertext = Vec::new();
+        encrypt_with_password(b"hunter2", 1000, &message[..], &mut ciphertext).unwrap();
+        let mut plaintext = Vec::new();
+        decrypt_with_password(b"hunter2", &ciphertext[..], &mut plaintext).unwrap();
+        assert_eq!(plaintext, message);
+
+        let failure = decrypt_with_password(b"hunter3", &ciphertext[..], &mut Vec::new());
+        assert_eq!(failure.unwrap_err().exit_code(), 3);
+        // a key can't open a file made with a password
+        let failure = decrypt(Mode::Gcm, &KEY, &ciphertext[..], &mut Vec::new()).unwrap_err();
+        assert_eq!(failure.exit_code(), 4);
+    }
+}
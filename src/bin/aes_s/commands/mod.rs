@@ -0,0 +1,293 @@
+/*!
+ * The subcommands, working on any reader and writer so that they can be
+ * tested without files.
+ */
+use crate::args::{KeySource, Mode};
+use crate::hex;
+use aes_s::cipher::{Key, KeySize};
+use aes_s::container::{ContainerReader, ContainerWriter, Header, Kdf};
+use aes_s::error::Error;
+use aes_s::io::{self as aes_io, DecryptReader, EncryptWriter};
+use aes_s::keywrap::KeyWrap;
+use aes_s::random;
+use aes_s::xts::Xts;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+mod commands_test;
+
+/**
+ * Length of the data units of XTS. The last one takes what is left, between
+ * 16 and XTS_UNIT_LEN + 15 bytes, so that it is never too short for
+ * ciphertext stealing.
+ */
+pub const XTS_UNIT_LEN: usize = 4096;
+
+/**
+ * Why a command failed, each with its own exit code
+ */
+#[derive(Debug)]
+pub enum Failure {
+    Usage(String),
+    /**
+     * A file that couldn't be opened or created
+     */
+    File(PathBuf, io::Error),
+    Crypto(Error),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage(_) => 1,
+            Failure::File(..) | Failure::Crypto(Error::Io(_)) => 2,
+            Failure::Crypto(Error::AuthenticationFailed) => 3,
+            Failure::Crypto(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Usage(message) => write!(f, "{}", message),
+            Failure::File(path, error) => write!(f, "{}: {}", path.display(), error),
+            Failure::Crypto(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Failure {
+        Failure::Crypto(error)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Failure {
+        Failure::Crypto(error.into())
+    }
+}
+
+/**
+ * The bytes of the key, from the command line or from a file
+ */
+pub fn read_key(source: &KeySource) -> Result<Vec<u8>, Failure> {
+    let text = match source {
+        KeySource::Hex(text) => text.clone(),
+        KeySource::File(path) => read_text(path)?,
+        KeySource::Password(_) | KeySource::PasswordFile(_) => {
+            return Err(Failure::Usage(
+                "a key is needed, not a password".to_string(),
+            ))
+        }
+    };
+    hex::decode(&text).ok_or_else(|| Failure::Usage("the key is not hexadecimal".to_string()))
+}
+
+/**
+ * The bytes of the password, from the command line or from the first line of
+ * a file
+ */
+pub fn read_password(source: &KeySource) -> Result<Vec<u8>, Failure> {
+    match source {
+        KeySource::Password(password) => Ok(password.as_bytes().to_vec()),
+        KeySource::PasswordFile(path) => {
+            let text = read_text(path)?;
+            let line = text.lines().next().unwrap_or("");
+            Ok(line.as_bytes().to_vec())
+        }
+        KeySource::Hex(_) | KeySource::File(_) => Err(Failure::Usage(
+            "a password is needed, not a key".to_string(),
+        )),
+    }
+}
+
+fn read_text(path: &Path) -> Result<String, Failure> {
+    fs::read_to_string(path).map_err(|error| Failure::File(path.to_path_buf(), error))
+}
+
+pub fn encrypt<R: Read, W: Write>(
+    mode: Mode,
+    key: &[u8],
+    mut input: R,
+    output: W,
+) -> Result<(), Failure> {
+    match mode {
+        Mode::Gcm => {
+            let key = Key::from_bytes(key)?;
+            let header = Header::generate(key.size(), Kdf::Kbkdf)?;
+            let mut writer = ContainerWriter::new(output, &key, &header)?;
+            io::copy(&mut input, &mut writer)?;
+            writer.finish()?;
+        }
+        Mode::Cbc | Mode::Ctr => {
+            let key = Key::from_bytes(key)?;
+            let iv = random::generate_iv().map_err(Error::from)?;
+            let mode = if mode == Mode::Cbc {
+                aes_io::Mode::Cbc { iv }
+            } else {
+                aes_io::Mode::Ctr { iv }
+            };
+            let mut output = output;
+            output.write_all(&iv)?;
+            let mut writer = EncryptWriter::new(output, &key, mode)?;
+            io::copy(&mut input, &mut writer)?;
+            writer.finish()?;
+        }
+        Mode::Xts => apply_xts(&Xts::new(key)?, true, input, output)?,
+    }
+    Ok(())
+}
+
+pub fn decrypt<R: Read, W: Write>(
+    mode: Mode,
+    key: &[u8],
+    mut input: R,
+    mut output: W,
+) -> Result<(), Failure> {
+    match mode {
+        Mode::Gcm => {
+            let key = Key::from_bytes(key)?;
+            let mut reader = ContainerReader::new(input, &key)?;
+            io::copy(&mut reader, &mut output)?;
+        }
+        Mode::Cbc | Mode::Ctr => {
+            let key = Key::from_bytes(key)?;
+            let mut iv = [0; 16];
+            if read_full(&mut input, &mut iv)? < 16 {
+                return Err(Error::InvalidLength.into());
+            }
+            let mode = if mode == Mode::Cbc {
+                aes_io::Mode::Cbc { iv }
+            } else {
+                aes_io::Mode::Ctr { iv }
+            };
+            let mut reader = DecryptReader::new(input, &key, mode)?;
+            io::copy(&mut reader, &mut output)?;
+        }
+        Mode::Xts => apply_xts(&Xts::new(key)?, false, input, &mut output)?,
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/**
+ * Encrypts into the container format with an AES-256 key derived from the
+ * password by PBKDF2, with a random salt stored in the header
+ */
+pub fn encrypt_with_password<R: Read, W: Write>(
+    password: &[u8],
+    iterations: u32,
+    mut input: R,
+    output: W,
+) -> Result<(), Failure> {
+    let header = Header::generate(KeySize::Aes256, Kdf::Pbkdf2 { iterations })?;
+    let mut writer = ContainerWriter::with_password(output, password, &header)?;
+    io::copy(&mut input, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn decrypt_with_password<R: Read, W: Write>(
+    password: &[u8],
+    input: R,
+    mut output: W,
+) -> Result<(), Failure> {
+    let mut reader = ContainerReader::with_password(input, password)?;
+    io::copy(&mut reader, &mut output)?;
+    output.flush()?;
+    Ok(())
+}
+
+/**
+ * Writes a random key in hexadecimal, twice as long for XTS
+ */
+pub fn keygen<W: Write>(bits: usize, xts: bool, mut output: W) -> Result<(), Failure> {
+    let mut key = vec![0; bits / 8 * if xts { 2 } else { 1 }];
+    if xts && bits == 192 {
+        return Err(Failure::Usage("XTS keys are 128 or 256 bits".to_string()));
+    }
+    random::fill(&mut key).map_err(Error::from)?;
+    writeln!(output, "{}", hex::encode(&key))?;
+    Ok(())
+}
+
+/**
+ * Wraps the key read in hexadecimal from input with the key encryption key
+ */
+pub fn wrap<R: Read, W: Write>(kek: &[u8], input: R, mut output: W) -> Result<(), Failure> {
+    let key_data = read_hex(input)?;
+    let mut wrapped = vec![0; key_data.len() + 8];
+    KeyWrap::new(&Key::from_bytes(kek)?).wrap(&key_data, &mut wrapped)?;
+    writeln!(output, "{}", hex::encode(&wrapped))?;
+    Ok(())
+}
+
+pub fn unwrap<R: Read, W: Write>(kek: &[u8], input: R, mut output: W) -> Result<(), Failure> {
+    let wrapped = read_hex(input)?;
+    let mut key_data = vec![0; wrapped.len().saturating_sub(8)];
+    KeyWrap::new(&Key::from_bytes(kek)?).unwrap(&wrapped, &mut key_data)?;
+    writeln!(output, "{}", hex::encode(&key_data))?;
+    Ok(())
+}
+
+fn read_hex<R: Read>(mut input: R) -> Result<Vec<u8>, Failure> {
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    hex::decode(&text).ok_or_else(|| Failure::Usage("the input is not hexadecimal".to_string()))
+}
+
+/**
+ * Ciphers or deciphers the input in data units of XTS_UNIT_LEN bytes. One
+ * more block is read past every unit to know whether it is the last one.
+ */
+fn apply_xts<R: Read, W: Write>(
+    xts: &Xts,
+    encrypt: bool,
+    mut input: R,
+    mut output: W,
+) -> Result<(), Error> {
+    let mut buffer = vec![0; XTS_UNIT_LEN + 16];
+    let mut filled = 0;
+    let mut unit = 0;
+    loop {
+        filled += read_full(&mut input, &mut buffer[filled..])?;
+        let length = if filled == buffer.len() {
+            XTS_UNIT_LEN
+        } else {
+            filled
+        };
+        if length == 0 {
+            return Ok(());
+        }
+        let data = &mut buffer[..length];
+        if encrypt {
+            xts.encrypt_data_unit(unit, data)?;
+        } else {
+            xts.decrypt_data_unit(unit, data)?;
+        }
+        output.write_all(data)?;
+        buffer.copy_within(length..filled, 0);
+        filled -= length;
+        unit += 1;
+    }
+}
+
+/**
+ * Reads until buffer is full or the input ends, returns the bytes read
+ */
+fn read_full<R: Read>(input: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(length) => filled += length,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
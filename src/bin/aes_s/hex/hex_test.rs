@@ -0,0 +1,15 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod hex_test {
+    use crate::hex::{decode, encode};
+
+    #[test]
+    fn it_round_trips() {
+        assert_eq!(encode(&[0x00, 0x1f, 0xa0, 0xff]), "001fa0ff");
+        assert_eq!(decode(" 001FA0ff\n"), Some(vec![0x00, 0x1f, 0xa0, 0xff]));
+        assert_eq!(decode(""), Some(vec![]));
+        assert_eq!(decode("abc"), None);
+        assert_eq!(decode("zz"), None);
+        assert_eq!(decode("+1"), None);
+    }
+}
@@ -0,0 +1,25 @@
+/*!
+ * Keys are read and written as hexadecimal text, so that they can be passed
+ * on the command line and stored in text files.
+ */
+mod hex_test;
+
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/**
+ * The bytes of a hexadecimal string, upper or lower case, surrounding
+ * whitespace ignored. None if it has an odd length or a character that is
+ * not a hexadecimal digit.
+ */
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,113 @@
+/*!
+ * The aes_s command line tool: encrypts and decrypts files or streams,
+ * generates keys and wraps them. Run it with help for the usage.
+ */
+use args::{Command, Files, USAGE};
+use commands::Failure;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::process;
+
+mod args;
+mod commands;
+mod hex;
+
+fn main() {
+    let command = match args::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("aes_s: {}\n\n{}", message, USAGE);
+            process::exit(Failure::Usage(message).exit_code());
+        }
+    };
+    if let Err(failure) = run(command) {
+        eprintln!("aes_s: {}", failure);
+        process::exit(failure.exit_code());
+    }
+}
+
+fn run(command: Command) -> Result<(), Failure> {
+    match command {
+        Command::Encrypt {
+            mode,
+            key,
+            iterations,
+            files,
+        } => with_files(&files, |input, output| {
+            if key.is_password() {
+                let password = commands::read_password(&key)?;
+                commands::encrypt_with_password(&password, iterations, input, output)
+            } else {
+                commands::encrypt(mode, &commands::read_key(&key)?, input, output)
+            }
+        }),
+        Command::Decrypt { mode, key, files } => with_files(&files, |input, output| {
+            if key.is_password() {
+                commands::decrypt_with_password(&commands::read_password(&key)?, input, output)
+            } else {
+                commands::decrypt(mode, &commands::read_key(&key)?, input, output)
+            }
+        }),
+        Command::Keygen { bits, xts, output } => {
+            let files = Files {
+                input: None,
+                output,
+            };
+            with_output(&files, |output| commands::keygen(bits, xts, output))
+        }
+        Command::Wrap { kek, files } => with_files(&files, |input, output| {
+            commands::wrap(&commands::read_key(&kek)?, input, output)
+        }),
+        Command::Unwrap { kek, files } => with_files(&files, |input, output| {
+            commands::unwrap(&commands::read_key(&kek)?, input, output)
+        }),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn with_files<F>(files: &Files, command: F) -> Result<(), Failure>
+where
+    F: FnOnce(&mut dyn Read, &mut dyn Write) -> Result<(), Failure>,
+{
+    let mut input: Box<dyn Read> = match &files.input {
+        Some(path) => Box::new(BufReader::new(open(path)?)),
+        None => Box::new(io::stdin()),
+    };
+    with_output(files, |output| command(&mut input, output))
+}
+
+/**
+ * Runs the command on the output, which is flushed at the end. An output file
+ * is removed if the command fails, so that no partial result is left behind.
+ */
+fn with_output<F>(files: &Files, command: F) -> Result<(), Failure>
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), Failure>,
+{
+    match &files.output {
+        Some(path) => {
+            let file = File::create(path).map_err(|error| Failure::File(path.clone(), error))?;
+            let mut output = BufWriter::new(file);
+            let result = command(&mut output).and_then(|_| Ok(output.flush()?));
+            if result.is_err() {
+                drop(output);
+                let _ = fs::remove_file(path);
+            }
+            result
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut output = BufWriter::new(stdout.lock());
+            command(&mut output)?;
+            Ok(output.flush()?)
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<File, Failure> {
+    File::open(path).map_err(|error| Failure::File(path.to_path_buf(), error))
+}
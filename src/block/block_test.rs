@@ -0,0 +1,119 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod block_test {
+    use crate::block::{Block, BlockCipher, BlockDecrypt, BlockEncrypt, KeyInit};
+    use crate::cipher::{Aes128, Aes192, Aes256, Cipher, Key};
+    use crate::cmac::Cmac;
+    use crate::error::Error;
+    use crate::pmac::Pmac;
+    use core::cell::Cell;
+
+    const PLAIN: Block = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ];
+
+    fn key_bytes() -> [u8; 32] {
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        key
+    }
+
+    fn assert_round_trip<C: BlockEncrypt + BlockDecrypt>(cipher: &C, expected: u128) {
+        let mut block = PLAIN;
+        cipher.encrypt_block(&mut block);
+        assert_eq!(block, expected.to_be_bytes());
+        cipher.decrypt_block(&mut block);
+        assert_eq!(block, PLAIN);
+    }
+
+    /**
+     * The examples of appendix C of the AES standard
+     */
+    #[test]
+    fn it_ciphers_through_the_traits() {
+        let key = key_bytes();
+        assert_round_trip(
+            &Aes128::new_from_slice(&key[..16]).unwrap(),
+            0x69c4e0d86a7b0430d8cdb78070b4c55a,
+        );
+        assert_round_trip(
+            &Aes192::new_from_slice(&key[..24]).unwrap(),
+            0xdda97ca4864cdfe06eaf70a0ec0d7191,
+        );
+        assert_round_trip(
+            &Aes256::new_from_slice(&key).unwrap(),
+            0x8ea2b7ca516745bfeafc49904b496089,
+        );
+        assert_round_trip(
+            &Cipher::new(&key[..16]).unwrap(),
+            0x69c4e0d86a7b0430d8cdb78070b4c55a,
+        );
+        assert_eq!(Aes192::KEY_SIZE, 24);
+        assert_eq!(Aes256::BLOCK_SIZE, 16);
+    }
+
+    #[test]
+    fn it_refuses_keys_of_another_size() {
+        let key = key_bytes();
+        assert_eq!(
+            Aes128::new_from_slice(&key[..24]).err(),
+            Some(Error::InvalidKeyLength(24))
+        );
+        assert_eq!(
+            Aes256::new_from_slice(&key[..16]).err(),
+            Some(Error::InvalidKeyLength(16))
+        );
+    }
+
+    #[test]
+    fn it_ciphers_many_blocks() {
+        let aes = Aes128::new(&[0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+        let mut blocks = [PLAIN, PLAIN, [0; 16]];
+        aes.encrypt_blocks(&mut blocks);
+        assert_eq!(blocks[0], blocks[1]);
+        assert_ne!(blocks[0], blocks[2]);
+        aes.decrypt_blocks(&mut blocks);
+        assert_eq!(blocks, [PLAIN, PLAIN, [0; 16]]);
+    }
+
+    /**
+     * A cipher that does nothing but count how many times it is called
+     */
+    struct CountingCipher {
+        calls: Cell<usize>,
+    }
+
+    impl BlockCipher for CountingCipher {}
+
+    impl BlockEncrypt for CountingCipher {
+        fn encrypt_block(&self, block: &mut Block) {
+            self.calls.set(self.calls.get() + 1);
+            block[0] ^= 1;
+        }
+    }
+
+    #[test]
+    fn it_plugs_other_ciphers_into_the_modes() {
+        let counter = CountingCipher {
+            calls: Cell::new(0),
+        };
+        let mut cmac = Cmac::from_encryptor(&counter);
+        assert_eq!(counter.calls.get(), 1);
+        cmac.mac(&[0; 40]);
+        assert_eq!(counter.calls.get(), 4);
+        // a shared cipher gives the same tags of an owned one
+        let key = Key::Aes256([0, 1, 2, 3, 4, 5, 6, 7]);
+        let aes = Aes256::new(&[0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(
+            Pmac::from_encryptor(&aes).mac(&[7; 50]),
+            Pmac::new(&key).mac(&[7; 50])
+        );
+        assert_eq!(
+            Cmac::from_encryptor(&aes).mac(&[7; 50]),
+            Cmac::new(&key).mac(&[7; 50])
+        );
+    }
+}
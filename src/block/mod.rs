@@ -0,0 +1,94 @@
+/*!
+ * Traits for block ciphers, so that the modes of operation and the MACs of
+ * the crate don't need to know which cipher they are using.
+ *
+ * The modes take any type that implements BlockEncrypt (and BlockDecrypt
+ * when they need to decipher), by default Encryptor. A mock, a cipher with
+ * a different implementation or a hardware backend can then be plugged into
+ * them without touching their code.
+ */
+use crate::error::Error;
+
+mod block_test;
+
+/**
+ * Length in bytes of the blocks of AES, the only length the modes of the crate
+ * are defined for: the doublings of CMAC and PMAC and the multiplication
+ * of GHASH all work in GF(2^128).
+ */
+pub const BLOCK_SIZE: usize = 16;
+
+/**
+ * A block as taken by the traits
+ */
+pub type Block = [u8; BLOCK_SIZE];
+
+/**
+ * Anything that works on blocks of BLOCK_SIZE bytes
+ */
+pub trait BlockCipher {
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+}
+
+/**
+ * A cipher whose key length is fixed by its type
+ */
+pub trait KeyInit: Sized {
+    /**
+     * Length of the key in bytes
+     */
+    const KEY_SIZE: usize;
+
+    /**
+     * Initializes the cipher with a key of exactly KEY_SIZE bytes, otherwise
+     * the result is Error::InvalidKeyLength
+     */
+    fn new_from_slice(key: &[u8]) -> Result<Self, Error>;
+}
+
+/**
+ * The forward direction of a block cipher
+ */
+pub trait BlockEncrypt: BlockCipher {
+    fn encrypt_block(&self, block: &mut Block);
+
+    /**
+     * Ciphers each block on its own, as ECB does
+     */
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks.iter_mut() {
+            self.encrypt_block(block);
+        }
+    }
+}
+
+/**
+ * The inverse direction of a block cipher
+ */
+pub trait BlockDecrypt: BlockCipher {
+    fn decrypt_block(&self, block: &mut Block);
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks.iter_mut() {
+            self.decrypt_block(block);
+        }
+    }
+}
+
+impl<C: BlockCipher> BlockCipher for &C {}
+
+/**
+ * A borrowed cipher works as the cipher itself, so that one cipher can be
+ * shared by several modes
+ */
+impl<C: BlockEncrypt> BlockEncrypt for &C {
+    fn encrypt_block(&self, block: &mut Block) {
+        (**self).encrypt_block(block);
+    }
+}
+
+impl<C: BlockDecrypt> BlockDecrypt for &C {
+    fn decrypt_block(&self, block: &mut Block) {
+        (**self).decrypt_block(block);
+    }
+}
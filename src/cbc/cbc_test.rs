@@ -0,0 +1,104 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod cbc_test {
+    use crate::cbc::{pad_block, unpad_block, Cbc};
+    use crate::cipher::{Decryptor, Encryptor, Key};
+    use crate::error::Error;
+
+    const KEY: Key = Key::Aes128([0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c]);
+
+    fn iv() -> [u8; 16] {
+        let mut iv = [0; 16];
+        for (i, byte) in iv.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        iv
+    }
+
+    /**
+     * F.2.1 and F.2.2 of SP 800-38A
+     */
+    #[test]
+    fn it_matches_sp_800_38a() {
+        let plain = [
+            0x6bc1bee22e409f96e93d7e117393172a_u128,
+            0xae2d8a571e03ac9c9eb76fac45af8e51,
+            0x30c81c46a35ce411e5fbc1191a0a52ef,
+            0xf69f2445df4f9b17ad2b417be66c3710,
+        ];
+        let cipher = [
+            0x7649abac8119b246cee98e9b12e9197d_u128,
+            0x5086cb9b507219ee95db113a917678b2,
+            0x73bed6b8e3c1743b7116e69e22229516,
+            0x3ff1caa1681fac09120eca307586e1a7,
+        ];
+        let mut data = [0; 64];
+        for (chunk, block) in data.chunks_mut(16).zip(plain.iter()) {
+            chunk.copy_from_slice(&block.to_be_bytes());
+        }
+        let mut cbc = Cbc::from_cipher(Encryptor::from_key(&KEY), &iv());
+        cbc.encrypt_blocks(&mut data[..16]).unwrap();
+        cbc.encrypt_blocks(&mut data[16..]).unwrap();
+        for (chunk, block) in data.chunks(16).zip(cipher.iter()) {
+            assert_eq!(chunk, block.to_be_bytes());
+        }
+        let mut cbc = Cbc::from_cipher(Decryptor::from_key(&KEY), &iv());
+        cbc.decrypt_blocks(&mut data[..32]).unwrap();
+        cbc.decrypt_blocks(&mut data[32..]).unwrap();
+        for (chunk, block) in data.chunks(16).zip(plain.iter()) {
+            assert_eq!(chunk, block.to_be_bytes());
+        }
+        assert_eq!(
+            cbc.decrypt_blocks(&mut data[..15]),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn it_round_trips_padded_messages() {
+        let mut message = [0; 48];
+        for (i, byte) in message.iter_mut().enumerate() {
+            *byte = (i * 13) as u8;
+        }
+        for length in 0..=32 {
+            let mut buffer = message;
+            let ciphertext_length = Cbc::new(&KEY, &iv())
+                .encrypt_padded(&mut buffer, length)
+                .unwrap();
+            assert_eq!(ciphertext_length, (length / 16 + 1) * 16);
+            let plaintext_length = Cbc::new(&KEY, &iv())
+                .decrypt_padded(&mut buffer[..ciphertext_length])
+                .unwrap();
+            assert_eq!(buffer[..plaintext_length], message[..length]);
+        }
+        assert_eq!(
+            Cbc::new(&KEY, &iv()).encrypt_padded(&mut [0; 16], 16),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            Cbc::new(&KEY, &iv()).decrypt_padded(&mut []),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn it_checks_the_padding() {
+        assert_eq!(pad_block(&[1, 2, 3]).unwrap()[3..], [13; 13]);
+        assert_eq!(pad_block(&[]), Ok([16; 16]));
+        assert_eq!(pad_block(&[0; 16]), Err(Error::InvalidLength));
+        assert_eq!(unpad_block(&[16; 16]), Ok(0));
+        let mut block = [7; 16];
+        block[15] = 1;
+        assert_eq!(unpad_block(&block), Ok(15));
+        block[15] = 0;
+        assert_eq!(unpad_block(&block), Err(Error::InvalidPadding));
+        block[15] = 17;
+        assert_eq!(unpad_block(&block), Err(Error::InvalidPadding));
+        let mut block = [3; 16];
+        block[13] = 2;
+        assert_eq!(unpad_block(&block), Err(Error::InvalidPadding));
+        block[12] = 2;
+        block[13] = 3;
+        assert_eq!(unpad_block(&block), Ok(13));
+    }
+}
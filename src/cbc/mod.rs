@@ -0,0 +1,147 @@
+/*!
+ * CBC mode as described in NIST SP 800-38A, with the PKCS #7 padding of
+ * RFC 5652 for messages that are not made of whole blocks.
+ *
+ * Each block is xored with the previous ciphertext block (the IV for the
+ * first one) before being ciphered. The IV must be unpredictable, see
+ * random::generate_iv. CBC doesn't authenticate anything: a tampered
+ * ciphertext decrypts to garbage, or fails with Error::InvalidPadding.
+ */
+use crate::block::{BlockDecrypt, BlockEncrypt};
+use crate::cipher::{Cipher, Key};
+use crate::error::Error;
+
+mod cbc_test;
+
+/**
+ * The state of a CBC encryption or decryption: the cipher and the last
+ * ciphertext block. Encrypting needs a cipher that implements BlockEncrypt,
+ * decrypting one that implements BlockDecrypt.
+ */
+pub struct Cbc<C = Cipher> {
+    cipher: C,
+    chaining_value: [u8; 16],
+}
+
+impl Cbc {
+    pub fn new(key: &Key, iv: &[u8; 16]) -> Cbc {
+        Self::from_cipher(Cipher::from_key(key), iv)
+    }
+}
+
+impl<C> Cbc<C> {
+    pub fn from_cipher(cipher: C, iv: &[u8; 16]) -> Cbc<C> {
+        Cbc {
+            cipher,
+            chaining_value: *iv,
+        }
+    }
+}
+
+impl<C: BlockEncrypt> Cbc<C> {
+    /**
+     * Encrypts whole blocks in place, the next call continues the chain.
+     * Fails with Error::InvalidLength if data is not made of whole blocks.
+     */
+    pub fn encrypt_blocks(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        if !data.len().is_multiple_of(16) {
+            return Err(Error::InvalidLength);
+        }
+        for chunk in data.chunks_mut(16) {
+            for (byte, previous) in self.chaining_value.iter_mut().zip(chunk.iter()) {
+                *byte ^= previous;
+            }
+            self.cipher.encrypt_block(&mut self.chaining_value);
+            chunk.copy_from_slice(&self.chaining_value);
+        }
+        Ok(())
+    }
+
+    /**
+     * Pads and encrypts the first length bytes of buffer in place and returns
+     * the length of the ciphertext. The buffer must have room for the padding,
+     * up to a whole block more than the message, otherwise the result is
+     * Error::InvalidLength.
+     */
+    pub fn encrypt_padded(&mut self, buffer: &mut [u8], length: usize) -> Result<usize, Error> {
+        let padded_length = (length / 16 + 1) * 16;
+        if buffer.len() < padded_length {
+            return Err(Error::InvalidLength);
+        }
+        let last_block = pad_block(&buffer[length - length % 16..length])?;
+        buffer[padded_length - 16..padded_length].copy_from_slice(&last_block);
+        self.encrypt_blocks(&mut buffer[..padded_length])?;
+        Ok(padded_length)
+    }
+}
+
+impl<C: BlockDecrypt> Cbc<C> {
+    /**
+     * Decrypts whole blocks in place, the next call continues the chain.
+     * Fails with Error::InvalidLength if data is not made of whole blocks.
+     */
+    pub fn decrypt_blocks(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        if !data.len().is_multiple_of(16) {
+            return Err(Error::InvalidLength);
+        }
+        for chunk in data.chunks_mut(16) {
+            let mut block = [0; 16];
+            block.copy_from_slice(chunk);
+            let ciphertext = block;
+            self.cipher.decrypt_block(&mut block);
+            for (byte, previous) in block.iter_mut().zip(self.chaining_value.iter()) {
+                *byte ^= previous;
+            }
+            chunk.copy_from_slice(&block);
+            self.chaining_value = ciphertext;
+        }
+        Ok(())
+    }
+
+    /**
+     * Decrypts a whole padded message in place and returns the length of the
+     * plaintext, which is at the start of buffer
+     */
+    pub fn decrypt_padded(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        self.decrypt_blocks(buffer)?;
+        let mut last_block = [0; 16];
+        last_block.copy_from_slice(&buffer[buffer.len() - 16..]);
+        Ok(buffer.len() - 16 + unpad_block(&last_block)?)
+    }
+}
+
+/**
+ * The last block of a padded message, made of the last bytes of the message,
+ * less than a block, followed by as many bytes as needed, each holding their
+ * number. A message made of whole blocks gets a whole block of padding.
+ */
+pub fn pad_block(last_bytes: &[u8]) -> Result<[u8; 16], Error> {
+    if last_bytes.len() >= 16 {
+        return Err(Error::InvalidLength);
+    }
+    let mut block = [(16 - last_bytes.len()) as u8; 16];
+    block[..last_bytes.len()].copy_from_slice(last_bytes);
+    Ok(block)
+}
+
+/**
+ * The number of bytes of the message in the last decrypted block. Every byte
+ * of the block is checked whatever the padding is, so that the time taken
+ * doesn't tell where the padding is wrong.
+ */
+pub fn unpad_block(block: &[u8; 16]) -> Result<usize, Error> {
+    let padding = block[15];
+    let mut invalid = (padding.wrapping_sub(1) >= 16) as u8;
+    for (i, byte) in block.iter().enumerate() {
+        let in_padding = ((15 - i) < padding as usize) as u8;
+        invalid |= in_padding & (*byte != padding) as u8;
+    }
+    if invalid == 0 {
+        Ok(16 - padding as usize)
+    } else {
+        Err(Error::InvalidPadding)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::block::{Block, BlockCipher, BlockDecrypt, BlockEncrypt, KeyInit};
+use crate::cipher::{
+    cipher_rounds, equivalent_inverse_cipher_rounds, straight_inverse_cipher_rounds,
+};
+use crate::error::Error;
+use crate::key_manager::{InvKeyManager, KeyManager};
+use crate::math::{INV_S_BOX, S_BOX};
+use crate::trace::{Trace, TraceKind};
+
+/**
+ * AES with a key of NK words, fixed by the type: unlike a Cipher, an Aes128
+ * can't be built with the wrong key by mistake. Only the three lengths of the
+ * standard are allowed, any other NK fails to compile when the cipher is built.
+ *
+ * The number of rounds and the length of the expanded key are constants, so
+ * the compiler is free to unroll the rounds. The length of an array can't be
+ * computed from NK yet, so the number of words of the expanded key is a second
+ * parameter, WORDS, which must be 4 * (NK + 7): the aliases below give the
+ * right one, any other fails to compile like a wrong NK.
+ */
+pub struct Aes<const NK: usize, const WORDS: usize> {
+    key: [u32; WORDS],
+    inv_key: [u32; WORDS],
+}
+
+pub type Aes128 = Aes<4, 44>;
+pub type Aes192 = Aes<6, 52>;
+pub type Aes256 = Aes<8, 60>;
+
+impl<const NK: usize, const WORDS: usize> Aes<NK, WORDS> {
+    /**
+     * Number of rounds, the nr of the AES standard
+     */
+    pub const ROUNDS: usize = NK + 6;
+
+    /**
+     * Number of words of the expanded key: 4 for each round plus the initial one
+     */
+    pub const EXPANDED_KEY_WORDS: usize = WORDS;
+
+    const VALID_KEY_SIZE: () = {
+        assert!(
+            NK == 4 || NK == 6 || NK == 8,
+            "AES keys are 4, 6 or 8 words long"
+        );
+        assert!(
+            WORDS == 4 * (NK + 7),
+            "the expanded key of AES is 4 * (NK + 7) words long"
+        );
+    };
+
+    pub fn new(key: &[u32; NK]) -> Aes<NK, WORDS> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_KEY_SIZE;
+        let key_manager = match NK {
+            4 => KeyManager::new_128(&[key[0], key[1], key[2], key[3]]),
+            6 => KeyManager::new_192(&[key[0], key[1], key[2], key[3], key[4], key[5]]),
+            _ => KeyManager::new_256(&[
+                key[0], key[1], key[2], key[3], key[4], key[5], key[6], key[7],
+            ]),
+        };
+        let mut expanded_key = [0; WORDS];
+        expanded_key.copy_from_slice(key_manager.key());
+        let mut inv_key = [0; WORDS];
+        inv_key.copy_from_slice(InvKeyManager::new(&key_manager).inv_key());
+        Aes {
+            key: expanded_key,
+            inv_key,
+        }
+    }
+
+    /**
+     * Ciphers a 4x4 matrix of bytes, same as Cipher::cipher
+     */
+    pub fn cipher(&self, input: &mut [u8; 16]) {
+        cipher_rounds(&self.key, Self::ROUNDS, false, &S_BOX, input, |_, _, _| {});
+    }
+
+    /**
+     * Deciphers a 4x4 matrix of bytes with the equivalent inverse cipher,
+     * same as Cipher::decipher
+     */
+    pub fn decipher(&self, input: &mut [u8; 16]) {
+        equivalent_inverse_cipher_rounds(
+            &self.inv_key,
+            Self::ROUNDS,
+            false,
+            &INV_S_BOX,
+            input,
+            |_, _, _| {},
+        );
+    }
+
+    /**
+     * Deciphers a 4x4 matrix of bytes with the straight inverse cipher,
+     * same as Cipher::decipher_straight
+     */
+    pub fn decipher_straight(&self, input: &mut [u8; 16]) {
+        straight_inverse_cipher_rounds(
+            &self.key,
+            Self::ROUNDS,
+            false,
+            &INV_S_BOX,
+            input,
+            |_, _, _| {},
+        );
+    }
+
+    /**
+     * Same as cipher, but also returns the state after each step of each round
+     */
+    pub fn cipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::Cipher);
+        cipher_rounds(
+            &self.key,
+            Self::ROUNDS,
+            false,
+            &S_BOX,
+            input,
+            |round, step, state| trace.record(round, step, state),
+        );
+        trace
+    }
+
+    /**
+     * Same as decipher, but also returns the state after each step of each round
+     */
+    pub fn decipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::EquivalentInverseCipher);
+        equivalent_inverse_cipher_rounds(
+            &self.inv_key,
+            Self::ROUNDS,
+            false,
+            &INV_S_BOX,
+            input,
+            |round, step, state| trace.record(round, step, state),
+        );
+        trace
+    }
+
+    /**
+     * Same as decipher_straight, but also returns the state after each step of each round
+     */
+    pub fn decipher_straight_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::InverseCipher);
+        straight_inverse_cipher_rounds(
+            &self.key,
+            Self::ROUNDS,
+            false,
+            &INV_S_BOX,
+            input,
+            |round, step, state| trace.record(round, step, state),
+        );
+        trace
+    }
+}
+
+impl<const NK: usize, const WORDS: usize> KeyInit for Aes<NK, WORDS> {
+    const KEY_SIZE: usize = NK * 4;
+
+    fn new_from_slice(key: &[u8]) -> Result<Aes<NK, WORDS>, Error> {
+        if key.len() != Self::KEY_SIZE {
+            return Err(Error::InvalidKeyLength(key.len()));
+        }
+        let mut words = [0; NK];
+        for (word, chunk) in words.iter_mut().zip(key.chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Ok(Self::new(&words))
+    }
+}
+
+impl<const NK: usize, const WORDS: usize> BlockCipher for Aes<NK, WORDS> {}
+
+impl<const NK: usize, const WORDS: usize> BlockEncrypt for Aes<NK, WORDS> {
+    fn encrypt_block(&self, block: &mut Block) {
+        self.cipher(block);
+    }
+}
+
+impl<const NK: usize, const WORDS: usize> BlockDecrypt for Aes<NK, WORDS> {
+    fn decrypt_block(&self, block: &mut Block) {
+        self.decipher(block);
+    }
+}
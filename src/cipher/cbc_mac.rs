@@ -0,0 +1,44 @@
+use crate::block::BlockEncrypt;
+
+/**
+ * CBC-MAC with a zero IV that ciphers the data as it comes in, so that
+ * a message made of several pieces never needs to be concatenated in memory.
+ * It is the BCC function of the CTR_DRBG derivation function and the PRF of FF1.
+ * On its own it is only secure for messages of a fixed length.
+ */
+pub(crate) struct CbcMac<'a, C> {
+    encryptor: &'a C,
+    chaining_value: [u8; 16],
+    filled: usize,
+}
+
+impl<'a, C: BlockEncrypt> CbcMac<'a, C> {
+    pub(crate) fn new(encryptor: &'a C) -> CbcMac<'a, C> {
+        CbcMac {
+            encryptor,
+            chaining_value: [0; 16],
+            filled: 0,
+        }
+    }
+
+    pub(crate) fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.chaining_value[self.filled] ^= byte;
+            self.filled += 1;
+            if self.filled == 16 {
+                self.encryptor.encrypt_block(&mut self.chaining_value);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /**
+     * Pads the data with zeros up to a multiple of the block length
+     */
+    pub(crate) fn finish(mut self) -> [u8; 16] {
+        if self.filled > 0 {
+            self.encryptor.encrypt_block(&mut self.chaining_value);
+        }
+        self.chaining_value
+    }
+}
@@ -1,9 +1,12 @@
 #[cfg(test)]
-
+#[allow(clippy::module_inception)]
 mod cipher_test {
 
-    use crate::aes_matrix::AesMatrix;
-    use crate::cipher::Cipher;
+    use crate::cipher::hazmat::{self, ReducedCipher, RoundConfig};
+    use crate::cipher::{Aes128, Aes192, Aes256, Cipher, Decryptor, Encryptor, Key};
+    use crate::error::Error;
+    use crate::math::{SBox, SBoxParams};
+    use crate::trace::{Step, TraceKind};
 
     #[test]
     fn it_chiphers_128() {
@@ -116,4 +119,338 @@ mod cipher_test {
             ]
         );
     }
+
+    #[test]
+    fn it_ciphers_with_encryptor() {
+        let mut content: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let encryptor = Encryptor::new_192(&[
+            0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617,
+        ]);
+        encryptor.cipher(&mut content);
+        assert_eq!(
+            content,
+            [
+                0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+                0x71, 0x91
+            ]
+        );
+    }
+
+    #[test]
+    fn it_deciphers_with_decryptor() {
+        let mut content: [u8; 16] = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+        let decryptor = Decryptor::new_256(&[
+            0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+            0x1c1d1e1f,
+        ]);
+        decryptor.decipher(&mut content);
+        assert_eq!(
+            content,
+            [
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+                0xee, 0xff,
+            ]
+        );
+    }
+
+    #[test]
+    fn it_deciphers_straight_128() {
+        let mut content: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let cipher = Cipher::new_128(&[0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+        cipher.decipher_straight(&mut content);
+        assert_eq!(
+            content,
+            [
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+                0xee, 0xff,
+            ]
+        );
+    }
+
+    /**
+     * Xorshift generator, good enough to produce test inputs
+     */
+    fn next_random(state: &mut u32) -> u32 {
+        *state ^= *state << 13;
+        *state ^= *state >> 17;
+        *state ^= *state << 5;
+        *state
+    }
+
+    fn assert_decipher_paths_agree(cipher: &Cipher, state: &mut u32) {
+        for _ in 0..64 {
+            let mut content = [0u8; 16];
+            for byte in content.iter_mut() {
+                *byte = next_random(state) as u8;
+            }
+            let mut equivalent = content;
+            let mut straight = content;
+            cipher.decipher(&mut equivalent);
+            cipher.decipher_straight(&mut straight);
+            assert_eq!(equivalent, straight);
+            cipher.cipher(&mut straight);
+            assert_eq!(straight, content);
+        }
+    }
+
+    #[test]
+    fn it_deciphers_straight_as_equivalent() {
+        let mut state = 0x2545f491;
+        for _ in 0..8 {
+            let mut key = [0u32; 8];
+            for word in key.iter_mut() {
+                *word = next_random(&mut state);
+            }
+            let cipher = Cipher::new_128(&[key[0], key[1], key[2], key[3]]);
+            assert_decipher_paths_agree(&cipher, &mut state);
+            let cipher = Cipher::new_192(&[key[0], key[1], key[2], key[3], key[4], key[5]]);
+            assert_decipher_paths_agree(&cipher, &mut state);
+            let cipher = Cipher::new_256(&key);
+            assert_decipher_paths_agree(&cipher, &mut state);
+        }
+    }
+
+    #[test]
+    fn it_ciphers_reduced_with_all_rounds_as_standard() {
+        let key = [0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f];
+        let mut content: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let cipher = hazmat::reduced_cipher_128(&key, RoundConfig::with_rounds(10)).unwrap();
+        cipher.cipher(&mut content);
+        assert_eq!(
+            content,
+            [
+                0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+                0xc5, 0x5a
+            ]
+        );
+    }
+
+    #[test]
+    fn it_ciphers_reduced_as_truncated_standard() {
+        let key = [0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f];
+        let plain: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let mut content = plain;
+        let trace = Cipher::new_128(&key).cipher_traced(&mut content);
+        for rounds in 1..10 {
+            let cipher = hazmat::reduced_cipher_128(
+                &key,
+                RoundConfig {
+                    final_mix_columns: true,
+                    ..RoundConfig::with_rounds(rounds)
+                },
+            )
+            .unwrap();
+            let mut content = plain;
+            cipher.cipher(&mut content);
+            let expected = trace
+                .entries()
+                .iter()
+                .find(|entry| entry.round == rounds + 1 && entry.step == Step::Start)
+                .unwrap();
+            assert_eq!(content, expected.state);
+        }
+    }
+
+    fn assert_reduced_round_trip(cipher: &ReducedCipher, state: &mut u32) {
+        let mut content = [0u8; 16];
+        for byte in content.iter_mut() {
+            *byte = next_random(state) as u8;
+        }
+        let mut ciphered = content;
+        cipher.cipher(&mut ciphered);
+        assert_ne!(ciphered, content);
+        let mut deciphered = ciphered;
+        cipher.decipher(&mut deciphered);
+        assert_eq!(deciphered, content);
+        let mut deciphered = ciphered;
+        cipher.decipher_straight(&mut deciphered);
+        assert_eq!(deciphered, content);
+    }
+
+    #[test]
+    fn it_deciphers_reduced() {
+        let mut state = 0x9e3779b9;
+        let mut key = [0u32; 8];
+        for word in key.iter_mut() {
+            *word = next_random(&mut state);
+        }
+        for &final_mix_columns in [false, true].iter() {
+            for rounds in 1..=14 {
+                let config = RoundConfig {
+                    final_mix_columns,
+                    ..RoundConfig::with_rounds(rounds)
+                };
+                if rounds <= 10 {
+                    let cipher =
+                        hazmat::reduced_cipher_128(&[key[0], key[1], key[2], key[3]], config)
+                            .unwrap();
+                    assert_reduced_round_trip(&cipher, &mut state);
+                }
+                if rounds <= 12 {
+                    let cipher = hazmat::reduced_cipher_192(
+                        &[key[0], key[1], key[2], key[3], key[4], key[5]],
+                        config,
+                    )
+                    .unwrap();
+                    assert_reduced_round_trip(&cipher, &mut state);
+                }
+                let cipher = hazmat::reduced_cipher_256(&key, config).unwrap();
+                assert_reduced_round_trip(&cipher, &mut state);
+            }
+        }
+    }
+
+    #[test]
+    fn it_refuses_more_rounds_than_standard() {
+        assert_eq!(
+            hazmat::reduced_cipher_192(&[0, 0, 0, 0, 0, 0], RoundConfig::with_rounds(13)).err(),
+            Some(Error::InvalidRounds(12))
+        );
+    }
+
+    #[test]
+    fn it_refuses_zero_rounds() {
+        assert_eq!(
+            hazmat::reduced_cipher_128(&[0, 0, 0, 0], RoundConfig::with_rounds(0)).err(),
+            Some(Error::InvalidRounds(10))
+        );
+    }
+
+    #[test]
+    fn it_builds_from_key_bytes() {
+        let key = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+        ];
+        assert_eq!(
+            Key::from_bytes(&key),
+            Ok(Key::Aes192([
+                0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617
+            ]))
+        );
+        let plain = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let mut content = plain;
+        Cipher::new(&key[..16]).unwrap().cipher(&mut content);
+        let mut expected = plain;
+        Encryptor::new(&key[..16]).unwrap().cipher(&mut expected);
+        assert_eq!(content, expected);
+        Decryptor::new(&key[..16]).unwrap().decipher(&mut content);
+        assert_eq!(content, plain);
+    }
+
+    #[test]
+    fn it_refuses_wrong_key_lengths() {
+        for &length in [0, 15, 17, 31, 33].iter() {
+            let key = [0; 33];
+            assert_eq!(
+                Key::from_bytes(&key[..length]),
+                Err(Error::InvalidKeyLength(length))
+            );
+            assert_eq!(
+                Cipher::new(&key[..length]).err(),
+                Some(Error::InvalidKeyLength(length))
+            );
+            assert!(Encryptor::new(&key[..length]).is_err());
+            assert!(Decryptor::new(&key[..length]).is_err());
+        }
+    }
+
+    static SWAPPED_S_BOX: [u8; 256] = SBoxParams {
+        polynomial: 0x11d,
+        affine_constant: 0x63,
+    }
+    .s_box();
+    static SWAPPED_INV_S_BOX: [u8; 256] = SBoxParams {
+        polynomial: 0x11d,
+        affine_constant: 0x63,
+    }
+    .inv_s_box();
+
+    #[test]
+    fn it_ciphers_with_custom_s_box() {
+        let key = [0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f];
+        let config = RoundConfig {
+            s_box: SBox {
+                forward: &SWAPPED_S_BOX,
+                inverse: &SWAPPED_INV_S_BOX,
+            },
+            ..RoundConfig::with_rounds(10)
+        };
+        let cipher = hazmat::reduced_cipher_128(&key, config).unwrap();
+        let plain: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let mut content = plain;
+        cipher.cipher(&mut content);
+        let mut standard = plain;
+        Cipher::new_128(&key).cipher(&mut standard);
+        assert_ne!(content, standard);
+        let mut deciphered = content;
+        cipher.decipher(&mut deciphered);
+        assert_eq!(deciphered, plain);
+        cipher.decipher_straight(&mut content);
+        assert_eq!(content, plain);
+    }
+
+    #[test]
+    fn it_fixes_the_rounds_in_the_type() {
+        assert_eq!((Aes128::ROUNDS, Aes128::EXPANDED_KEY_WORDS), (10, 44));
+        assert_eq!((Aes192::ROUNDS, Aes192::EXPANDED_KEY_WORDS), (12, 52));
+        assert_eq!((Aes256::ROUNDS, Aes256::EXPANDED_KEY_WORDS), (14, 60));
+    }
+
+    #[test]
+    fn it_dispatches_on_the_key_length() {
+        let plain: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let keys = [
+            Key::Aes128([0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]),
+            Key::Aes192([1, 2, 3, 4, 5, 6]),
+            Key::Aes256([1, 2, 3, 4, 5, 6, 7, 8]),
+        ];
+        for key in keys.iter() {
+            let cipher = Cipher::from_key(key);
+            assert_eq!(cipher.key_size(), key.size());
+            let mut content = plain;
+            let trace = cipher.cipher_traced(&mut content);
+            let mut expected = plain;
+            let expected_trace = Encryptor::from_key(key).cipher_traced(&mut expected);
+            assert_eq!(content, expected);
+            assert_eq!(trace.entries(), expected_trace.entries());
+            let mut straight = content;
+            let straight_trace = cipher.decipher_straight_traced(&mut straight);
+            assert_eq!(straight_trace.kind(), TraceKind::InverseCipher);
+            let equivalent_trace = cipher.decipher_traced(&mut content);
+            assert_eq!(
+                equivalent_trace.entries(),
+                Decryptor::from_key(key)
+                    .decipher_traced(&mut expected)
+                    .entries()
+            );
+            assert_eq!(content, plain);
+            assert_eq!(straight, plain);
+        }
+        assert!(matches!(Cipher::from_key(&keys[1]), Cipher::Aes192(_)));
+    }
 }
@@ -0,0 +1,130 @@
+/*!
+ * HAZARDOUS: the ciphers built by this module are NOT AES.
+ *
+ * They are meant for cryptanalysis experiments, like square/integral or
+ * differential attacks, that need AES with fewer rounds than the standard,
+ * with a mix columns step in the last round too or with a different secure
+ * box. Never use them to protect real data.
+ */
+use crate::cipher::{straight_inverse_cipher_rounds, Decryptor, Encryptor};
+use crate::error::Error;
+use crate::key_manager::{InvKeyManager, KeyManager};
+use crate::math::{SBox, AES_S_BOX};
+use crate::trace::{Trace, TraceKind};
+
+/**
+ * Parameters of a reduced round cipher. rounds must be between 1 and the
+ * standard number of rounds for the key length (10, 12 or 14), while
+ * final_mix_columns tells if the last round keeps the mix columns step
+ * that the standard omits. s_box replaces the AES secure box both in the
+ * rounds and in the key expansion, see SBoxParams to generate one.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundConfig {
+    pub rounds: usize,
+    pub final_mix_columns: bool,
+    pub s_box: SBox,
+}
+
+impl RoundConfig {
+    /**
+     * The given number of rounds with everything else as in the standard
+     */
+    pub fn with_rounds(rounds: usize) -> RoundConfig {
+        RoundConfig {
+            rounds,
+            final_mix_columns: false,
+            s_box: AES_S_BOX,
+        }
+    }
+}
+
+/**
+ * A cipher built with a RoundConfig. It has the same methods of Cipher, but the
+ * number of rounds and the secure box are only known at runtime.
+ */
+pub struct ReducedCipher {
+    encryptor: Encryptor,
+    decryptor: Decryptor,
+}
+
+/**
+ * Builds a reduced round cipher for a 128 bits long key. Only the round
+ * keys needed by the given number of rounds are expanded.
+ *
+ * Fails with Error::InvalidRounds if the number of rounds is not in the
+ * range described in RoundConfig.
+ */
+pub fn reduced_cipher_128(key: &[u32; 4], config: RoundConfig) -> Result<ReducedCipher, Error> {
+    let key_manager = KeyManager::with_params_128(key, config.rounds, config.s_box.forward)?;
+    Ok(ReducedCipher::new(key_manager, config))
+}
+
+pub fn reduced_cipher_192(key: &[u32; 6], config: RoundConfig) -> Result<ReducedCipher, Error> {
+    let key_manager = KeyManager::with_params_192(key, config.rounds, config.s_box.forward)?;
+    Ok(ReducedCipher::new(key_manager, config))
+}
+
+pub fn reduced_cipher_256(key: &[u32; 8], config: RoundConfig) -> Result<ReducedCipher, Error> {
+    let key_manager = KeyManager::with_params_256(key, config.rounds, config.s_box.forward)?;
+    Ok(ReducedCipher::new(key_manager, config))
+}
+
+impl ReducedCipher {
+    fn new(key_manager: KeyManager, config: RoundConfig) -> ReducedCipher {
+        ReducedCipher {
+            decryptor: Decryptor {
+                inv_key_manager: InvKeyManager::new(&key_manager),
+                final_mix_columns: config.final_mix_columns,
+                inv_s_box: config.s_box.inverse,
+            },
+            encryptor: Encryptor {
+                key_manager,
+                final_mix_columns: config.final_mix_columns,
+                s_box: config.s_box.forward,
+            },
+        }
+    }
+
+    pub fn cipher(&self, input: &mut [u8; 16]) {
+        self.encryptor.cipher(input);
+    }
+
+    pub fn decipher(&self, input: &mut [u8; 16]) {
+        self.decryptor.decipher(input);
+    }
+
+    pub fn decipher_straight(&self, input: &mut [u8; 16]) {
+        let key_manager = &self.encryptor.key_manager;
+        straight_inverse_cipher_rounds(
+            key_manager.key(),
+            key_manager.rounds(),
+            self.encryptor.final_mix_columns,
+            self.decryptor.inv_s_box,
+            input,
+            |_, _, _| {},
+        );
+    }
+
+    pub fn cipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        self.encryptor.cipher_traced(input)
+    }
+
+    pub fn decipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        self.decryptor.decipher_traced(input)
+    }
+
+    pub fn decipher_straight_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::InverseCipher);
+        let key_manager = &self.encryptor.key_manager;
+        straight_inverse_cipher_rounds(
+            key_manager.key(),
+            key_manager.rounds(),
+            self.encryptor.final_mix_columns,
+            self.decryptor.inv_s_box,
+            input,
+            |round, step, state| trace.record(round, step, state),
+        );
+        trace
+    }
+}
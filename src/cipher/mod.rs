@@ -1,38 +1,209 @@
 use crate::aes_matrix::AesMatrix;
-use crate::key_manager::KeyManager;
+use crate::block::{Block, BlockCipher, BlockDecrypt, BlockEncrypt};
+use crate::error::Error;
+use crate::key_manager::{InvKeyManager, KeyManager};
+use crate::math::AES_S_BOX;
+#[cfg(feature = "std")]
+use crate::random::{self, RandomError};
+use crate::trace::{Step, Trace, TraceKind};
 
+mod aes;
+pub(crate) mod cbc_mac;
 mod cipher_test;
+pub mod hazmat;
+
+pub use aes::{Aes, Aes128, Aes192, Aes256};
 
 /**
  * Struct to cipher or decipher a 4x4 array of bytes given
  * a key, wich can be 4 words long (128 bits), 6 words long
  * (192 bits) or 8 words lorg (256 bit). Here word is intended
- * as 4 bytes (32 bits).  
+ * as 4 bytes (32 bits).
+ *
+ * It is meant for keys whose length is only known at runtime: each variant
+ * holds the Aes type for its length and every call is dispatched to it.
+ * It holds the keys for both directions, if only one of them is needed
+ * an Encryptor or a Decryptor is cheaper to build and to keep around.
+ */
+pub enum Cipher {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+/**
+ * Calls the same method on the Aes held by any variant of Cipher
+ */
+macro_rules! dispatch {
+    ($cipher: expr, $aes: ident => $call: expr) => {
+        match $cipher {
+            Cipher::Aes128($aes) => $call,
+            Cipher::Aes192($aes) => $call,
+            Cipher::Aes256($aes) => $call,
+        }
+    };
+}
+
+/**
+ * Struct to only cipher 4x4 arrays of bytes. Building it doesn't compute the
+ * key for the equivalent inverse cipher, so it is the right choice for modes
+ * of operation that only use the forward cipher, like CTR, CFB, OFB, GCM or CMAC.
  */
-pub struct Cipher {
+pub struct Encryptor {
     key_manager: KeyManager,
+    final_mix_columns: bool,
+    s_box: &'static [u8; 256],
+}
+
+/**
+ * Struct to only decipher 4x4 arrays of bytes. It only keeps the key
+ * for the equivalent inverse cipher.
+ */
+pub struct Decryptor {
+    inv_key_manager: InvKeyManager,
+    final_mix_columns: bool,
+    inv_s_box: &'static [u8; 256],
+}
+
+/**
+ * The three key lengths allowed by the AES standard
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeySize {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+/**
+ * A key for any of the three key lengths, in the words taken by the constructors
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Aes128([u32; 4]),
+    Aes192([u32; 6]),
+    Aes256([u32; 8]),
+}
+
+impl KeySize {
+    /**
+     * Length of the key in bytes
+     */
+    pub fn bytes(self) -> usize {
+        match self {
+            KeySize::Aes128 => 16,
+            KeySize::Aes192 => 24,
+            KeySize::Aes256 => 32,
+        }
+    }
+
+    /**
+     * Length of the key in words, the nk of the AES standard
+     */
+    pub fn words(self) -> usize {
+        self.bytes() / 4
+    }
+}
+
+impl Key {
+    pub fn size(&self) -> KeySize {
+        match self {
+            Key::Aes128(_) => KeySize::Aes128,
+            Key::Aes192(_) => KeySize::Aes192,
+            Key::Aes256(_) => KeySize::Aes256,
+        }
+    }
+
+    /**
+     * The key from its bytes, in the same order in which they appear in the
+     * standard. Fails if the length is not one of the ones in KeySize.
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Key, Error> {
+        match bytes.len() {
+            16 | 24 | 32 => Ok(Self::from_valid_bytes(bytes)),
+            length => Err(Error::InvalidKeyLength(length)),
+        }
+    }
+
+    /**
+     * Same as from_bytes, for the modules that produce keys as byte strings
+     * whose length comes from a KeySize. Panics if the length is wrong.
+     */
+    pub(crate) fn from_valid_bytes(bytes: &[u8]) -> Key {
+        let mut words = [0; 8];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        match bytes.len() {
+            16 => Key::Aes128([words[0], words[1], words[2], words[3]]),
+            24 => Key::Aes192([words[0], words[1], words[2], words[3], words[4], words[5]]),
+            32 => Key::Aes256(words),
+            length => panic!("invalid key length: {} bytes", length),
+        }
+    }
+
+    pub fn words(&self) -> &[u32] {
+        match self {
+            Key::Aes128(words) => words,
+            Key::Aes192(words) => words,
+            Key::Aes256(words) => words,
+        }
+    }
 }
 
 impl Cipher {
     /**
      * Initializes the cipher based on key length
      */
-
     pub fn new_128(key: &[u32; 4]) -> Cipher {
-        Cipher {
-            key_manager: KeyManager::new_128(key),
-        }
+        Cipher::Aes128(Aes128::new(key))
     }
 
     pub fn new_192(key: &[u32; 6]) -> Cipher {
-        Cipher {
-            key_manager: KeyManager::new_192(key),
-        }
+        Cipher::Aes192(Aes192::new(key))
     }
 
     pub fn new_256(key: &[u32; 8]) -> Cipher {
-        Cipher {
-            key_manager: KeyManager::new_256(key),
+        Cipher::Aes256(Aes256::new(key))
+    }
+
+    /**
+     * Initializes the cipher with a key given as bytes, see Key::from_bytes
+     */
+    pub fn new(key: &[u8]) -> Result<Cipher, Error> {
+        Ok(Self::from_key(&Key::from_bytes(key)?))
+    }
+
+    /**
+     * Initializes the cipher with a key of any length
+     */
+    pub fn from_key(key: &Key) -> Cipher {
+        match key {
+            Key::Aes128(words) => Self::new_128(words),
+            Key::Aes192(words) => Self::new_192(words),
+            Key::Aes256(words) => Self::new_256(words),
+        }
+    }
+
+    /**
+     * A fresh key of the given length made of random bytes from the operating
+     * system, see the random module. Only available with the std feature.
+     */
+    #[cfg(feature = "std")]
+    pub fn generate_key(size: KeySize) -> Result<Key, RandomError> {
+        let mut bytes = [0; 32];
+        random::fill(&mut bytes[..size.bytes()])?;
+        Ok(Key::from_valid_bytes(&bytes[..size.bytes()]))
+    }
+
+    /**
+     * Length of the key the cipher was built with
+     */
+    pub fn key_size(&self) -> KeySize {
+        match self {
+            Cipher::Aes128(_) => KeySize::Aes128,
+            Cipher::Aes192(_) => KeySize::Aes192,
+            Cipher::Aes256(_) => KeySize::Aes256,
         }
     }
 
@@ -41,19 +212,7 @@ impl Cipher {
      * module following the AES encryption standard. The procedure is the same regardless of key length
      */
     pub fn cipher(&self, input: &mut [u8; 16]) {
-        AesMatrix::add_round_key_8(input, self.key_manager.next_words(0));
-        for round in 0..self.key_manager.rounds() - 1 {
-            AesMatrix::substitute_bytes_8(input);
-            AesMatrix::shift_rows_8(input);
-            AesMatrix::mix_columns_8(input);
-            AesMatrix::add_round_key_8(input, self.key_manager.next_words((round + 1) * 4));
-        }
-        AesMatrix::substitute_bytes_8(input);
-        AesMatrix::shift_rows_8(input);
-        AesMatrix::add_round_key_8(
-            input,
-            self.key_manager.next_words(self.key_manager.rounds() * 4),
-        );
+        dispatch!(self, aes => aes.cipher(input))
     }
 
     /**
@@ -64,18 +223,373 @@ impl Cipher {
      * refers to as the'equivalent inverse cipher'
      */
     pub fn decipher(&self, input: &mut [u8; 16]) {
-        AesMatrix::add_round_key_8(
+        dispatch!(self, aes => aes.decipher(input))
+    }
+
+    /**
+     * Deciphers a 4x4 matrix of bytes with what the AES documentation
+     * calls the straight inverse cipher (InvCipher), that undoes the steps
+     * of the cipher in reverse order using the unmodified expanded key.
+     * The result is the same as the one of decipher, it is kept for teaching
+     * purposes and to cross check other implementations.
+     */
+    pub fn decipher_straight(&self, input: &mut [u8; 16]) {
+        dispatch!(self, aes => aes.decipher_straight(input))
+    }
+
+    /**
+     * Same as cipher, but also returns the state after each step of each round
+     */
+    pub fn cipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        dispatch!(self, aes => aes.cipher_traced(input))
+    }
+
+    /**
+     * Same as decipher, but also returns the state after each step of each round
+     */
+    pub fn decipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        dispatch!(self, aes => aes.decipher_traced(input))
+    }
+
+    /**
+     * Same as decipher_straight, but also returns the state after each step of each round
+     */
+    pub fn decipher_straight_traced(&self, input: &mut [u8; 16]) -> Trace {
+        dispatch!(self, aes => aes.decipher_straight_traced(input))
+    }
+}
+
+impl Encryptor {
+    /**
+     * Initializes the encryptor based on key length
+     */
+    pub fn new_128(key: &[u32; 4]) -> Encryptor {
+        Encryptor {
+            key_manager: KeyManager::new_128(key),
+            final_mix_columns: false,
+            s_box: AES_S_BOX.forward,
+        }
+    }
+
+    pub fn new_192(key: &[u32; 6]) -> Encryptor {
+        Encryptor {
+            key_manager: KeyManager::new_192(key),
+            final_mix_columns: false,
+            s_box: AES_S_BOX.forward,
+        }
+    }
+
+    pub fn new_256(key: &[u32; 8]) -> Encryptor {
+        Encryptor {
+            key_manager: KeyManager::new_256(key),
+            final_mix_columns: false,
+            s_box: AES_S_BOX.forward,
+        }
+    }
+
+    /**
+     * Initializes the encryptor with a key given as bytes, see Key::from_bytes
+     */
+    pub fn new(key: &[u8]) -> Result<Encryptor, Error> {
+        Ok(Self::from_key(&Key::from_bytes(key)?))
+    }
+
+    /**
+     * Same as new, for keys whose length is known to be right, see Key::from_valid_bytes
+     */
+    pub(crate) fn from_key_bytes(key: &[u8]) -> Encryptor {
+        Self::from_key(&Key::from_valid_bytes(key))
+    }
+
+    /**
+     * Initializes the encryptor with a key of any length
+     */
+    pub fn from_key(key: &Key) -> Encryptor {
+        match key {
+            Key::Aes128(words) => Self::new_128(words),
+            Key::Aes192(words) => Self::new_192(words),
+            Key::Aes256(words) => Self::new_256(words),
+        }
+    }
+
+    /**
+     * Ciphers a 4x4 matrix of bytes, same as Cipher::cipher
+     */
+    pub fn cipher(&self, input: &mut [u8; 16]) {
+        self.cipher_observed(input, |_, _, _| {});
+    }
+
+    /**
+     * Same as cipher, but also returns the state after each step of each round
+     */
+    pub fn cipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::Cipher);
+        self.cipher_observed(input, |round, step, state| trace.record(round, step, state));
+        trace
+    }
+
+    /**
+     * The actual cipher. observe is called with the state after each step,
+     * when it does nothing the compiler removes the calls altogether.
+     */
+    fn cipher_observed<F>(&self, input: &mut [u8; 16], observe: F)
+    where
+        F: FnMut(usize, Step, &[u8; 16]),
+    {
+        cipher_rounds(
+            self.key_manager.key(),
+            self.key_manager.rounds(),
+            self.final_mix_columns,
+            self.s_box,
+            input,
+            observe,
+        );
+    }
+}
+
+impl Decryptor {
+    /**
+     * Initializes the decryptor based on key length. The forward key
+     * is only needed while producing the inverse one and is then dropped.
+     */
+    pub fn new_128(key: &[u32; 4]) -> Decryptor {
+        Decryptor {
+            inv_key_manager: InvKeyManager::new(&KeyManager::new_128(key)),
+            final_mix_columns: false,
+            inv_s_box: AES_S_BOX.inverse,
+        }
+    }
+
+    pub fn new_192(key: &[u32; 6]) -> Decryptor {
+        Decryptor {
+            inv_key_manager: InvKeyManager::new(&KeyManager::new_192(key)),
+            final_mix_columns: false,
+            inv_s_box: AES_S_BOX.inverse,
+        }
+    }
+
+    pub fn new_256(key: &[u32; 8]) -> Decryptor {
+        Decryptor {
+            inv_key_manager: InvKeyManager::new(&KeyManager::new_256(key)),
+            final_mix_columns: false,
+            inv_s_box: AES_S_BOX.inverse,
+        }
+    }
+
+    /**
+     * Initializes the decryptor with a key given as bytes, see Key::from_bytes
+     */
+    pub fn new(key: &[u8]) -> Result<Decryptor, Error> {
+        Ok(Self::from_key(&Key::from_bytes(key)?))
+    }
+
+    /**
+     * Initializes the decryptor with a key of any length
+     */
+    pub fn from_key(key: &Key) -> Decryptor {
+        match key {
+            Key::Aes128(words) => Self::new_128(words),
+            Key::Aes192(words) => Self::new_192(words),
+            Key::Aes256(words) => Self::new_256(words),
+        }
+    }
+
+    /**
+     * Deciphers a 4x4 matrix of bytes with the equivalent inverse cipher,
+     * same as Cipher::decipher
+     */
+    pub fn decipher(&self, input: &mut [u8; 16]) {
+        self.decipher_observed(input, |_, _, _| {});
+    }
+
+    /**
+     * Same as decipher, but also returns the state after each step of each round
+     */
+    pub fn decipher_traced(&self, input: &mut [u8; 16]) -> Trace {
+        let mut trace = Trace::new(TraceKind::EquivalentInverseCipher);
+        self.decipher_observed(input, |round, step, state| trace.record(round, step, state));
+        trace
+    }
+
+    /**
+     * The actual equivalent inverse cipher, observe works as in Encryptor::cipher_observed.
+     * The rounds are numbered as in the trace of the AES standard, so
+     * the first round to be undone is round 1.
+     */
+    fn decipher_observed<F>(&self, input: &mut [u8; 16], observe: F)
+    where
+        F: FnMut(usize, Step, &[u8; 16]),
+    {
+        equivalent_inverse_cipher_rounds(
+            self.inv_key_manager.inv_key(),
+            self.inv_key_manager.rounds(),
+            self.final_mix_columns,
+            self.inv_s_box,
             input,
-            self.key_manager.next_words(self.key_manager.rounds() * 4),
+            observe,
         );
-        for round in (1..self.key_manager.rounds()).rev() {
-            AesMatrix::inv_substitute_bytes_8(input);
-            AesMatrix::inv_shift_rows_8(input);
+    }
+}
+
+impl BlockCipher for Cipher {}
+
+impl BlockEncrypt for Cipher {
+    fn encrypt_block(&self, block: &mut Block) {
+        self.cipher(block);
+    }
+}
+
+impl BlockDecrypt for Cipher {
+    fn decrypt_block(&self, block: &mut Block) {
+        self.decipher(block);
+    }
+}
+
+impl BlockCipher for Encryptor {}
+
+impl BlockEncrypt for Encryptor {
+    fn encrypt_block(&self, block: &mut Block) {
+        self.cipher(block);
+    }
+}
+
+impl BlockCipher for Decryptor {}
+
+impl BlockDecrypt for Decryptor {
+    fn decrypt_block(&self, block: &mut Block) {
+        self.decipher(block);
+    }
+}
+
+/**
+ * The actual cipher, on the expanded key of a cipher with the given number of rounds.
+ * observe is called with the state after each step, when it does nothing the compiler
+ * removes the calls altogether. When rounds is a constant, as it is for the Aes
+ * types, the loop over the rounds can be unrolled too.
+ */
+#[inline(always)]
+fn cipher_rounds<F>(
+    key: &[u32],
+    rounds: usize,
+    final_mix_columns: bool,
+    s_box: &[u8; 256],
+    input: &mut [u8; 16],
+    mut observe: F,
+) where
+    F: FnMut(usize, Step, &[u8; 16]),
+{
+    observe(0, Step::Input, input);
+    let round_key = &key[..4];
+    observe(0, Step::KSch, &round_key_bytes(round_key));
+    AesMatrix::add_round_key_8(input, round_key);
+    for round in 1..=rounds {
+        observe(round, Step::Start, input);
+        AesMatrix::substitute_bytes_with(input, s_box);
+        observe(round, Step::SBox, input);
+        AesMatrix::shift_rows_8(input);
+        observe(round, Step::SRow, input);
+        if round < rounds || final_mix_columns {
+            AesMatrix::mix_columns_8(input);
+            observe(round, Step::MCol, input);
+        }
+        let round_key = &key[round * 4..round * 4 + 4];
+        observe(round, Step::KSch, &round_key_bytes(round_key));
+        AesMatrix::add_round_key_8(input, round_key);
+    }
+    observe(rounds, Step::Output, input);
+}
+
+/**
+ * The actual equivalent inverse cipher on the key produced by InvKeyManager,
+ * observe works as in cipher_rounds. The rounds are numbered as in the trace
+ * of the AES standard, so the first round to be undone is round 1.
+ */
+#[inline(always)]
+fn equivalent_inverse_cipher_rounds<F>(
+    inv_key: &[u32],
+    rounds: usize,
+    final_mix_columns: bool,
+    inv_s_box: &[u8; 256],
+    input: &mut [u8; 16],
+    mut observe: F,
+) where
+    F: FnMut(usize, Step, &[u8; 16]),
+{
+    observe(0, Step::Input, input);
+    let round_key = &inv_key[rounds * 4..rounds * 4 + 4];
+    observe(0, Step::KSch, &round_key_bytes(round_key));
+    AesMatrix::add_round_key_8(input, round_key);
+    if final_mix_columns {
+        observe(0, Step::KAdd, input);
+        AesMatrix::inv_mix_columns_8(input);
+    }
+    for round in 1..=rounds {
+        observe(round, Step::Start, input);
+        AesMatrix::substitute_bytes_with(input, inv_s_box);
+        observe(round, Step::SBox, input);
+        AesMatrix::inv_shift_rows_8(input);
+        observe(round, Step::SRow, input);
+        if round < rounds {
             AesMatrix::inv_mix_columns_8(input);
-            AesMatrix::add_round_key_8(input, self.key_manager.next_words_inv(round * 4));
+            observe(round, Step::MCol, input);
         }
-        AesMatrix::inv_substitute_bytes_8(input);
+        let base = (rounds - round) * 4;
+        let round_key = &inv_key[base..base + 4];
+        observe(round, Step::KSch, &round_key_bytes(round_key));
+        AesMatrix::add_round_key_8(input, round_key);
+    }
+    observe(rounds, Step::Output, input);
+}
+
+/**
+ * The straight inverse cipher on the unmodified expanded key, observe works
+ * as in cipher_rounds and the rounds are numbered as in
+ * equivalent_inverse_cipher_rounds.
+ */
+fn straight_inverse_cipher_rounds<F>(
+    key: &[u32],
+    rounds: usize,
+    final_mix_columns: bool,
+    inv_s_box: &[u8; 256],
+    input: &mut [u8; 16],
+    mut observe: F,
+) where
+    F: FnMut(usize, Step, &[u8; 16]),
+{
+    observe(0, Step::Input, input);
+    let round_key = &key[rounds * 4..rounds * 4 + 4];
+    observe(0, Step::KSch, &round_key_bytes(round_key));
+    AesMatrix::add_round_key_8(input, round_key);
+    if final_mix_columns {
+        observe(0, Step::KAdd, input);
+        AesMatrix::inv_mix_columns_8(input);
+    }
+    for round in 1..=rounds {
+        observe(round, Step::Start, input);
         AesMatrix::inv_shift_rows_8(input);
-        AesMatrix::add_round_key_8(input, self.key_manager.next_words(0));
+        observe(round, Step::SRow, input);
+        AesMatrix::substitute_bytes_with(input, inv_s_box);
+        observe(round, Step::SBox, input);
+        let base = (rounds - round) * 4;
+        let round_key = &key[base..base + 4];
+        observe(round, Step::KSch, &round_key_bytes(round_key));
+        AesMatrix::add_round_key_8(input, round_key);
+        if round < rounds {
+            observe(round, Step::KAdd, input);
+            AesMatrix::inv_mix_columns_8(input);
+        }
+    }
+    observe(rounds, Step::Output, input);
+}
+
+/**
+ * Bytes of a round key in the same order in which add_round_key_8 xors them
+ * into the state, used to record the key schedule in the traces.
+ */
+fn round_key_bytes(key_words: &[u32]) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    for (chunk, word) in bytes.chunks_mut(4).zip(key_words.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
     }
+    bytes
 }
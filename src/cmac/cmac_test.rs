@@ -0,0 +1,137 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod cmac_test {
+    use crate::cipher::Key;
+    use crate::cmac::{Cmac, CmacPrf128};
+
+    /**
+     * The message of the examples in SP 800-38B, the tags are computed on its
+     * first 0, 16, 40 and 64 bytes
+     */
+    const MESSAGE: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf,
+        0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a,
+        0x0a, 0x52, 0xef, 0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b,
+        0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    fn assert_tags(key: Key, tags: [u128; 4]) {
+        let mut cmac = Cmac::new(&key);
+        for (length, tag) in [0, 16, 40, 64].iter().zip(tags.iter()) {
+            assert_eq!(cmac.mac(&MESSAGE[..*length]), tag.to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn it_computes_subkeys() {
+        let cmac = Cmac::new(&Key::Aes128([
+            0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c,
+        ]));
+        assert_eq!(
+            cmac.k1,
+            0xfbeed618357133667c85e08f7236a8de_u128.to_be_bytes()
+        );
+        assert_eq!(
+            cmac.k2,
+            0xf7ddac306ae266ccf90bc11ee46d513b_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_macs_128() {
+        assert_tags(
+            Key::Aes128([0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c]),
+            [
+                0xbb1d6929e95937287fa37d129b756746,
+                0x070a16b46b4d4144f79bdd9dd04a287c,
+                0xdfa66747de9ae63030ca32611497c827,
+                0x51f0bebf7e3b9d92fc49741779363cfe,
+            ],
+        );
+    }
+
+    #[test]
+    fn it_macs_192() {
+        assert_tags(
+            Key::Aes192([
+                0x8e73b0f7, 0xda0e6452, 0xc810f32b, 0x809079e5, 0x62f8ead2, 0x522c6b7b,
+            ]),
+            [
+                0xd17ddf46adaacde531cac483de7a9367,
+                0x9e99a7bf31e710900662f65e617c5184,
+                0x8a1de5be2eb31aad089a82e6ee908b0e,
+                0xa1d5df0eed790f794d77589659f39a11,
+            ],
+        );
+    }
+
+    #[test]
+    fn it_macs_256() {
+        assert_tags(
+            Key::Aes256([
+                0x603deb10, 0x15ca71be, 0x2b73aef0, 0x857d7781, 0x1f352c07, 0x3b6108d7, 0x2d9810a3,
+                0x0914dff4,
+            ]),
+            [
+                0x028962f61b7bf89efc6b551f4667d983,
+                0x28a7023f452e8f82bd4bf28d8c37c35c,
+                0xaaf3d8f1de5640c232f5b169b9c911e6,
+                0xe1992190549f6ed5696a2c056c315410,
+            ],
+        );
+    }
+
+    #[test]
+    fn it_macs_in_pieces() {
+        let mut cmac = Cmac::new(&Key::Aes128([
+            0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c,
+        ]));
+        for piece in MESSAGE[..40].chunks(7) {
+            cmac.update(piece);
+        }
+        assert_eq!(
+            cmac.finalize(),
+            0xdfa66747de9ae63030ca32611497c827_u128.to_be_bytes()
+        );
+        cmac.update(&MESSAGE[..40]);
+        assert!(cmac.verify(&0xdfa66747de9ae63030ca32611497c827_u128.to_be_bytes()));
+        cmac.update(&MESSAGE[..40]);
+        assert!(!cmac.verify(&0xdfa66747de9ae630_u64.to_be_bytes()));
+        cmac.update(&MESSAGE[..40]);
+        assert!(cmac.verify_truncated(&0xdfa66747de9ae630_u64.to_be_bytes(), 8));
+        cmac.update(&MESSAGE[..40]);
+        assert!(!cmac.verify_truncated(&0xdfa66747de9ae631_u64.to_be_bytes(), 8));
+        cmac.update(&MESSAGE[..40]);
+        assert!(!cmac.verify_truncated(&0xdfa66747de9ae630_u64.to_be_bytes(), 12));
+        cmac.update(&MESSAGE[..40]);
+        assert!(!cmac.verify_truncated(&[0xdf], 1));
+    }
+
+    /**
+     * The test vectors of RFC 4615, with keys of 18, 16 and 10 bytes
+     */
+    #[test]
+    fn it_matches_rfc_4615() {
+        let key = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0xed, 0xcb,
+        ];
+        let message = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+        ];
+        let outputs = [
+            (18, 0x84a348a4a45d235babfffc0d2b4da09a_u128),
+            (16, 0x980ae87b5f4c9c5214f5b6a8455e4c2d),
+            (10, 0x290d9e112edb09ee141fcf64c0b72f3d),
+        ];
+        for (length, output) in outputs.iter() {
+            let mut prf = CmacPrf128::new(&key[..*length]);
+            assert_eq!(prf.prf(&message), output.to_be_bytes());
+            prf.update(&message[..3]);
+            prf.update(&message[3..]);
+            assert_eq!(prf.finalize(), output.to_be_bytes());
+        }
+    }
+}
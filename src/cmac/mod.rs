@@ -0,0 +1,191 @@
+/*!
+ * CMAC, the message authentication code built on a block cipher described in
+ * NIST SP 800-38B (and in RFC 4493 for AES-128), and the AES-CMAC-PRF-128
+ * pseudo random function of RFC 4615 built on it.
+ *
+ * The message can be passed in any number of pieces, only the last incomplete
+ * block is kept in memory.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+use crate::math::Math;
+
+mod cmac_test;
+
+/**
+ * Length in bytes of a full tag
+ */
+pub const TAG_LEN: usize = 16;
+
+/**
+ * Length in bytes of the shortest tag accepted by verify_truncated
+ */
+pub const MIN_TRUNCATED_TAG_LEN: usize = 8;
+
+/**
+ * The state of a CMAC computation: the subkeys derived from the key, the
+ * chaining value of the blocks processed so far and the last block, which
+ * can't be processed until it is known whether more data follows.
+ */
+pub struct Cmac<C = Encryptor> {
+    encryptor: C,
+    k1: [u8; 16],
+    k2: [u8; 16],
+    chaining_value: [u8; 16],
+    last_block: [u8; 16],
+    filled: usize,
+}
+
+impl Cmac {
+    pub fn new(key: &Key) -> Cmac {
+        Self::from_encryptor(Encryptor::from_key(key))
+    }
+}
+
+impl<C: BlockEncrypt> Cmac<C> {
+    /**
+     * Derives the two subkeys by doubling the cipher of the zero block in GF(2^128)
+     */
+    pub fn from_encryptor(encryptor: C) -> Cmac<C> {
+        let mut l = [0; 16];
+        encryptor.encrypt_block(&mut l);
+        let k1 = double(&l);
+        let k2 = double(&k1);
+        Cmac {
+            encryptor,
+            k1,
+            k2,
+            chaining_value: [0; 16],
+            last_block: [0; 16],
+            filled: 0,
+        }
+    }
+
+    /**
+     * Adds data to the message
+     */
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            if self.filled == 16 {
+                xor(&mut self.chaining_value, &self.last_block);
+                self.encryptor.encrypt_block(&mut self.chaining_value);
+                self.filled = 0;
+            }
+            self.last_block[self.filled] = *byte;
+            self.filled += 1;
+        }
+    }
+
+    /**
+     * Returns the tag of the message and resets the state, so that the same
+     * key can be used for the next message without deriving the subkeys again
+     */
+    pub fn finalize(&mut self) -> [u8; TAG_LEN] {
+        let mut tag = self.chaining_value;
+        if self.filled == 16 {
+            xor(&mut tag, &self.last_block);
+            xor(&mut tag, &self.k1);
+        } else {
+            // the incomplete block is padded with a one bit followed by zeros
+            self.last_block[self.filled] = 0x80;
+            for byte in self.last_block[self.filled + 1..].iter_mut() {
+                *byte = 0;
+            }
+            xor(&mut tag, &self.last_block);
+            xor(&mut tag, &self.k2);
+        }
+        self.encryptor.encrypt_block(&mut tag);
+        self.chaining_value = [0; 16];
+        self.filled = 0;
+        tag
+    }
+
+    /**
+     * Tag of a whole message
+     */
+    pub fn mac(&mut self, message: &[u8]) -> [u8; TAG_LEN] {
+        self.update(message);
+        self.finalize()
+    }
+
+    /**
+     * Checks the full TAG_LEN bytes tag of the message. The comparison takes
+     * the same time wherever the first difference is.
+     */
+    pub fn verify(&mut self, tag: &[u8]) -> bool {
+        self.verify_truncated(tag, TAG_LEN)
+    }
+
+    /**
+     * Checks a tag truncated to its first len bytes, where len is what the
+     * protocol agreed on and not the length of whatever tag was received.
+     * SP 800-38B advises against tags shorter than MIN_TRUNCATED_TAG_LEN
+     * bytes, so they always fail.
+     */
+    pub fn verify_truncated(&mut self, tag: &[u8], len: usize) -> bool {
+        let expected = self.finalize();
+        (MIN_TRUNCATED_TAG_LEN..=TAG_LEN).contains(&len)
+            && tag.len() == len
+            && Math::constant_time_eq(&expected[..len], tag)
+    }
+}
+
+/**
+ * AES-CMAC-PRF-128: CMAC with AES-128 that accepts keys of any length.
+ * A key that is not 128 bits long is first replaced by its CMAC under the
+ * zero key, as in IKEv2 and the other protocols that use RFC 4615.
+ */
+pub struct CmacPrf128 {
+    cmac: Cmac,
+}
+
+impl CmacPrf128 {
+    pub fn new(variable_key: &[u8]) -> CmacPrf128 {
+        let key = if variable_key.len() == 16 {
+            Key::from_valid_bytes(variable_key)
+        } else {
+            let tag = Cmac::new(&Key::Aes128([0; 4])).mac(variable_key);
+            Key::from_valid_bytes(&tag)
+        };
+        CmacPrf128 {
+            cmac: Cmac::new(&key),
+        }
+    }
+
+    /**
+     * Adds data to the input of the PRF
+     */
+    pub fn update(&mut self, data: &[u8]) {
+        self.cmac.update(data);
+    }
+
+    /**
+     * Returns the 128 bits output for the input given so far and resets the state
+     */
+    pub fn finalize(&mut self) -> [u8; 16] {
+        self.cmac.finalize()
+    }
+
+    /**
+     * Output of the PRF for a whole message
+     */
+    pub fn prf(&mut self, message: &[u8]) -> [u8; 16] {
+        self.cmac.mac(message)
+    }
+}
+
+/**
+ * Multiplication by x in GF(2^128) with the polynomial x^128 + x^7 + x^2 + x + 1,
+ * the block being read as a big endian number
+ */
+fn double(block: &[u8; 16]) -> [u8; 16] {
+    let value = u128::from_be_bytes(*block);
+    // the reduction is masked in instead of branched on, the block is secret
+    ((value << 1) ^ (0x87 & (value >> 127).wrapping_neg())).to_be_bytes()
+}
+
+fn xor(target: &mut [u8; 16], data: &[u8; 16]) {
+    for (byte, other) in target.iter_mut().zip(data.iter()) {
+        *byte ^= other;
+    }
+}
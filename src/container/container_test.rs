@@ -0,0 +1,210 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod container_test {
+    use crate::cipher::KeySize;
+    use crate::container::{
+        ContainerError, Header, Kdf, HEADER_LEN, MAX_CHUNK_LEN, MAX_PBKDF2_ITERATIONS,
+        MIN_PBKDF2_ITERATIONS,
+    };
+
+    /**
+     * Files written by the first version of the format, which every release
+     * must keep reading and writing the same way. They were produced
+     * independently of aes_s, with Python's cryptography.
+     */
+    const GOLDEN_AES128: &[u8] = include_bytes!("golden/v1_aes128.bin");
+    const GOLDEN_KBKDF_AES256: &[u8] = include_bytes!("golden/v1_kbkdf_aes256.bin");
+    const GOLDEN_PBKDF2_AES128: &[u8] = include_bytes!("golden/v1_pbkdf2_aes128.bin");
+
+    const PREFIX: [u8; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+    fn golden_headers() -> (Header, Header) {
+        let mut aes128 = Header::new(KeySize::Aes128, &PREFIX);
+        aes128.chunk_len = 16;
+        let mut kbkdf = Header::new(KeySize::Aes256, &PREFIX);
+        kbkdf.kdf = Kdf::Kbkdf;
+        for (i, byte) in kbkdf.salt.iter_mut().enumerate() {
+            *byte = 0xa0 + i as u8;
+        }
+        kbkdf.chunk_len = 32;
+        (aes128, kbkdf)
+    }
+
+    fn golden_pbkdf2_header() -> Header {
+        let mut header = Header::new(KeySize::Aes128, &PREFIX);
+        header.kdf = Kdf::Pbkdf2 { iterations: 1000 };
+        for (i, byte) in header.salt.iter_mut().enumerate() {
+            *byte = 0xc0 + i as u8;
+        }
+        header.chunk_len = 16;
+        header
+    }
+
+    #[test]
+    fn it_decodes_the_golden_headers() {
+        let (aes128, kbkdf) = golden_headers();
+        assert_eq!(Header::decode(GOLDEN_AES128), Ok(aes128));
+        assert_eq!(Header::decode(GOLDEN_KBKDF_AES256), Ok(kbkdf));
+        assert_eq!(aes128.encode()[..], GOLDEN_AES128[..HEADER_LEN]);
+        assert_eq!(kbkdf.encode()[..], GOLDEN_KBKDF_AES256[..HEADER_LEN]);
+        let pbkdf2 = golden_pbkdf2_header();
+        assert_eq!(Header::decode(GOLDEN_PBKDF2_AES128), Ok(pbkdf2));
+        assert_eq!(pbkdf2.encode()[..], GOLDEN_PBKDF2_AES128[..HEADER_LEN]);
+    }
+
+    #[test]
+    fn it_parses_strictly() {
+        let header = golden_headers().1.encode();
+        let refused = |offset: usize, value: u8| {
+            let mut bytes = header;
+            bytes[offset] = value;
+            Header::decode(&bytes).unwrap_err()
+        };
+        assert_eq!(
+            Header::decode(&header[..HEADER_LEN - 1]),
+            Err(ContainerError::Truncated)
+        );
+        assert_eq!(refused(0, b'a'), ContainerError::BadMagic);
+        assert_eq!(refused(8, 2), ContainerError::UnsupportedVersion(2));
+        assert_eq!(refused(8, 0), ContainerError::UnsupportedVersion(0));
+        assert_eq!(refused(9, 0), ContainerError::UnknownAlgorithm(0));
+        assert_eq!(refused(10, 20), ContainerError::InvalidKeySize(20));
+        assert_eq!(refused(11, 9), ContainerError::UnknownKdf(9));
+        assert_eq!(refused(15, 1), ContainerError::InvalidKdfParams);
+        // a salt without a KDF
+        assert_eq!(refused(11, 0), ContainerError::InvalidKdfParams);
+        // PBKDF2 without iterations
+        assert_eq!(refused(11, 2), ContainerError::InvalidKdfParams);
+        assert_eq!(refused(42, 0), ContainerError::InvalidChunkLen(0));
+        let mut bytes = header;
+        bytes[39..43].copy_from_slice(&(MAX_CHUNK_LEN + 1).to_be_bytes());
+        assert_eq!(
+            Header::decode(&bytes),
+            Err(ContainerError::InvalidChunkLen(MAX_CHUNK_LEN + 1))
+        );
+        // PBKDF2 with too few or too many iterations
+        let with_iterations = |iterations: u32| {
+            let mut header = golden_pbkdf2_header();
+            header.kdf = Kdf::Pbkdf2 { iterations };
+            Header::decode(&header.encode())
+        };
+        for &iterations in [1, MIN_PBKDF2_ITERATIONS - 1, MAX_PBKDF2_ITERATIONS + 1].iter() {
+            assert_eq!(
+                with_iterations(iterations),
+                Err(ContainerError::InvalidKdfParams)
+            );
+        }
+        for &iterations in [MIN_PBKDF2_ITERATIONS, MAX_PBKDF2_ITERATIONS].iter() {
+            assert!(with_iterations(iterations).is_ok());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod files {
+        use super::*;
+        use crate::cipher::Key;
+        use crate::container::{decrypt, encrypt, ContainerReader, ContainerWriter};
+        use crate::error::Error;
+        use std::io::{Read, Write};
+        use std::vec::Vec;
+
+        const GOLDEN_PLAINTEXT: &[u8] = b"aes_s container golden file, version 1\n";
+
+        fn key_128() -> Key {
+            Key::Aes128([0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f])
+        }
+
+        fn key_256() -> Key {
+            Key::Aes256([
+                0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+                0x1c1d1e1f,
+            ])
+        }
+
+        #[test]
+        fn it_reads_and_writes_the_golden_files() {
+            let (aes128, kbkdf) = golden_headers();
+            assert_eq!(
+                decrypt(&key_128(), GOLDEN_AES128).unwrap(),
+                GOLDEN_PLAINTEXT
+            );
+            assert_eq!(
+                decrypt(&key_256(), GOLDEN_KBKDF_AES256).unwrap(),
+                GOLDEN_PLAINTEXT
+            );
+            assert_eq!(
+                encrypt(&key_128(), &aes128, GOLDEN_PLAINTEXT).unwrap(),
+                GOLDEN_AES128
+            );
+            assert_eq!(
+                encrypt(&key_256(), &kbkdf, GOLDEN_PLAINTEXT).unwrap(),
+                GOLDEN_KBKDF_AES256
+            );
+        }
+
+        #[test]
+        fn it_authenticates_the_header() {
+            let mut file = GOLDEN_KBKDF_AES256.to_vec();
+            // still a valid header, for another nonce prefix
+            file[38] ^= 1;
+            assert_eq!(decrypt(&key_256(), &file), Err(Error::AuthenticationFailed));
+            let mut file = GOLDEN_AES128.to_vec();
+            file[42] = 8;
+            assert_eq!(decrypt(&key_128(), &file), Err(Error::AuthenticationFailed));
+            assert_eq!(
+                decrypt(&key_128(), &GOLDEN_AES128[..20]),
+                Err(Error::Container(ContainerError::Truncated))
+            );
+            assert_eq!(
+                decrypt(&key_256(), GOLDEN_AES128),
+                Err(Error::Container(ContainerError::KeyMismatch))
+            );
+        }
+
+        #[test]
+        fn it_derives_keys_from_passwords() {
+            let password = b"correct horse battery staple";
+            let mut plaintext = Vec::new();
+            ContainerReader::with_password(GOLDEN_PBKDF2_AES128, password)
+                .unwrap()
+                .read_to_end(&mut plaintext)
+                .unwrap();
+            assert_eq!(plaintext, GOLDEN_PLAINTEXT);
+            let mut writer =
+                ContainerWriter::with_password(Vec::new(), password, &golden_pbkdf2_header())
+                    .unwrap();
+            writer.write_all(GOLDEN_PLAINTEXT).unwrap();
+            assert_eq!(writer.finish().unwrap(), GOLDEN_PBKDF2_AES128);
+
+            let mut reader =
+                ContainerReader::with_password(GOLDEN_PBKDF2_AES128, b"Tr0ub4dor&3").unwrap();
+            let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(Error::from(error), Error::AuthenticationFailed);
+            assert_eq!(
+                decrypt(&key_128(), GOLDEN_PBKDF2_AES128),
+                Err(Error::Container(ContainerError::KeyMismatch))
+            );
+            assert!(ContainerReader::with_password(GOLDEN_AES128, password).is_err());
+        }
+
+        #[test]
+        fn it_round_trips_generated_headers() {
+            let message: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+            for &kdf in [Kdf::None, Kdf::Kbkdf].iter() {
+                let header = Header::generate(KeySize::Aes256, kdf).unwrap();
+                let file = encrypt(&key_256(), &header, &message).unwrap();
+                let mut reader = ContainerReader::new(&file[..], &key_256()).unwrap();
+                assert_eq!(*reader.header(), header);
+                let mut plaintext = Vec::new();
+                reader.read_to_end(&mut plaintext).unwrap();
+                assert_eq!(plaintext, message);
+            }
+            let mut header = golden_headers().0;
+            header.chunk_len = 0;
+            assert_eq!(
+                encrypt(&key_128(), &header, &[]),
+                Err(Error::Container(ContainerError::InvalidChunkLen(0)))
+            );
+        }
+    }
+}
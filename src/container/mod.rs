@@ -0,0 +1,477 @@
+/*!
+ * The file format of aes_s: a header that says how the file was encrypted,
+ * followed by the STREAM encrypted content (see the stream module).
+ *
+ * The header is HEADER_LEN bytes long, with the numbers in big endian:
+ *
+ * | offset | length | field                                                    |
+ * |--------|--------|----------------------------------------------------------|
+ * | 0      | 8      | MAGIC, "AES_SENC"                                        |
+ * | 8      | 1      | version, VERSION                                         |
+ * | 9      | 1      | algorithm, 1 for STREAM over AES-GCM                     |
+ * | 10     | 1      | length in bytes of the AES key: 16, 24 or 32             |
+ * | 11     | 1      | KDF, 0 for none, 1 for KBKDF and 2 for PBKDF2            |
+ * | 12     | 4      | KDF iterations, 0 for the KDFs that have none            |
+ * |        |        | and MIN_PBKDF2_ITERATIONS to MAX_PBKDF2_ITERATIONS       |
+ * |        |        | for PBKDF2                                               |
+ * | 16     | 16     | salt of the KDF, all zeros without a KDF                 |
+ * | 32     | 7      | nonce prefix of the stream                               |
+ * | 39     | 4      | length of the plaintext of a chunk, 1 to MAX_CHUNK_LEN   |
+ *
+ * The stream starts right after it, and every chunk authenticates the whole
+ * header as associated data, so a header that has been changed makes the first
+ * chunk fail. Parsing is strict: any value that is not listed above is an
+ * error, so that a file of a later version is refused instead of misread.
+ *
+ * The key of the stream is the one given by the caller or, with a KDF, one
+ * derived from it and the salt, so that a master key can encrypt any number of
+ * files without tracking the nonce prefixes. With PBKDF2 it is derived from a
+ * password instead.
+ */
+use crate::cipher::{Key, KeySize};
+use crate::error::Error;
+use crate::kbkdf::{Counter, CounterPosition, FixedInput, Kbkdf, KdfParams, Mode};
+use crate::pbkdf2;
+#[cfg(feature = "std")]
+use crate::stream::{StreamReader, StreamWriter};
+use crate::stream::{DEFAULT_CHUNK_LEN, NONCE_PREFIX_LEN};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+mod container_test;
+
+pub const MAGIC: [u8; 8] = *b"AES_SENC";
+
+/**
+ * The version written by this release, the only one it can read
+ */
+pub const VERSION: u8 = 1;
+
+pub const HEADER_LEN: usize = 43;
+
+pub const SALT_LEN: usize = 16;
+
+/**
+ * The longest chunk allowed, 16 MiB, so that a reader never has to hold more
+ */
+pub const MAX_CHUNK_LEN: u32 = 1 << 24;
+
+/**
+ * The fewest PBKDF2 iterations a header can ask for: less would make the
+ * password cheap to guess, far less than the pbkdf2::DEFAULT_ITERATIONS
+ * written by default
+ */
+pub const MIN_PBKDF2_ITERATIONS: u32 = 1000;
+
+/**
+ * The most PBKDF2 iterations a header can ask for, so that a file can't make
+ * its reader spend hours deriving the key
+ */
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/**
+ * Label of the KBKDF that derives the key of a file
+ */
+const KBKDF_LABEL: &[u8] = b"aes_s container";
+
+/**
+ * How the content is encrypted
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    AesGcmStream,
+}
+
+/**
+ * How the key of the stream is obtained from the key given to the reader or
+ * the writer
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    /**
+     * The given key is the key of the stream
+     */
+    None,
+    /**
+     * KBKDF in counter mode with CMAC, keyed with the given key, with the salt
+     * as context
+     */
+    Kbkdf,
+    /**
+     * PBKDF2-HMAC-SHA256 of a password with the salt, with
+     * MIN_PBKDF2_ITERATIONS to MAX_PBKDF2_ITERATIONS iterations
+     */
+    Pbkdf2 { iterations: u32 },
+}
+
+/**
+ * Everything the header holds
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub algorithm: Algorithm,
+    pub key_size: KeySize,
+    pub kdf: Kdf,
+    pub salt: [u8; SALT_LEN],
+    pub nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    pub chunk_len: u32,
+}
+
+/**
+ * Reasons for which a header is refused
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerError {
+    /**
+     * The data is shorter than a header
+     */
+    Truncated,
+    /**
+     * The data doesn't start with MAGIC, it is not an aes_s file
+     */
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownAlgorithm(u8),
+    InvalidKeySize(u8),
+    UnknownKdf(u8),
+    /**
+     * The iterations or the salt don't fit the KDF
+     */
+    InvalidKdfParams,
+    /**
+     * The chunk length is 0 or more than MAX_CHUNK_LEN
+     */
+    InvalidChunkLen(u32),
+    /**
+     * The key given to open or write a file is not as long as the header says,
+     * which is required when there is no KDF, or a key was given for a file
+     * that needs a password or the other way round
+     */
+    KeyMismatch,
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerError::Truncated => write!(f, "header truncated"),
+            ContainerError::BadMagic => write!(f, "not an aes_s file"),
+            ContainerError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {}", version)
+            }
+            ContainerError::UnknownAlgorithm(id) => write!(f, "unknown algorithm {}", id),
+            ContainerError::InvalidKeySize(size) => write!(f, "invalid key size {}", size),
+            ContainerError::UnknownKdf(id) => write!(f, "unknown KDF {}", id),
+            ContainerError::InvalidKdfParams => write!(f, "invalid KDF parameters"),
+            ContainerError::InvalidChunkLen(length) => {
+                write!(f, "invalid chunk length {}", length)
+            }
+            ContainerError::KeyMismatch => write!(f, "the key doesn't match the header"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ContainerError {}
+
+impl Header {
+    /**
+     * A header for a file encrypted directly with a key of the given size
+     */
+    pub fn new(key_size: KeySize, nonce_prefix: &[u8; NONCE_PREFIX_LEN]) -> Header {
+        Header {
+            algorithm: Algorithm::AesGcmStream,
+            key_size,
+            kdf: Kdf::None,
+            salt: [0; SALT_LEN],
+            nonce_prefix: *nonce_prefix,
+            chunk_len: DEFAULT_CHUNK_LEN as u32,
+        }
+    }
+
+    /**
+     * A header for a file whose key is derived from a master key and a random
+     * salt, with a random nonce prefix
+     */
+    #[cfg(feature = "std")]
+    pub fn generate(key_size: KeySize, kdf: Kdf) -> Result<Header, Error> {
+        let mut header = Header::new(key_size, &[0; NONCE_PREFIX_LEN]);
+        header.kdf = kdf;
+        if kdf != Kdf::None {
+            crate::random::fill(&mut header.salt)?;
+        }
+        header.nonce_prefix = crate::stream::generate_prefix()?;
+        Ok(header)
+    }
+
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..8].copy_from_slice(&MAGIC);
+        bytes[8] = VERSION;
+        bytes[9] = match self.algorithm {
+            Algorithm::AesGcmStream => 1,
+        };
+        bytes[10] = self.key_size.bytes() as u8;
+        let (kdf, iterations) = match self.kdf {
+            Kdf::None => (0, 0),
+            Kdf::Kbkdf => (1, 0),
+            Kdf::Pbkdf2 { iterations } => (2, iterations),
+        };
+        bytes[11] = kdf;
+        bytes[12..16].copy_from_slice(&iterations.to_be_bytes());
+        bytes[16..32].copy_from_slice(&self.salt);
+        bytes[32..39].copy_from_slice(&self.nonce_prefix);
+        bytes[39..43].copy_from_slice(&self.chunk_len.to_be_bytes());
+        bytes
+    }
+
+    /**
+     * Parses the header at the start of bytes, what follows it is ignored
+     */
+    pub fn decode(bytes: &[u8]) -> Result<Header, ContainerError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(ContainerError::Truncated);
+        }
+        if bytes[..8] != MAGIC {
+            return Err(ContainerError::BadMagic);
+        }
+        if bytes[8] != VERSION {
+            return Err(ContainerError::UnsupportedVersion(bytes[8]));
+        }
+        let algorithm = match bytes[9] {
+            1 => Algorithm::AesGcmStream,
+            id => return Err(ContainerError::UnknownAlgorithm(id)),
+        };
+        let key_size = match bytes[10] {
+            16 => KeySize::Aes128,
+            24 => KeySize::Aes192,
+            32 => KeySize::Aes256,
+            size => return Err(ContainerError::InvalidKeySize(size)),
+        };
+        let iterations = read_u32(&bytes[12..16]);
+        let kdf = match bytes[11] {
+            0 => Kdf::None,
+            1 => Kdf::Kbkdf,
+            2 => Kdf::Pbkdf2 { iterations },
+            id => return Err(ContainerError::UnknownKdf(id)),
+        };
+        let mut salt = [0; SALT_LEN];
+        salt.copy_from_slice(&bytes[16..32]);
+        let params_fit = match kdf {
+            Kdf::None => iterations == 0 && salt == [0; SALT_LEN],
+            Kdf::Kbkdf => iterations == 0,
+            Kdf::Pbkdf2 { .. } => {
+                (MIN_PBKDF2_ITERATIONS..=MAX_PBKDF2_ITERATIONS).contains(&iterations)
+            }
+        };
+        if !params_fit {
+            return Err(ContainerError::InvalidKdfParams);
+        }
+        let mut nonce_prefix = [0; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&bytes[32..39]);
+        let chunk_len = read_u32(&bytes[39..43]);
+        if chunk_len == 0 || chunk_len > MAX_CHUNK_LEN {
+            return Err(ContainerError::InvalidChunkLen(chunk_len));
+        }
+        Ok(Header {
+            algorithm,
+            key_size,
+            kdf,
+            salt,
+            nonce_prefix,
+            chunk_len,
+        })
+    }
+
+    /**
+     * The key of the stream, from the key given by the caller. Fails with
+     * ContainerError::KeyMismatch if the header asks for a password.
+     */
+    pub fn stream_key(&self, key: &Key) -> Result<Key, Error> {
+        match self.kdf {
+            Kdf::None if key.size() == self.key_size => Ok(*key),
+            Kdf::None | Kdf::Pbkdf2 { .. } => Err(ContainerError::KeyMismatch.into()),
+            Kdf::Kbkdf => {
+                let params = KdfParams {
+                    mode: Mode::Counter,
+                    counter: Some(Counter {
+                        bits: 32,
+                        position: CounterPosition::BeforeFixedData,
+                    }),
+                    fixed_input: FixedInput::LabelContext {
+                        label: KBKDF_LABEL,
+                        context: &self.salt,
+                    },
+                };
+                Ok(Kbkdf::new(key).derive_key(&params, self.key_size)?)
+            }
+        }
+    }
+
+    /**
+     * The key of the stream, from the password given by the caller. Fails
+     * with ContainerError::KeyMismatch if the header asks for a key.
+     */
+    pub fn password_key(&self, password: &[u8]) -> Result<Key, Error> {
+        match self.kdf {
+            Kdf::Pbkdf2 { iterations } => Ok(pbkdf2::derive_key(
+                password,
+                &self.salt,
+                iterations,
+                self.key_size,
+            )?),
+            Kdf::None | Kdf::Kbkdf => Err(ContainerError::KeyMismatch.into()),
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/**
+ * Writes the header and then encrypts what is written to it. finish must be
+ * called at the end.
+ */
+#[cfg(feature = "std")]
+pub struct ContainerWriter<W: Write> {
+    stream: StreamWriter<W>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> ContainerWriter<W> {
+    pub fn new(inner: W, key: &Key, header: &Header) -> Result<ContainerWriter<W>, Error> {
+        Self::start(inner, header, |header| header.stream_key(key))
+    }
+
+    /**
+     * For a header with Kdf::Pbkdf2
+     */
+    pub fn with_password(
+        inner: W,
+        password: &[u8],
+        header: &Header,
+    ) -> Result<ContainerWriter<W>, Error> {
+        Self::start(inner, header, |header| header.password_key(password))
+    }
+
+    fn start<F>(mut inner: W, header: &Header, stream_key: F) -> Result<ContainerWriter<W>, Error>
+    where
+        F: FnOnce(&Header) -> Result<Key, Error>,
+    {
+        let encoded = header.encode();
+        // the fields are public, so they could hold what a reader would refuse
+        Header::decode(&encoded)?;
+        let stream_key = stream_key(header)?;
+        inner.write_all(&encoded)?;
+        let stream = StreamWriter::with_aad(
+            inner,
+            &stream_key,
+            &header.nonce_prefix,
+            header.chunk_len as usize,
+            &encoded,
+        )?;
+        Ok(ContainerWriter { stream })
+    }
+
+    /**
+     * Writes the last chunk and gives back the underlying writer
+     */
+    pub fn finish(self) -> Result<W, Error> {
+        self.stream.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for ContainerWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.stream.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/**
+ * Reads the header of a file and then decrypts its content, see StreamReader
+ */
+#[cfg(feature = "std")]
+pub struct ContainerReader<R: Read> {
+    header: Header,
+    stream: StreamReader<R>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ContainerReader<R> {
+    /**
+     * Reads and parses the header. A header that has been tampered with is
+     * only detected when the first chunk is read.
+     */
+    pub fn new(inner: R, key: &Key) -> Result<ContainerReader<R>, Error> {
+        Self::start(inner, |header| header.stream_key(key))
+    }
+
+    /**
+     * For a file whose header has Kdf::Pbkdf2
+     */
+    pub fn with_password(inner: R, password: &[u8]) -> Result<ContainerReader<R>, Error> {
+        Self::start(inner, |header| header.password_key(password))
+    }
+
+    fn start<F>(mut inner: R, stream_key: F) -> Result<ContainerReader<R>, Error>
+    where
+        F: FnOnce(&Header) -> Result<Key, Error>,
+    {
+        let mut encoded = [0; HEADER_LEN];
+        inner.read_exact(&mut encoded).map_err(|error| {
+            if error.kind() == io::ErrorKind::UnexpectedEof {
+                Error::from(ContainerError::Truncated)
+            } else {
+                error.into()
+            }
+        })?;
+        let header = Header::decode(&encoded)?;
+        let stream = StreamReader::with_aad(
+            inner,
+            &stream_key(&header)?,
+            &header.nonce_prefix,
+            header.chunk_len as usize,
+            &encoded,
+        )?;
+        Ok(ContainerReader { header, stream })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for ContainerReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buffer)
+    }
+}
+
+/**
+ * Encrypts a whole message into a file
+ */
+#[cfg(feature = "std")]
+pub fn encrypt(key: &Key, header: &Header, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut writer = ContainerWriter::new(Vec::new(), key, header)?;
+    writer.write_all(plaintext)?;
+    writer.finish()
+}
+
+/**
+ * Decrypts a whole file, returning nothing unless all of it is authentic
+ */
+#[cfg(feature = "std")]
+pub fn decrypt(key: &Key, file: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut plaintext = Vec::new();
+    ContainerReader::new(file, key)?.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
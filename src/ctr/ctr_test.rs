@@ -0,0 +1,61 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod ctr_test {
+    use crate::cipher::Key;
+    use crate::ctr::Ctr;
+
+    const KEY: Key = Key::Aes128([0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c]);
+
+    const PLAIN: u128 = 0x6bc1bee22e409f96e93d7e117393172a;
+
+    /**
+     * F.5.1 of SP 800-38A, on the four blocks of the example
+     */
+    #[test]
+    fn it_matches_sp_800_38a() {
+        let plain = [
+            PLAIN,
+            0xae2d8a571e03ac9c9eb76fac45af8e51,
+            0x30c81c46a35ce411e5fbc1191a0a52ef,
+            0xf69f2445df4f9b17ad2b417be66c3710,
+        ];
+        let cipher = [
+            0x874d6191b620e3261bef6864990db6ce_u128,
+            0x9806f66b7970fdff8617187bb9fffdff,
+            0x5ae4df3edbd5d35e5b4f09020db03eab,
+            0x1e031dda2fbe03d1792170a0f3009cee,
+        ];
+        let iv = 0xf0f1f2f3f4f5f6f7f8f9fafbfcfdfeff_u128.to_be_bytes();
+        let mut data = [0; 64];
+        for (chunk, block) in data.chunks_mut(16).zip(plain.iter()) {
+            chunk.copy_from_slice(&block.to_be_bytes());
+        }
+        let mut ctr = Ctr::new(&KEY, &iv);
+        for piece in data.chunks_mut(7) {
+            ctr.apply_keystream(piece);
+        }
+        for (chunk, block) in data.chunks(16).zip(cipher.iter()) {
+            assert_eq!(chunk, block.to_be_bytes());
+        }
+        Ctr::new(&KEY, &iv).apply_keystream(&mut data);
+        assert_eq!(data[..16], PLAIN.to_be_bytes());
+    }
+
+    /**
+     * The counter wraps around to zero, expected keystream computed with
+     * the CTR of another library
+     */
+    #[test]
+    fn it_wraps_the_counter() {
+        let mut data = [0; 32];
+        Ctr::new(&KEY, &[0xff; 16]).apply_keystream(&mut data);
+        assert_eq!(
+            data[..16],
+            0x8af2860142f786f409307c1a3f7eaaac_u128.to_be_bytes()
+        );
+        assert_eq!(
+            data[16..],
+            0x7df76b0c1ab899b33e42f047b91b546f_u128.to_be_bytes()
+        );
+    }
+}
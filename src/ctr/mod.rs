@@ -0,0 +1,58 @@
+/*!
+ * CTR mode as described in NIST SP 800-38A: the cipher of a counter block,
+ * incremented after each block, is xored with the data. Encryption and
+ * decryption are the same operation.
+ *
+ * The whole counter block is incremented as a 128 bits big endian integer,
+ * as OpenSSL does, so the IV is the first counter block. The same IV must never
+ * be used twice with the same key.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+
+mod ctr_test;
+
+/**
+ * The state of a CTR stream: the next counter block and what is left
+ * of the current block of keystream
+ */
+pub struct Ctr<C = Encryptor> {
+    encryptor: C,
+    counter: u128,
+    keystream: [u8; 16],
+    used: usize,
+}
+
+impl Ctr {
+    pub fn new(key: &Key, iv: &[u8; 16]) -> Ctr {
+        Self::from_encryptor(Encryptor::from_key(key), iv)
+    }
+}
+
+impl<C: BlockEncrypt> Ctr<C> {
+    pub fn from_encryptor(encryptor: C, iv: &[u8; 16]) -> Ctr<C> {
+        Ctr {
+            encryptor,
+            counter: u128::from_be_bytes(*iv),
+            keystream: [0; 16],
+            used: 16,
+        }
+    }
+
+    /**
+     * Encrypts or decrypts data in place. The data can be given in pieces of
+     * any length, the keystream continues where the previous piece stopped.
+     */
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.used == 16 {
+                self.keystream = self.counter.to_be_bytes();
+                self.encryptor.encrypt_block(&mut self.keystream);
+                self.counter = self.counter.wrapping_add(1);
+                self.used = 0;
+            }
+            *byte ^= self.keystream[self.used];
+            self.used += 1;
+        }
+    }
+}
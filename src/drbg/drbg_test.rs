@@ -0,0 +1,134 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod drbg_test {
+    use crate::cipher::KeySize;
+    use crate::drbg::{CtrDrbg, DrbgError, MAX_BYTES_PER_REQUEST};
+    use std::vec;
+    use std::vec::Vec;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /**
+     * Runs every COUNT of a file in the CAVP DRBG format. The operations are
+     * done in the order in which their inputs appear: the generator is instantiated
+     * after the personalization string, reseeded after the reseed additional input
+     * and asked for the returned bits after each additional input, or after each
+     * prediction resistance entropy input when prediction resistance is on.
+     * Returns the number of checked answers.
+     */
+    fn run_vector_file(file: &str) -> usize {
+        let mut key_size = KeySize::Aes128;
+        let mut derivation_function = false;
+        let mut prediction_resistance = false;
+        let mut drbg = None;
+        let (mut entropy, mut nonce, mut additional) = (vec![], vec![], vec![]);
+        let mut output = vec![];
+        let mut checked = 0;
+        for line in file.lines().map(|line| line.trim()) {
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section.trim_end_matches(']');
+                if section.starts_with("AES-") {
+                    key_size = match &section[4..7] {
+                        "128" => KeySize::Aes128,
+                        "192" => KeySize::Aes192,
+                        _ => KeySize::Aes256,
+                    };
+                    derivation_function = section.ends_with("use df");
+                } else if let Some(value) = section.strip_prefix("PredictionResistance = ") {
+                    prediction_resistance = value == "True";
+                } else if let Some(bits) = section.strip_prefix("ReturnedBitsLen = ") {
+                    output = vec![0; bits.parse::<usize>().unwrap() / 8];
+                }
+                continue;
+            }
+            let mut parts = line.splitn(2, '=').map(|part| part.trim());
+            let (name, value) = (parts.next().unwrap(), parts.next().unwrap());
+            if name == "COUNT" {
+                drbg = None;
+                continue;
+            }
+            let value = from_hex(value);
+            match name {
+                "EntropyInput" | "EntropyInputReseed" => entropy = value,
+                "Nonce" => nonce = value,
+                "PersonalizationString" => {
+                    drbg = Some(
+                        CtrDrbg::new(key_size, derivation_function, &entropy, &nonce, &value)
+                            .unwrap(),
+                    )
+                }
+                "AdditionalInputReseed" => {
+                    drbg.as_mut().unwrap().reseed(&entropy, &value).unwrap();
+                }
+                "AdditionalInput" if prediction_resistance => additional = value,
+                "AdditionalInput" => drbg
+                    .as_mut()
+                    .unwrap()
+                    .generate(&mut output, &value)
+                    .unwrap(),
+                "EntropyInputPR" => drbg
+                    .as_mut()
+                    .unwrap()
+                    .generate_with_prediction_resistance(&mut output, &value, &additional)
+                    .unwrap(),
+                "ReturnedBits" => {
+                    assert_eq!(output, value);
+                    checked += 1;
+                }
+                _ => panic!("unknown field {}", name),
+            }
+        }
+        checked
+    }
+
+    #[test]
+    fn it_matches_known_answers() {
+        let checked = run_vector_file(include_str!("CTR_DRBG.rsp"));
+        assert_eq!(checked, 49);
+    }
+
+    #[test]
+    fn it_counts_requests_since_reseed() {
+        let mut drbg = CtrDrbg::new(KeySize::Aes256, true, &[1; 32], &[2; 16], &[]).unwrap();
+        let mut output = [0; 16];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, b"additional").unwrap();
+        assert_eq!(drbg.reseed_counter(), 3);
+        drbg.reseed(&[3; 32], &[]).unwrap();
+        assert_eq!(drbg.reseed_counter(), 1);
+    }
+
+    #[test]
+    fn it_refuses_wrong_lengths() {
+        assert_eq!(
+            CtrDrbg::new(KeySize::Aes192, true, &[0; 23], &[0; 12], &[]).err(),
+            Some(DrbgError::EntropyLength)
+        );
+        assert_eq!(
+            CtrDrbg::new(KeySize::Aes128, false, &[0; 16], &[], &[]).err(),
+            Some(DrbgError::EntropyLength)
+        );
+        assert_eq!(
+            CtrDrbg::new(KeySize::Aes128, false, &[0; 32], &[], &[0; 33]).err(),
+            Some(DrbgError::InputTooLong)
+        );
+        let mut drbg = CtrDrbg::new(KeySize::Aes128, false, &[0; 32], &[], &[]).unwrap();
+        let mut output = vec![0; MAX_BYTES_PER_REQUEST + 1];
+        assert_eq!(
+            drbg.generate(&mut output, &[]),
+            Err(DrbgError::RequestTooLarge)
+        );
+        assert_eq!(
+            drbg.generate(&mut output[..16], &[0; 33]),
+            Err(DrbgError::InputTooLong)
+        );
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+ * CTR_DRBG, the deterministic random bit generator built on a block cipher
+ * described in section 10.2.1 of NIST SP 800-90A, with AES as the block cipher.
+ *
+ * The generator never gathers entropy on its own: the entropy input is passed
+ * to new, reseed and generate_with_prediction_resistance by the caller, so the
+ * same generator works on devices without an operating system and can be checked
+ * against known answers.
+ */
+use crate::cipher::cbc_mac::CbcMac;
+use crate::cipher::{Encryptor, KeySize};
+use core::fmt;
+
+mod drbg_test;
+
+/**
+ * Number of generate requests after which the standard requires a reseed
+ */
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/**
+ * Maximum number of bytes returned by a single generate request (2^19 bits)
+ */
+pub const MAX_BYTES_PER_REQUEST: usize = 1 << 16;
+
+/**
+ * Length of the longest seed, the one of AES-256: key length plus block length
+ */
+const MAX_SEED_LEN: usize = 32 + 16;
+
+/**
+ * Key used by the derivation function to compress its input, only the first
+ * key length bytes of it are used
+ */
+const DF_KEY: [u8; 32] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+];
+
+/**
+ * Reasons for which the generator refuses a request
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrbgError {
+    /**
+     * The entropy input is too short or, without the derivation function,
+     * not exactly as long as the seed
+     */
+    EntropyLength,
+    /**
+     * Without the derivation function the personalization string and the
+     * additional input can't be longer than the seed
+     */
+    InputTooLong,
+    /**
+     * More than MAX_BYTES_PER_REQUEST bytes were requested at once
+     */
+    RequestTooLarge,
+    /**
+     * The generator was used RESEED_INTERVAL times since the last reseed
+     */
+    ReseedRequired,
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrbgError::EntropyLength => write!(f, "wrong entropy input length"),
+            DrbgError::InputTooLong => write!(f, "input longer than the seed length"),
+            DrbgError::RequestTooLarge => write!(f, "too many bytes requested at once"),
+            DrbgError::ReseedRequired => write!(f, "the generator must be reseeded"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DrbgError {}
+
+/**
+ * The internal state of CTR_DRBG: the key and the counter block V, which
+ * change after every request, and the number of requests since the last reseed.
+ */
+pub struct CtrDrbg {
+    key_size: KeySize,
+    derivation_function: bool,
+    encryptor: Encryptor,
+    v: [u8; 16],
+    reseed_counter: u64,
+}
+
+impl CtrDrbg {
+    /**
+     * Instantiates the generator. With the derivation function the entropy input
+     * must be at least as long as the key and it is mixed with the nonce and the
+     * personalization string, which can have any length. Without it the entropy
+     * input must be exactly key length + 16 bytes of full entropy, the nonce is
+     * not used and the personalization string can be at most as long as the entropy.
+     */
+    pub fn new(
+        key_size: KeySize,
+        derivation_function: bool,
+        entropy_input: &[u8],
+        nonce: &[u8],
+        personalization_string: &[u8],
+    ) -> Result<CtrDrbg, DrbgError> {
+        let mut drbg = CtrDrbg {
+            key_size,
+            derivation_function,
+            encryptor: Encryptor::from_key_bytes(&[0; 32][..key_size.bytes()]),
+            v: [0; 16],
+            reseed_counter: 1,
+        };
+        drbg.check_entropy(entropy_input)?;
+        let mut seed = [0; MAX_SEED_LEN];
+        let seed = &mut seed[..drbg.seed_len()];
+        if derivation_function {
+            drbg.derive(&[entropy_input, nonce, personalization_string], seed);
+        } else {
+            drbg.pad(personalization_string, seed)?;
+            xor(seed, entropy_input);
+        }
+        drbg.update(seed);
+        Ok(drbg)
+    }
+
+    /**
+     * Mixes new entropy and an optional additional input into the state, with
+     * the same length constraints of new. Resets the reseed counter.
+     */
+    pub fn reseed(
+        &mut self,
+        entropy_input: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.check_entropy(entropy_input)?;
+        let mut seed = [0; MAX_SEED_LEN];
+        let seed = &mut seed[..self.seed_len()];
+        if self.derivation_function {
+            self.derive(&[entropy_input, additional_input], seed);
+        } else {
+            self.pad(additional_input, seed)?;
+            xor(seed, entropy_input);
+        }
+        self.update(seed);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /**
+     * Fills output with pseudo random bytes. The additional input, which can be
+     * empty, is mixed into the state before and after the bytes are produced.
+     */
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        let mut additional = [0; MAX_SEED_LEN];
+        let additional = &mut additional[..self.seed_len()];
+        if !additional_input.is_empty() {
+            if self.derivation_function {
+                self.derive(&[additional_input], additional);
+            } else {
+                self.pad(additional_input, additional)?;
+            }
+            self.update(additional);
+        }
+        for chunk in output.chunks_mut(16) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(additional);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /**
+     * Generate with prediction resistance: the state is first reseeded with the
+     * given entropy input and additional input, so that the output can't be
+     * predicted even by someone who learned the previous state.
+     */
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        output: &mut [u8],
+        entropy_input: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        self.reseed(entropy_input, additional_input)?;
+        self.generate(output, &[])
+    }
+
+    /**
+     * Number of generate requests since the last reseed, plus one
+     */
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn seed_len(&self) -> usize {
+        self.key_size.bytes() + 16
+    }
+
+    fn check_entropy(&self, entropy_input: &[u8]) -> Result<(), DrbgError> {
+        let valid = if self.derivation_function {
+            entropy_input.len() >= self.key_size.bytes()
+        } else {
+            entropy_input.len() == self.seed_len()
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(DrbgError::EntropyLength)
+        }
+    }
+
+    /**
+     * Copies input to the beginning of seed, which is already filled with zeros
+     */
+    fn pad(&self, input: &[u8], seed: &mut [u8]) -> Result<(), DrbgError> {
+        if input.len() > seed.len() {
+            return Err(DrbgError::InputTooLong);
+        }
+        seed[..input.len()].copy_from_slice(input);
+        Ok(())
+    }
+
+    /**
+     * Increments V as a big endian number and ciphers it with the current key
+     */
+    fn next_block(&mut self) -> [u8; 16] {
+        self.v = u128::from_be_bytes(self.v).wrapping_add(1).to_be_bytes();
+        let mut block = self.v;
+        self.encryptor.cipher(&mut block);
+        block
+    }
+
+    /**
+     * CTR_DRBG_Update: produces a new key and V from seed length bytes of
+     * output of the generator xored with provided_data
+     */
+    fn update(&mut self, provided_data: &[u8]) {
+        let mut temp = [0; MAX_SEED_LEN];
+        let temp = &mut temp[..self.seed_len()];
+        for chunk in temp.chunks_mut(16) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        xor(temp, provided_data);
+        let (key, v) = temp.split_at(self.key_size.bytes());
+        self.encryptor = Encryptor::from_key_bytes(key);
+        self.v.copy_from_slice(v);
+    }
+
+    /**
+     * Block_Cipher_df: compresses the concatenation of inputs into output, which
+     * is at most seed length bytes long. The input string is never concatenated
+     * in memory, it is fed to BCC, a CBC-MAC, one piece at a time.
+     */
+    fn derive(&self, inputs: &[&[u8]], output: &mut [u8]) {
+        let key_len = self.key_size.bytes();
+        let input_len: usize = inputs.iter().map(|input| input.len()).sum();
+        let encryptor = Encryptor::from_key_bytes(&DF_KEY[..key_len]);
+        let mut temp = [0; MAX_SEED_LEN];
+        for (i, chunk) in temp[..key_len + 16].chunks_mut(16).enumerate() {
+            let mut bcc = CbcMac::new(&encryptor);
+            bcc.absorb(&(i as u32).to_be_bytes());
+            bcc.absorb(&[0; 12]);
+            bcc.absorb(&(input_len as u32).to_be_bytes());
+            bcc.absorb(&(output.len() as u32).to_be_bytes());
+            for input in inputs {
+                bcc.absorb(input);
+            }
+            bcc.absorb(&[0x80]);
+            let block = bcc.finish();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        let encryptor = Encryptor::from_key_bytes(&temp[..key_len]);
+        let mut x = [0; 16];
+        x.copy_from_slice(&temp[key_len..key_len + 16]);
+        for chunk in output.chunks_mut(16) {
+            encryptor.cipher(&mut x);
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+    }
+}
+
+fn xor(target: &mut [u8], data: &[u8]) {
+    for (byte, other) in target.iter_mut().zip(data.iter()) {
+        *byte ^= other;
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod error_test {
+    use crate::drbg::DrbgError;
+    use crate::error::Error;
+    use crate::kbkdf::KdfError;
+    use std::string::ToString;
+
+    fn derive() -> Result<(), Error> {
+        Err(KdfError::OutputLength)?;
+        Ok(())
+    }
+
+    #[test]
+    fn it_wraps_module_errors() {
+        assert_eq!(derive(), Err(Error::Kdf(KdfError::OutputLength)));
+        assert_eq!(
+            Error::from(DrbgError::ReseedRequired),
+            Error::Drbg(DrbgError::ReseedRequired)
+        );
+    }
+
+    #[test]
+    fn it_displays() {
+        assert_eq!(
+            Error::InvalidKeyLength(20).to_string(),
+            "invalid key length: 20 bytes, expected 16, 24 or 32"
+        );
+        assert_eq!(
+            Error::Drbg(DrbgError::ReseedRequired).to_string(),
+            "DRBG: the generator must be reseeded"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_has_a_source() {
+        use std::error::Error as _;
+        assert!(Error::AuthenticationFailed.source().is_none());
+        assert_eq!(
+            Error::Kdf(KdfError::CounterWidth)
+                .source()
+                .unwrap()
+                .to_string(),
+            KdfError::CounterWidth.to_string()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+/*!
+ * The error type shared by the whole crate.
+ *
+ * The modules that can fail in ways of their own (the DRBG, FPE, the KDFs,
+ * GMAC, the file format and the random bytes) keep their own error types, which say exactly
+ * what went wrong, and Error wraps them so that a caller using more than one
+ * module can propagate all of them with `?`. Everything else that depends on
+ * the input, like the length of a key or of a ciphertext, fails with one of
+ * the other variants instead of panicking.
+ */
+use crate::container::ContainerError;
+use crate::drbg::DrbgError;
+use crate::fpe::FpeError;
+use crate::gmac::GmacError;
+use crate::kbkdf::KdfError;
+use crate::pbkdf2::Pbkdf2Error;
+#[cfg(feature = "std")]
+use crate::random::RandomError;
+use core::fmt;
+
+mod error_test;
+
+/**
+ * Anything that can go wrong in aes_s
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /**
+     * A key given as bytes is not 16, 24 or 32 bytes long, holds the length
+     */
+    InvalidKeyLength(usize),
+    /**
+     * An input, like a ciphertext that should be made of whole blocks or an
+     * IV, doesn't have a length that the operation accepts
+     */
+    InvalidLength,
+    /**
+     * The padding at the end of a decrypted message is malformed
+     */
+    InvalidPadding,
+    /**
+     * The number of rounds of a reduced cipher is not between 1 and the one
+     * of the standard, which is held here
+     */
+    InvalidRounds(usize),
+    /**
+     * The tag of an authenticated message doesn't match: the message, the
+     * associated data or the key are not the ones the tag was made for
+     */
+    AuthenticationFailed,
+    /**
+     * A nonce would be used twice with the same key, for example because a
+     * counter of nonces has run out
+     */
+    NonceReuse,
+    /**
+     * The input doesn't start with the magic bytes of the format it is read
+     * as, like a file without the "Salted__" of OpenSSL
+     */
+    UnknownFormat,
+    /**
+     * The key is one that the mode refuses, like an XTS key whose two halves
+     * are the same
+     */
+    WeakKey,
+    Drbg(DrbgError),
+    Fpe(FpeError),
+    Kdf(KdfError),
+    Pbkdf2(Pbkdf2Error),
+    Gmac(GmacError),
+    Container(ContainerError),
+    #[cfg(feature = "std")]
+    Random(RandomError),
+    /**
+     * Reading or writing failed, see the io module
+     */
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidKeyLength(length) => write!(
+                f,
+                "invalid key length: {} bytes, expected 16, 24 or 32",
+                length
+            ),
+            Error::InvalidLength => write!(f, "invalid input length"),
+            Error::InvalidPadding => write!(f, "invalid padding"),
+            Error::InvalidRounds(max_rounds) => write!(
+                f,
+                "the number of rounds must be between 1 and {}",
+                max_rounds
+            ),
+            Error::AuthenticationFailed => write!(f, "authentication failed"),
+            Error::NonceReuse => write!(f, "a nonce would be reused"),
+            Error::UnknownFormat => write!(f, "unknown format"),
+            Error::WeakKey => write!(f, "weak key"),
+            Error::Drbg(error) => write!(f, "DRBG: {}", error),
+            Error::Fpe(error) => write!(f, "FPE: {}", error),
+            Error::Kdf(error) => write!(f, "KDF: {}", error),
+            Error::Pbkdf2(error) => write!(f, "PBKDF2: {}", error),
+            Error::Gmac(error) => write!(f, "GMAC: {}", error),
+            Error::Container(error) => write!(f, "file format: {}", error),
+            #[cfg(feature = "std")]
+            Error::Random(error) => write!(f, "random bytes: {}", error),
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "I/O error: {:?}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Drbg(error) => Some(error),
+            Error::Fpe(error) => Some(error),
+            Error::Kdf(error) => Some(error),
+            Error::Pbkdf2(error) => Some(error),
+            Error::Gmac(error) => Some(error),
+            Error::Container(error) => Some(error),
+            Error::Random(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DrbgError> for Error {
+    fn from(error: DrbgError) -> Error {
+        Error::Drbg(error)
+    }
+}
+
+impl From<FpeError> for Error {
+    fn from(error: FpeError) -> Error {
+        Error::Fpe(error)
+    }
+}
+
+impl From<KdfError> for Error {
+    fn from(error: KdfError) -> Error {
+        Error::Kdf(error)
+    }
+}
+
+impl From<Pbkdf2Error> for Error {
+    fn from(error: Pbkdf2Error) -> Error {
+        Error::Pbkdf2(error)
+    }
+}
+
+impl From<GmacError> for Error {
+    fn from(error: GmacError) -> Error {
+        Error::Gmac(error)
+    }
+}
+
+impl From<ContainerError> for Error {
+    fn from(error: ContainerError) -> Error {
+        Error::Container(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<RandomError> for Error {
+    fn from(error: RandomError) -> Error {
+        Error::Random(error)
+    }
+}
+
+/**
+ * The readers and writers of the io module can only return io::Error, so they
+ * wrap the Error inside it. This gets it back, any other error becomes Error::Io.
+ */
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        match error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<Error>())
+        {
+            Some(inner) => *inner,
+            None => Error::Io(error.kind()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> std::io::Error {
+        use std::io::ErrorKind;
+        let kind = match error {
+            Error::Io(kind) => return kind.into(),
+            Error::AuthenticationFailed
+            | Error::InvalidPadding
+            | Error::InvalidLength
+            | Error::UnknownFormat
+            | Error::Container(_) => ErrorKind::InvalidData,
+            _ => ErrorKind::InvalidInput,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod fpe_test {
+    use crate::cipher::Key;
+    use crate::fpe::{Ff1, Ff3_1, FpeError};
+    use std::string::String;
+    use std::vec::Vec;
+
+    const FF1_KEY_128: Key = Key::Aes128([0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c]);
+    const FF1_KEY_192: Key = Key::Aes192([
+        0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c, 0xef4359d8, 0xd580aa4f,
+    ]);
+    const FF1_KEY_256: Key = Key::Aes256([
+        0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c, 0xef4359d8, 0xd580aa4f, 0x7f036d6f,
+        0x04fc6a94,
+    ]);
+    const FF3_KEY_128: Key = Key::Aes128([0xef4359d8, 0xd580aa4f, 0x7f036d6f, 0x04fc6a94]);
+    const FF3_KEY_192: Key = Key::Aes192([
+        0xef4359d8, 0xd580aa4f, 0x7f036d6f, 0x04fc6a94, 0x2b7e1516, 0x28aed2a6,
+    ]);
+    const FF3_KEY_256: Key = Key::Aes256([
+        0xef4359d8, 0xd580aa4f, 0x7f036d6f, 0x04fc6a94, 0x2b7e1516, 0x28aed2a6, 0xabf71588,
+        0x09cf4f3c,
+    ]);
+
+    /**
+     * The samples write the numerals with the digits followed by the lowercase letters
+     */
+    fn numerals(text: &str) -> Vec<u16> {
+        text.chars()
+            .map(|c| c.to_digit(36).unwrap() as u16)
+            .collect()
+    }
+
+    fn text(numerals: &[u16]) -> String {
+        numerals
+            .iter()
+            .map(|n| std::char::from_digit(*n as u32, 36).unwrap())
+            .collect()
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /**
+     * The FF1 samples published by NIST with SP 800-38G
+     */
+    #[test]
+    fn it_matches_ff1_samples() {
+        let samples = [
+            (FF1_KEY_128, 10, "", "0123456789", "2433477484"),
+            (
+                FF1_KEY_128,
+                10,
+                "39383736353433323130",
+                "0123456789",
+                "6124200773",
+            ),
+            (
+                FF1_KEY_128,
+                36,
+                "3737373770717273373737",
+                "0123456789abcdefghi",
+                "a9tv40mll9kdu509eum",
+            ),
+            (FF1_KEY_192, 10, "", "0123456789", "2830668132"),
+            (
+                FF1_KEY_192,
+                10,
+                "39383736353433323130",
+                "0123456789",
+                "2496655549",
+            ),
+            (
+                FF1_KEY_192,
+                36,
+                "3737373770717273373737",
+                "0123456789abcdefghi",
+                "xbj3kv35jrawxv32ysr",
+            ),
+            (FF1_KEY_256, 10, "", "0123456789", "6657667009"),
+            (
+                FF1_KEY_256,
+                10,
+                "39383736353433323130",
+                "0123456789",
+                "1001623463",
+            ),
+            (
+                FF1_KEY_256,
+                36,
+                "3737373770717273373737",
+                "0123456789abcdefghi",
+                "xs8a0azh2avyalyzuwd",
+            ),
+        ];
+        for (key, radix, tweak, plain, expected) in samples.iter() {
+            let ff1 = Ff1::new(key, *radix).unwrap();
+            let tweak = from_hex(tweak);
+            let mut content = numerals(plain);
+            ff1.encrypt(&tweak, &mut content).unwrap();
+            assert_eq!(text(&content), *expected);
+            ff1.decrypt(&tweak, &mut content).unwrap();
+            assert_eq!(text(&content), *plain);
+        }
+    }
+
+    /**
+     * The samples published by NIST for the original FF3, which had a 64 bits
+     * tweak split in two halves. The rounds are the same of FF3-1.
+     */
+    #[test]
+    fn it_matches_ff3_samples() {
+        let samples = [
+            (
+                FF3_KEY_128,
+                10,
+                "d8e7920afa330a73",
+                "890121234567890000",
+                "750918814058654607",
+            ),
+            (
+                FF3_KEY_128,
+                10,
+                "9a768a92f60e12d8",
+                "890121234567890000",
+                "018989839189395384",
+            ),
+            (
+                FF3_KEY_128,
+                10,
+                "d8e7920afa330a73",
+                "89012123456789000000789000000",
+                "48598367162252569629397416226",
+            ),
+            (
+                FF3_KEY_128,
+                10,
+                "0000000000000000",
+                "89012123456789000000789000000",
+                "34695224821734535122613701434",
+            ),
+            (
+                FF3_KEY_128,
+                26,
+                "9a768a92f60e12d8",
+                "0123456789abcdefghi",
+                "g2pk40i992fn20cjakb",
+            ),
+            (
+                FF3_KEY_192,
+                10,
+                "d8e7920afa330a73",
+                "890121234567890000",
+                "646965393875028755",
+            ),
+            (
+                FF3_KEY_192,
+                10,
+                "9a768a92f60e12d8",
+                "890121234567890000",
+                "961610514491424446",
+            ),
+            (
+                FF3_KEY_192,
+                10,
+                "d8e7920afa330a73",
+                "89012123456789000000789000000",
+                "53048884065350204541786380807",
+            ),
+            (
+                FF3_KEY_192,
+                10,
+                "0000000000000000",
+                "89012123456789000000789000000",
+                "98083802678820389295041483512",
+            ),
+            (
+                FF3_KEY_192,
+                26,
+                "9a768a92f60e12d8",
+                "0123456789abcdefghi",
+                "i0ihe2jfj7a9opf9p88",
+            ),
+            (
+                FF3_KEY_256,
+                10,
+                "d8e7920afa330a73",
+                "890121234567890000",
+                "922011205562777495",
+            ),
+            (
+                FF3_KEY_256,
+                10,
+                "9a768a92f60e12d8",
+                "890121234567890000",
+                "504149865578056140",
+            ),
+            (
+                FF3_KEY_256,
+                10,
+                "d8e7920afa330a73",
+                "89012123456789000000789000000",
+                "04344343235792599165734622699",
+            ),
+            (
+                FF3_KEY_256,
+                10,
+                "0000000000000000",
+                "89012123456789000000789000000",
+                "30859239999374053872365555822",
+            ),
+            (
+                FF3_KEY_256,
+                26,
+                "9a768a92f60e12d8",
+                "0123456789abcdefghi",
+                "p0b2godfja9bhb7bk38",
+            ),
+        ];
+        for (key, radix, tweak, plain, expected) in samples.iter() {
+            let ff3 = Ff3_1::new(key, *radix).unwrap();
+            let tweak = from_hex(tweak);
+            let left_tweak = [tweak[0], tweak[1], tweak[2], tweak[3]];
+            let right_tweak = [tweak[4], tweak[5], tweak[6], tweak[7]];
+            let mut content = numerals(plain);
+            ff3.feistel(left_tweak, right_tweak, &mut content, false)
+                .unwrap();
+            assert_eq!(text(&content), *expected);
+            ff3.feistel(left_tweak, right_tweak, &mut content, true)
+                .unwrap();
+            assert_eq!(text(&content), *plain);
+        }
+    }
+
+    #[test]
+    fn it_matches_ff3_1_vector() {
+        let key = Key::Aes128([0x2de79d23, 0x2df5585d, 0x68ce4788, 0x2ae256d6]);
+        let ff3 = Ff3_1::new(&key, 10).unwrap();
+        let tweak = from_hex("cbd09280979564");
+        let mut content = numerals("3992520240");
+        ff3.encrypt(&tweak, &mut content).unwrap();
+        assert_eq!(text(&content), "8901801106");
+        ff3.decrypt(&tweak, &mut content).unwrap();
+        assert_eq!(text(&content), "3992520240");
+    }
+
+    #[test]
+    fn it_round_trips_big_radixes() {
+        let ff1 = Ff1::new(&FF1_KEY_256, 1 << 16).unwrap();
+        let mut content: Vec<u16> = (0..256u32).map(|i| (i * 257) as u16).collect();
+        let original = content.clone();
+        ff1.encrypt(b"tweak", &mut content).unwrap();
+        assert_ne!(content, original);
+        ff1.decrypt(b"tweak", &mut content).unwrap();
+        assert_eq!(content, original);
+
+        let ff3 = Ff3_1::new(&FF3_KEY_128, 2).unwrap();
+        let mut content: Vec<u16> = (0..192).map(|i| (i % 3 % 2) as u16).collect();
+        let original = content.clone();
+        ff3.encrypt(&[1; 7], &mut content).unwrap();
+        assert_ne!(content, original);
+        ff3.decrypt(&[1; 7], &mut content).unwrap();
+        assert_eq!(content, original);
+    }
+
+    #[test]
+    fn it_refuses_invalid_inputs() {
+        assert_eq!(Ff1::new(&FF1_KEY_128, 1).err(), Some(FpeError::Radix));
+        assert_eq!(
+            Ff3_1::new(&FF1_KEY_128, (1 << 16) + 1).err(),
+            Some(FpeError::Radix)
+        );
+        let ff1 = Ff1::new(&FF1_KEY_128, 10).unwrap();
+        // 10^5 is smaller than the minimum domain size
+        assert_eq!(
+            ff1.encrypt(&[], &mut numerals("12345")),
+            Err(FpeError::Length)
+        );
+        assert_eq!(ff1.encrypt(&[], &mut [0; 257]), Err(FpeError::Length));
+        assert_eq!(
+            ff1.encrypt(&[], &mut numerals("123456789a")),
+            Err(FpeError::Numeral)
+        );
+        let ff3 = Ff3_1::new(&FF3_KEY_128, 10).unwrap();
+        assert_eq!(
+            ff3.encrypt(&[0; 8], &mut numerals("1234567")),
+            Err(FpeError::Tweak)
+        );
+        // 2 * floor(log10(2^96)) = 56
+        assert_eq!(ff3.encrypt(&[0; 7], &mut [0; 56]), Ok(()));
+        assert_eq!(ff3.encrypt(&[0; 7], &mut [0; 57]), Err(FpeError::Length));
+    }
+}
@@ -0,0 +1,487 @@
+/*!
+ * Format preserving encryption with the FF1 and FF3-1 modes of NIST SP 800-38G.
+ *
+ * Both encrypt a string of numerals in a given radix into another string of
+ * numerals with the same radix and length, so that for example a 16 digits card
+ * number stays a 16 digits number. The numerals are integers smaller than the
+ * radix, mapping them to and from characters is left to the caller.
+ *
+ * Everything lives on the stack, so the length of the strings is capped:
+ * FF1 accepts at most MAX_FF1_LEN numerals, FF3-1 at most the maximum of the
+ * standard, 192 numerals in radix 2 and less for bigger radixes.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::cbc_mac::CbcMac;
+use crate::cipher::{Encryptor, Key};
+use core::fmt;
+
+mod fpe_test;
+
+/**
+ * Highest number of numerals that FF1 accepts, the standard allows up to 2^32
+ */
+pub const MAX_FF1_LEN: usize = 256;
+
+/**
+ * Smallest number of values the domain must have, radix^length must be at least this
+ */
+pub const MIN_DOMAIN_SIZE: u32 = 1_000_000;
+
+/**
+ * Highest radix allowed by the standard
+ */
+pub const MAX_RADIX: u32 = 1 << 16;
+
+/**
+ * Length of the FF3-1 tweak in bytes
+ */
+pub const FF3_1_TWEAK_LEN: usize = 7;
+
+/**
+ * Number of 32 bits limbs of the biggest integer used by FF1: the d bytes of
+ * output of the PRF when half of the string is MAX_FF1_LEN / 2 numerals in
+ * radix 2^16, plus one limb of room for the multiplications.
+ */
+const LIMBS: usize = (MAX_FF1_LEN / 2 * 2 + 4) / 4 + 2;
+
+/**
+ * Reasons for which a string of numerals can't be encrypted or decrypted
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FpeError {
+    /**
+     * The radix is smaller than 2 or bigger than 2^16
+     */
+    Radix,
+    /**
+     * The string is too short for radix^length to reach MIN_DOMAIN_SIZE,
+     * or longer than the maximum length
+     */
+    Length,
+    /**
+     * A numeral is not smaller than the radix
+     */
+    Numeral,
+    /**
+     * The tweak is too long for FF1 or not FF3_1_TWEAK_LEN bytes long for FF3-1
+     */
+    Tweak,
+}
+
+impl fmt::Display for FpeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FpeError::Radix => write!(f, "radix out of range"),
+            FpeError::Length => write!(f, "numeral string length out of range"),
+            FpeError::Numeral => write!(f, "numeral not smaller than the radix"),
+            FpeError::Tweak => write!(f, "wrong tweak length"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FpeError {}
+
+/**
+ * The FF1 mode: a 10 rounds Feistel network whose round function is a CBC-MAC
+ * of the tweak and of half of the string. The tweak can have any length.
+ */
+pub struct Ff1<C = Encryptor> {
+    encryptor: C,
+    radix: u32,
+}
+
+/**
+ * The FF3-1 mode: an 8 rounds Feistel network whose round function is a single
+ * block cipher call on the key with reversed bytes. The tweak is 56 bits long.
+ */
+#[allow(non_camel_case_types)]
+pub struct Ff3_1<C = Encryptor> {
+    encryptor: C,
+    radix: u32,
+    max_len: usize,
+}
+
+impl Ff1 {
+    pub fn new(key: &Key, radix: u32) -> Result<Ff1, FpeError> {
+        Self::from_encryptor(Encryptor::from_key(key), radix)
+    }
+}
+
+impl<C: BlockEncrypt> Ff1<C> {
+    pub fn from_encryptor(encryptor: C, radix: u32) -> Result<Ff1<C>, FpeError> {
+        check_radix(radix)?;
+        Ok(Ff1 { encryptor, radix })
+    }
+
+    /**
+     * Encrypts the numerals in place
+     */
+    pub fn encrypt(&self, tweak: &[u8], numerals: &mut [u16]) -> Result<(), FpeError> {
+        self.check(tweak, numerals)?;
+        let (mut a, mut b) = split(numerals, numerals.len() / 2);
+        for round in 0..10 {
+            let mut y = self.round_function(tweak, numerals.len(), round, &b);
+            combine(a.numerals_mut().iter_mut().rev(), &mut y, self.radix, false);
+            core::mem::swap(&mut a, &mut b);
+        }
+        join(&a, &b, numerals);
+        Ok(())
+    }
+
+    /**
+     * Decrypts the numerals in place
+     */
+    pub fn decrypt(&self, tweak: &[u8], numerals: &mut [u16]) -> Result<(), FpeError> {
+        self.check(tweak, numerals)?;
+        let (mut a, mut b) = split(numerals, numerals.len() / 2);
+        for round in (0..10).rev() {
+            let mut y = self.round_function(tweak, numerals.len(), round, &a);
+            combine(b.numerals_mut().iter_mut().rev(), &mut y, self.radix, true);
+            core::mem::swap(&mut a, &mut b);
+        }
+        join(&a, &b, numerals);
+        Ok(())
+    }
+
+    fn check(&self, tweak: &[u8], numerals: &[u16]) -> Result<(), FpeError> {
+        if tweak.len() > u32::MAX as usize {
+            return Err(FpeError::Tweak);
+        }
+        check_numerals(numerals, self.radix, MAX_FF1_LEN)
+    }
+
+    /**
+     * Steps i to iv of each round: the PRF of the fixed block P and of Q, which
+     * holds the tweak, the round number and the numerals of half of the string,
+     * is expanded to d bytes and read as an integer.
+     */
+    fn round_function(&self, tweak: &[u8], n: usize, round: u8, half: &Half) -> BigUint {
+        let u = n / 2;
+        let v = n - u;
+        let b = ceil_log2_pow(self.radix, v).div_ceil(8);
+        let d = 4 * b.div_ceil(4) + 4;
+        let radix = self.radix.to_be_bytes();
+        let n_bytes = (n as u32).to_be_bytes();
+        let t_bytes = (tweak.len() as u32).to_be_bytes();
+        let p = [
+            1, 2, 1, radix[1], radix[2], radix[3], 10, u as u8, n_bytes[0], n_bytes[1], n_bytes[2],
+            n_bytes[3], t_bytes[0], t_bytes[1], t_bytes[2], t_bytes[3],
+        ];
+        let padding = (16 - (tweak.len() + b + 1) % 16) % 16;
+        let mut number = [0; LIMBS * 4];
+        BigUint::from_numerals(half.numerals().iter(), self.radix).write_bytes(&mut number[..b]);
+
+        let mut mac = CbcMac::new(&self.encryptor);
+        mac.absorb(&p);
+        mac.absorb(tweak);
+        for _ in 0..padding {
+            mac.absorb(&[0]);
+        }
+        mac.absorb(&[round]);
+        mac.absorb(&number[..b]);
+        let r = mac.finish();
+
+        let mut s = [0; LIMBS * 4];
+        for (j, chunk) in s[..d].chunks_mut(16).enumerate() {
+            let mut block = r;
+            if j > 0 {
+                for (byte, counter) in block.iter_mut().zip((j as u128).to_be_bytes().iter()) {
+                    *byte ^= counter;
+                }
+                self.encryptor.encrypt_block(&mut block);
+            }
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        BigUint::from_bytes(&s[..d])
+    }
+}
+
+impl Ff3_1 {
+    pub fn new(key: &Key, radix: u32) -> Result<Ff3_1, FpeError> {
+        // the key is used with its bytes in reverse order
+        let mut key_bytes = [0; 32];
+        for (chunk, word) in key_bytes.chunks_mut(4).zip(key.words().iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        let key_len = key.size().bytes();
+        key_bytes[..key_len].reverse();
+        Self::from_encryptor(Encryptor::from_key_bytes(&key_bytes[..key_len]), radix)
+    }
+}
+
+impl<C: BlockEncrypt> Ff3_1<C> {
+    /**
+     * The encryptor must already be keyed with the bytes of the key in reverse
+     * order, as FF3-1 requires; new takes care of that
+     */
+    pub fn from_encryptor(encryptor: C, radix: u32) -> Result<Ff3_1<C>, FpeError> {
+        check_radix(radix)?;
+        // the biggest n such that radix^(n / 2) fits in 96 bits
+        let mut max_len = 0;
+        let mut power = 1u128;
+        while power * radix as u128 <= 1 << 96 {
+            power *= radix as u128;
+            max_len += 2;
+        }
+        Ok(Ff3_1 {
+            encryptor,
+            radix,
+            max_len,
+        })
+    }
+
+    /**
+     * Encrypts the numerals in place, tweak must be FF3_1_TWEAK_LEN bytes long
+     */
+    pub fn encrypt(&self, tweak: &[u8], numerals: &mut [u16]) -> Result<(), FpeError> {
+        let (left_tweak, right_tweak) = split_tweak(tweak)?;
+        self.feistel(left_tweak, right_tweak, numerals, false)
+    }
+
+    /**
+     * Decrypts the numerals in place, tweak must be FF3_1_TWEAK_LEN bytes long
+     */
+    pub fn decrypt(&self, tweak: &[u8], numerals: &mut [u16]) -> Result<(), FpeError> {
+        let (left_tweak, right_tweak) = split_tweak(tweak)?;
+        self.feistel(left_tweak, right_tweak, numerals, true)
+    }
+
+    /**
+     * The 8 rounds shared by FF3-1 and the original FF3, which only differ
+     * in how the two halves of the tweak are obtained. The strings are read
+     * in reverse order, so the first numeral of each half is the least significant.
+     */
+    fn feistel(
+        &self,
+        left_tweak: [u8; 4],
+        right_tweak: [u8; 4],
+        numerals: &mut [u16],
+        decrypt: bool,
+    ) -> Result<(), FpeError> {
+        check_numerals(numerals, self.radix, self.max_len)?;
+        let (mut a, mut b) = split(numerals, numerals.len().div_ceil(2));
+        for step in 0..8u8 {
+            let round = if decrypt { 7 - step } else { step };
+            let tweak = if round % 2 == 0 {
+                right_tweak
+            } else {
+                left_tweak
+            };
+            let (source, target) = if decrypt { (&a, &mut b) } else { (&b, &mut a) };
+            let mut block = [0; 16];
+            for (byte, (tweak_byte, round_byte)) in block
+                .iter_mut()
+                .zip(tweak.iter().zip((round as u32).to_be_bytes().iter()))
+            {
+                *byte = tweak_byte ^ round_byte;
+            }
+            BigUint::from_numerals(source.numerals().iter().rev(), self.radix)
+                .write_bytes(&mut block[4..]);
+            block.reverse();
+            self.encryptor.encrypt_block(&mut block);
+            block.reverse();
+            let mut y = BigUint::from_bytes(&block);
+            combine(
+                target.numerals_mut().iter_mut(),
+                &mut y,
+                self.radix,
+                decrypt,
+            );
+            core::mem::swap(&mut a, &mut b);
+        }
+        join(&a, &b, numerals);
+        Ok(())
+    }
+}
+
+fn split_tweak(tweak: &[u8]) -> Result<([u8; 4], [u8; 4]), FpeError> {
+    if tweak.len() != FF3_1_TWEAK_LEN {
+        return Err(FpeError::Tweak);
+    }
+    Ok((
+        [tweak[0], tweak[1], tweak[2], tweak[3] & 0xf0],
+        [tweak[4], tweak[5], tweak[6], (tweak[3] & 0x0f) << 4],
+    ))
+}
+
+fn check_radix(radix: u32) -> Result<(), FpeError> {
+    if (2..=MAX_RADIX).contains(&radix) {
+        Ok(())
+    } else {
+        Err(FpeError::Radix)
+    }
+}
+
+fn check_numerals(numerals: &[u16], radix: u32, max_len: usize) -> Result<(), FpeError> {
+    let domain_size = (0..numerals.len()).fold(1u64, |size, _| size.saturating_mul(radix as u64));
+    if numerals.len() < 2 || domain_size < MIN_DOMAIN_SIZE as u64 || numerals.len() > max_len {
+        return Err(FpeError::Length);
+    }
+    if numerals.iter().any(|numeral| *numeral as u32 >= radix) {
+        return Err(FpeError::Numeral);
+    }
+    Ok(())
+}
+
+/**
+ * One of the two halves of the Feistel network, at most half of MAX_FF1_LEN
+ * numerals (FF3-1 strings are always shorter)
+ */
+#[derive(Clone, Copy)]
+struct Half {
+    numerals: [u16; MAX_FF1_LEN / 2],
+    len: usize,
+}
+
+impl Half {
+    fn numerals(&self) -> &[u16] {
+        &self.numerals[..self.len]
+    }
+
+    fn numerals_mut(&mut self) -> &mut [u16] {
+        &mut self.numerals[..self.len]
+    }
+}
+
+fn split(numerals: &[u16], left_len: usize) -> (Half, Half) {
+    let mut left = Half {
+        numerals: [0; MAX_FF1_LEN / 2],
+        len: left_len,
+    };
+    let mut right = Half {
+        numerals: [0; MAX_FF1_LEN / 2],
+        len: numerals.len() - left_len,
+    };
+    left.numerals_mut().copy_from_slice(&numerals[..left_len]);
+    right.numerals_mut().copy_from_slice(&numerals[left_len..]);
+    (left, right)
+}
+
+fn join(left: &Half, right: &Half, numerals: &mut [u16]) {
+    numerals[..left.len].copy_from_slice(left.numerals());
+    numerals[left.len..].copy_from_slice(right.numerals());
+}
+
+/**
+ * Adds y to (or subtracts it from) the number made of the given numerals,
+ * modulo radix^m where m is the number of numerals. The numerals come from the
+ * least significant, so only the lowest m digits of y in the radix are needed
+ * and the reduction modulo radix^m is just dropping the last carry.
+ */
+fn combine<'a, I>(numerals: I, y: &mut BigUint, radix: u32, subtract: bool)
+where
+    I: Iterator<Item = &'a mut u16>,
+{
+    let mut carry = 0;
+    for numeral in numerals {
+        let digit = y.div_small(radix);
+        let value = if subtract {
+            (*numeral as u32 + 2 * radix - digit - carry) % radix
+        } else {
+            (*numeral as u32 + digit + carry) % radix
+        };
+        carry = if subtract {
+            (*numeral as u32) < digit + carry
+        } else {
+            *numeral as u32 + digit + carry >= radix
+        } as u32;
+        *numeral = value as u16;
+    }
+}
+
+/**
+ * ceil(length * log2(radix)), the number of bits needed to write any number of
+ * length numerals: the number of bits of radix^length - 1
+ */
+fn ceil_log2_pow(radix: u32, length: usize) -> usize {
+    let mut power = BigUint::from_numerals(core::iter::once(&1), radix);
+    for _ in 0..length {
+        power.mul_add_small(radix, 0);
+    }
+    let bits = power.bits();
+    if power.is_power_of_two() {
+        bits - 1
+    } else {
+        bits
+    }
+}
+
+/**
+ * Just enough of a fixed size unsigned integer for the conversions between
+ * numeral strings and byte strings. The limbs go from the least significant.
+ */
+struct BigUint {
+    limbs: [u32; LIMBS],
+}
+
+impl BigUint {
+    /**
+     * NUM_radix of the standard, the numerals come from the most significant
+     */
+    fn from_numerals<'a, I>(numerals: I, radix: u32) -> BigUint
+    where
+        I: Iterator<Item = &'a u16>,
+    {
+        let mut number = BigUint { limbs: [0; LIMBS] };
+        for numeral in numerals {
+            number.mul_add_small(radix, *numeral as u32);
+        }
+        number
+    }
+
+    /**
+     * NUM of the standard, big endian
+     */
+    fn from_bytes(bytes: &[u8]) -> BigUint {
+        let mut number = BigUint { limbs: [0; LIMBS] };
+        for (i, byte) in bytes.iter().rev().enumerate() {
+            number.limbs[i / 4] |= (*byte as u32) << (8 * (i % 4));
+        }
+        number
+    }
+
+    /**
+     * The lowest bytes of the number, big endian
+     */
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        let len = bytes.len();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let position = len - 1 - i;
+            *byte = (self.limbs[position / 4] >> (8 * (position % 4))) as u8;
+        }
+    }
+
+    fn mul_add_small(&mut self, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in self.limbs.iter_mut() {
+            let value = *limb as u64 * factor as u64 + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+    }
+
+    /**
+     * Divides the number in place and returns the remainder
+     */
+    fn div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        remainder as u32
+    }
+
+    fn bits(&self) -> usize {
+        match self.limbs.iter().rposition(|limb| *limb != 0) {
+            Some(i) => 32 * i + 32 - self.limbs[i].leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn is_power_of_two(&self) -> bool {
+        self.limbs.iter().map(|limb| limb.count_ones()).sum::<u32>() == 1
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod gcm_test {
+    use crate::cipher::{Encryptor, Key};
+    use crate::error::Error;
+    use crate::gcm::{Gcm, GcmEncryption};
+
+    const KEY: Key = Key::Aes128([0xfeffe992, 0x8665731c, 0x6d6a8f94, 0x67308308]);
+
+    const NONCE: [u8; 12] = [
+        0xca, 0xfe, 0xba, 0xbe, 0xfa, 0xce, 0xdb, 0xad, 0xde, 0xca, 0xf8, 0x88,
+    ];
+
+    const AAD: [u8; 20] = [
+        0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef, 0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe,
+        0xef, 0xab, 0xad, 0xda, 0xd2,
+    ];
+
+    fn blocks(values: &[u128]) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        for (chunk, value) in bytes.chunks_mut(16).zip(values.iter()) {
+            chunk.copy_from_slice(&value.to_be_bytes());
+        }
+        bytes
+    }
+
+    fn plaintext() -> [u8; 64] {
+        blocks(&[
+            0xd9313225f88406e5a55909c5aff5269a,
+            0x86a7a9531534f7da2e4c303d8a318a72,
+            0x1c3c0c95956809532fcf0e2449a6b525,
+            0xb16aedf5aa0de657ba637b391aafd255,
+        ])
+    }
+
+    fn ciphertext() -> [u8; 64] {
+        blocks(&[
+            0x42831ec2217774244b7221b784d0d49c,
+            0xe3aa212f2c02a4e035c17e2329aca12e,
+            0x21d514b25466931c7d8f6a5aac84aa05,
+            0x1ba30b396a0aac973d58e091473f5985,
+        ])
+    }
+
+    /**
+     * Test cases 3 and 4 of the GCM specification
+     */
+    #[test]
+    fn it_matches_the_specification() {
+        let gcm = Gcm::new(&KEY);
+        let mut buffer = plaintext();
+        let tag = gcm.encrypt(&NONCE, &[], &mut buffer).unwrap();
+        assert_eq!(buffer, ciphertext());
+        assert_eq!(tag, 0x4d5c2af327cd64a62cf35abd2ba6fab4_u128.to_be_bytes());
+
+        let mut buffer = plaintext();
+        let tag = gcm.encrypt(&NONCE, &AAD, &mut buffer[..60]).unwrap();
+        assert_eq!(buffer[..60], ciphertext()[..60]);
+        assert_eq!(tag, 0x5bc94fbc3221a5db94fae95ae7121a47_u128.to_be_bytes());
+        gcm.decrypt(&NONCE, &AAD, &mut buffer[..60], &tag).unwrap();
+        assert_eq!(buffer[..60], plaintext()[..60]);
+    }
+
+    /**
+     * Test case 6 of the GCM specification, with a 60 bytes long nonce
+     */
+    #[test]
+    fn it_accepts_long_nonces() {
+        let nonce = blocks(&[
+            0x9313225df88406e555909c5aff5269aa,
+            0x6a7a9538534f7da1e4c303d2a318a728,
+            0xc3c0c95156809539fcf0e2429a6b5254,
+            0x16aedbf5a0de6a57a637b39b << 32,
+        ]);
+        let mut buffer = plaintext();
+        let tag = Gcm::new(&KEY)
+            .encrypt(&nonce[..60], &AAD, &mut buffer[..60])
+            .unwrap();
+        assert_eq!(
+            buffer[..16],
+            0x8ce24998625615b603a033aca13fb894_u128.to_be_bytes()
+        );
+        assert_eq!(tag, 0x619cc5aefffe0bfa462af43c1699d050_u128.to_be_bytes());
+    }
+
+    #[test]
+    fn it_encrypts_in_pieces() {
+        let mut encryption = GcmEncryption::new(Encryptor::from_key(&KEY), &NONCE, &AAD).unwrap();
+        let mut buffer = plaintext();
+        for piece in buffer[..60].chunks_mut(7) {
+            encryption.update(piece).unwrap();
+        }
+        assert_eq!(buffer[..60], ciphertext()[..60]);
+        assert_eq!(
+            encryption.finalize(),
+            0x5bc94fbc3221a5db94fae95ae7121a47_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_releases_nothing_without_the_right_tag() {
+        let gcm = Gcm::new(&KEY);
+        let tag = 0x5bc94fbc3221a5db94fae95ae7121a47_u128.to_be_bytes();
+        let mut buffer = ciphertext();
+        assert_eq!(
+            gcm.decrypt(&NONCE, &AAD[..19], &mut buffer[..60], &tag),
+            Err(Error::AuthenticationFailed)
+        );
+        buffer[59] ^= 1;
+        assert_eq!(
+            gcm.decrypt(&NONCE, &AAD, &mut buffer[..60], &tag),
+            Err(Error::AuthenticationFailed)
+        );
+        assert_eq!(buffer[..59], ciphertext()[..59]);
+        buffer[59] ^= 1;
+        assert_eq!(
+            gcm.decrypt(&NONCE, &AAD, &mut buffer[..60], &tag[..12]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            gcm.decrypt(&NONCE, &AAD, &mut buffer[..60], &[]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(buffer[..60], ciphertext()[..60]);
+        gcm.decrypt(&NONCE, &AAD, &mut buffer[..60], &tag).unwrap();
+        assert_eq!(buffer[..60], plaintext()[..60]);
+        assert_eq!(
+            gcm.encrypt(&[], &AAD, &mut buffer).err(),
+            Some(Error::InvalidLength)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+ * GCM, the authenticated encryption mode of NIST SP 800-38D: CTR with a 32 bits
+ * counter for confidentiality and GHASH of the associated data and of the
+ * ciphertext, masked with the cipher of the first counter block, for the tag.
+ *
+ * Decryption checks the tag before decrypting anything, so a plaintext
+ * that has not been authenticated never leaves this module. A nonce must
+ * never be used twice with the same key: it would reveal the hash key.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+use crate::error::Error;
+use crate::ghash::Ghash;
+use crate::gmac::initial_counter_block;
+use crate::math::Math;
+
+mod gcm_test;
+
+/**
+ * Length in bytes of a full tag
+ */
+pub const TAG_LEN: usize = 16;
+
+/**
+ * The longest plaintext allowed by SP 800-38D, 2^39 - 256 bits: with a
+ * 32 bits counter the keystream would repeat after it
+ */
+pub const MAX_PLAINTEXT_LEN: u64 = (1 << 36) - 32;
+
+/**
+ * GCM keyed once and usable for any number of messages, each with its own nonce
+ */
+pub struct Gcm<C = Encryptor> {
+    encryptor: C,
+    ghash: Ghash,
+}
+
+/**
+ * The state of the encryption of a message given in pieces
+ */
+pub struct GcmEncryption<C = Encryptor> {
+    encryptor: C,
+    ghash: Ghash,
+    initial_counter_block: [u8; 16],
+    keystream: Keystream,
+    aad_len: u64,
+    text_len: u64,
+}
+
+/**
+ * The CTR part of GCM, that only increments the last 32 bits of the counter block
+ */
+#[derive(Clone, Copy)]
+struct Keystream {
+    counter_block: [u8; 16],
+    block: [u8; 16],
+    used: usize,
+}
+
+impl Gcm {
+    pub fn new(key: &Key) -> Gcm {
+        Self::from_encryptor(Encryptor::from_key(key))
+    }
+}
+
+impl<C: BlockEncrypt> Gcm<C> {
+    pub fn from_encryptor(encryptor: C) -> Gcm<C> {
+        let ghash = hash_key_ghash(&encryptor);
+        Gcm { encryptor, ghash }
+    }
+
+    /**
+     * Encrypts buffer in place and returns the tag, which authenticates
+     * both the ciphertext and the associated data
+     */
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; TAG_LEN], Error> {
+        let mut encryption = self.encryption(nonce, aad)?;
+        encryption.update(buffer)?;
+        Ok(encryption.finalize())
+    }
+
+    /**
+     * Checks the tag and only then decrypts buffer in place. On
+     * Error::AuthenticationFailed buffer still holds the ciphertext.
+     * The tag must be the full TAG_LEN bytes, anything else fails with
+     * Error::InvalidLength.
+     */
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), Error> {
+        if tag.len() != TAG_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let mut encryption = self.encryption(nonce, aad)?;
+        encryption.check_length(buffer.len())?;
+        encryption.ghash.update(buffer);
+        encryption.text_len = buffer.len() as u64;
+        let mut keystream = encryption.keystream;
+        let expected = encryption.finalize();
+        if !Math::constant_time_eq(&expected, tag) {
+            return Err(Error::AuthenticationFailed);
+        }
+        keystream.apply(&self.encryptor, buffer);
+        Ok(())
+    }
+
+    /**
+     * Starts the encryption of a message that is given in pieces, see
+     * GcmEncryption. The nonce can't be empty.
+     */
+    pub fn encryption(&self, nonce: &[u8], aad: &[u8]) -> Result<GcmEncryption<&C>, Error> {
+        GcmEncryption::with_ghash(&self.encryptor, self.ghash.clone(), nonce, aad)
+    }
+}
+
+impl<C: BlockEncrypt> GcmEncryption<C> {
+    /**
+     * Starts the encryption of a message with its own copy of the cipher,
+     * when there is no Gcm to borrow it from
+     */
+    pub fn new(encryptor: C, nonce: &[u8], aad: &[u8]) -> Result<GcmEncryption<C>, Error> {
+        let ghash = hash_key_ghash(&encryptor);
+        Self::with_ghash(encryptor, ghash, nonce, aad)
+    }
+
+    fn with_ghash(
+        encryptor: C,
+        mut ghash: Ghash,
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<GcmEncryption<C>, Error> {
+        if nonce.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        let initial_counter_block = initial_counter_block(&ghash, nonce);
+        ghash.update(aad);
+        ghash.pad();
+        let mut counter_block = initial_counter_block;
+        increment(&mut counter_block);
+        Ok(GcmEncryption {
+            encryptor,
+            ghash,
+            initial_counter_block,
+            keystream: Keystream {
+                counter_block,
+                block: [0; 16],
+                used: 16,
+            },
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        })
+    }
+
+    /**
+     * Encrypts the next piece of the plaintext in place. Fails with
+     * Error::InvalidLength once the plaintext would be longer than MAX_PLAINTEXT_LEN.
+     */
+    pub fn update(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.check_length(data.len())?;
+        self.keystream.apply(&self.encryptor, data);
+        self.ghash.update(data);
+        self.text_len += data.len() as u64;
+        Ok(())
+    }
+
+    /**
+     * The tag of the associated data and of all the ciphertext produced
+     */
+    pub fn finalize(mut self) -> [u8; TAG_LEN] {
+        self.ghash.pad();
+        self.ghash.update(&(self.aad_len * 8).to_be_bytes());
+        self.ghash.update(&(self.text_len * 8).to_be_bytes());
+        let mut tag = self.initial_counter_block;
+        self.encryptor.encrypt_block(&mut tag);
+        for (byte, hash) in tag.iter_mut().zip(self.ghash.finalize().iter()) {
+            *byte ^= hash;
+        }
+        tag
+    }
+
+    fn check_length(&self, length: usize) -> Result<(), Error> {
+        if self.text_len + length as u64 > MAX_PLAINTEXT_LEN {
+            Err(Error::InvalidLength)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Keystream {
+    fn apply<C: BlockEncrypt>(&mut self, encryptor: &C, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.used == 16 {
+                self.block = self.counter_block;
+                encryptor.encrypt_block(&mut self.block);
+                increment(&mut self.counter_block);
+                self.used = 0;
+            }
+            *byte ^= self.block[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+/**
+ * GHASH under the hash key H = CIPH(0)
+ */
+fn hash_key_ghash<C: BlockEncrypt>(encryptor: &C) -> Ghash {
+    let mut h = [0; 16];
+    encryptor.encrypt_block(&mut h);
+    Ghash::new(&h)
+}
+
+/**
+ * inc32 of the standard: the last 4 bytes are incremented modulo 2^32
+ */
+fn increment(counter_block: &mut [u8; 16]) {
+    let counter = u32::from_be_bytes([
+        counter_block[12],
+        counter_block[13],
+        counter_block[14],
+        counter_block[15],
+    ]);
+    counter_block[12..].copy_from_slice(&counter.wrapping_add(1).to_be_bytes());
+}
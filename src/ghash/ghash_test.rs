@@ -0,0 +1,49 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod ghash_test {
+    use crate::ghash::{multiply, Ghash};
+
+    #[test]
+    fn it_multiplies() {
+        // the block with only the most significant bit set is the identity
+        let one = 1 << 127;
+        let value = 0x66e94bd4ef8a2c3b884cfa59ca342b2e;
+        assert_eq!(multiply(one, value), value);
+        assert_eq!(multiply(value, one), value);
+        assert_eq!(multiply(value, 0), 0);
+        // x times x^127 wraps around to the reduction polynomial
+        assert_eq!(multiply(1 << 126, 1), 0xe1 << 120);
+    }
+
+    /**
+     * GHASH(H, A, C) from test case 2 of the GCM specification, with no
+     * associated data and a single block of ciphertext
+     */
+    #[test]
+    fn it_hashes() {
+        let h = 0x66e94bd4ef8a2c3b884cfa59ca342b2e_u128.to_be_bytes();
+        let ciphertext = 0x0388dace60b6a392f328c2b971b2fe78_u128.to_be_bytes();
+        let mut ghash = Ghash::new(&h);
+        ghash.update(&ciphertext);
+        ghash.update(&0x80_u128.to_be_bytes());
+        assert_eq!(
+            ghash.finalize(),
+            0xf38cbb1ad69223dcc3457ae5b6b0f885_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_pads_blocks() {
+        let h = [0x42; 16];
+        let mut whole = Ghash::new(&h);
+        whole.update(&[1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        whole.update(&[4; 16]);
+        let mut padded = Ghash::new(&h);
+        padded.update(&[1, 2]);
+        padded.update(&[3]);
+        padded.pad();
+        padded.pad();
+        padded.update(&[4; 16]);
+        assert_eq!(padded.finalize(), whole.finalize());
+    }
+}
@@ -0,0 +1,94 @@
+/*!
+ * GHASH, the universal hash function of GCM described in NIST SP 800-38D.
+ *
+ * It is a polynomial evaluated at the hash key H in GF(2^128), with the 16 bytes
+ * blocks of the input as coefficients. GCM and GMAC call it on the associated
+ * data and on the ciphertext, each padded with zeros to a whole number of
+ * blocks, followed by a block with their lengths.
+ */
+mod ghash_test;
+
+/**
+ * The reduction polynomial x^128 + x^7 + x^2 + x + 1 in the reflected bit order
+ * of GCM, without the x^128 term
+ */
+const R: u128 = 0xe1 << 120;
+
+/**
+ * The state of a GHASH computation. The input can be given in pieces of any
+ * length, pad completes the current block with zeros.
+ */
+#[derive(Clone)]
+pub struct Ghash {
+    h: u128,
+    accumulator: u128,
+    buffer: [u8; 16],
+    filled: usize,
+}
+
+impl Ghash {
+    /**
+     * Initializes GHASH with the hash key, which GCM computes as the cipher
+     * of the zero block
+     */
+    pub fn new(h: &[u8; 16]) -> Ghash {
+        Ghash {
+            h: u128::from_be_bytes(*h),
+            accumulator: 0,
+            buffer: [0; 16],
+            filled: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.buffer[self.filled] = *byte;
+            self.filled += 1;
+            if self.filled == 16 {
+                self.process_buffer();
+            }
+        }
+    }
+
+    /**
+     * Pads the data given so far with zeros up to a multiple of 16 bytes
+     */
+    pub fn pad(&mut self) {
+        if self.filled > 0 {
+            for byte in self.buffer[self.filled..].iter_mut() {
+                *byte = 0;
+            }
+            self.process_buffer();
+        }
+    }
+
+    /**
+     * Pads the data and returns the hash
+     */
+    pub fn finalize(mut self) -> [u8; 16] {
+        self.pad();
+        self.accumulator.to_be_bytes()
+    }
+
+    fn process_buffer(&mut self) {
+        self.accumulator = multiply(self.accumulator ^ u128::from_be_bytes(self.buffer), self.h);
+        self.filled = 0;
+    }
+}
+
+/**
+ * Multiplication in GF(2^128) as in algorithm 1 of SP 800-38D, where the most
+ * significant bit of a block is the coefficient of x^0. The choices are made
+ * with masks, so that the time doesn't depend on the values.
+ */
+pub fn multiply(x: u128, y: u128) -> u128 {
+    let mut z = 0;
+    let mut v = y;
+    for i in 0..128 {
+        let bit = (x >> (127 - i)) & 1;
+        z ^= v & bit.wrapping_neg();
+        let carry = v & 1;
+        v = (v >> 1) ^ (R & carry.wrapping_neg());
+    }
+    z
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod gmac_test {
+    use crate::cipher::Key;
+    use crate::gmac::{Gmac, GmacError};
+
+    /**
+     * 100 bytes of associated data used with the keys below
+     */
+    fn data() -> [u8; 100] {
+        let mut data = [0; 100];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = (i * 7 % 251) as u8;
+        }
+        data
+    }
+
+    fn iv(length: usize) -> [u8; 60] {
+        let mut iv = [0; 60];
+        for (i, byte) in iv[..length].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        iv
+    }
+
+    const KEY_128: Key = Key::Aes128([0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+    const KEY_256: Key = Key::Aes256([
+        0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+        0x1c1d1e1f,
+    ]);
+
+    /**
+     * Test case 1 of the GCM specification: zero key and IV, nothing to authenticate
+     */
+    #[test]
+    fn it_macs_nothing() {
+        let gmac = Gmac::new(&Key::Aes128([0; 4]), &[0; 12]).unwrap();
+        assert_eq!(
+            gmac.finalize(),
+            0x58e2fccefa7e3061367f1d57a4e7455a_u128.to_be_bytes()
+        );
+    }
+
+    /**
+     * COUNT = 0 of the CAVP GCM vectors with 128 bits of AAD and no plaintext
+     */
+    #[test]
+    fn it_matches_cavp() {
+        let mut gmac = Gmac::new(
+            &Key::Aes128([0x77be6370, 0x8971c4e2, 0x40d1cb79, 0xe8d77feb]),
+            &[
+                0xe0, 0xe0, 0x0f, 0x19, 0xfe, 0xd7, 0xba, 0x01, 0x36, 0xa7, 0x97, 0xf3,
+            ],
+        )
+        .unwrap();
+        gmac.update(&0x7a43ec1d9c0a5a78a0b16533a6213cab_u128.to_be_bytes());
+        assert_eq!(
+            gmac.finalize(),
+            0x209fcc8d3675ed938e9c7166709dd946_u128.to_be_bytes()
+        );
+    }
+
+    /**
+     * The expected tags were computed with the GCM of another library, on an
+     * empty plaintext
+     */
+    #[test]
+    fn it_macs_with_any_iv_length() {
+        let cases = [
+            (KEY_256, 12, 0xeee183e055e0ee56ea36bd1064c9f61e_u128),
+            (KEY_256, 8, 0xb2ef333164cead3a41162e0fbbe3e561),
+            (KEY_128, 60, 0x85fbf2abd2f9504a60c95ee21406011f),
+        ];
+        for (key, iv_length, tag) in cases.iter() {
+            let mut gmac = Gmac::new(key, &iv(*iv_length)[..*iv_length]).unwrap();
+            for piece in data().chunks(13) {
+                gmac.update(piece);
+            }
+            assert_eq!(gmac.finalize(), tag.to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn it_verifies_tags() {
+        let tag = 0xeee183e055e0ee56ea36bd1064c9f61e_u128.to_be_bytes();
+        let gmac = || {
+            let mut gmac = Gmac::new(&KEY_256, &iv(12)[..12]).unwrap();
+            gmac.update(&data());
+            gmac
+        };
+        assert!(gmac().verify(&tag));
+        assert!(!gmac().verify(&tag[..12]));
+        assert!(!gmac().verify(&[]));
+        assert!(gmac().verify_truncated(&tag[..12], 12));
+        assert!(gmac().verify_truncated(&tag[..4], 4));
+        assert!(!gmac().verify_truncated(&tag[..11], 11));
+        assert!(!gmac().verify_truncated(&tag[..12], 16));
+        assert!(!gmac().verify_truncated(&[], 0));
+        let mut wrong = tag;
+        wrong[15] ^= 1;
+        assert!(!gmac().verify(&wrong));
+    }
+
+    #[test]
+    fn it_refuses_empty_ivs() {
+        assert_eq!(Gmac::new(&KEY_128, &[]).err(), Some(GmacError::IvLength));
+    }
+}
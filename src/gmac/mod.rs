@@ -0,0 +1,148 @@
+/*!
+ * GMAC, GCM used only to authenticate data as described in NIST SP 800-38D:
+ * the tag is the GHASH of the data under H = CIPH(0), masked with the cipher
+ * of the first counter block.
+ *
+ * The data can be given in pieces of any length, so a long stream can be
+ * authenticated without keeping it in memory. Like GCM, GMAC is broken if
+ * an IV is ever reused with the same key, so each instance is consumed by
+ * the tag it produces.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+use crate::ghash::Ghash;
+use crate::math::Math;
+use core::fmt;
+
+mod gmac_test;
+
+/**
+ * Length in bytes of a full tag
+ */
+pub const TAG_LEN: usize = 16;
+
+/**
+ * Reasons for which a GMAC can't be computed
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GmacError {
+    /**
+     * The IV is empty
+     */
+    IvLength,
+}
+
+impl fmt::Display for GmacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GmacError::IvLength => write!(f, "the IV can't be empty"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GmacError {}
+
+/**
+ * The state of a GMAC computation for one IV
+ */
+pub struct Gmac<C = Encryptor> {
+    encryptor: C,
+    ghash: Ghash,
+    initial_counter_block: [u8; 16],
+    data_len: u64,
+}
+
+impl Gmac {
+    pub fn new(key: &Key, iv: &[u8]) -> Result<Gmac, GmacError> {
+        Self::from_encryptor(Encryptor::from_key(key), iv)
+    }
+}
+
+impl<C: BlockEncrypt> Gmac<C> {
+    /**
+     * The IV can have any length, but 12 bytes is the recommended one and
+     * the only one that doesn't need an extra GHASH to produce the first
+     * counter block
+     */
+    pub fn from_encryptor(encryptor: C, iv: &[u8]) -> Result<Gmac<C>, GmacError> {
+        if iv.is_empty() {
+            return Err(GmacError::IvLength);
+        }
+        let mut h = [0; 16];
+        encryptor.encrypt_block(&mut h);
+        let ghash = Ghash::new(&h);
+        let initial_counter_block = initial_counter_block(&ghash, iv);
+        Ok(Gmac {
+            encryptor,
+            ghash,
+            initial_counter_block,
+            data_len: 0,
+        })
+    }
+
+    /**
+     * Adds data to authenticate
+     */
+    pub fn update(&mut self, data: &[u8]) {
+        self.ghash.update(data);
+        self.data_len += data.len() as u64;
+    }
+
+    /**
+     * The tag of all the data given so far
+     */
+    pub fn finalize(mut self) -> [u8; TAG_LEN] {
+        // the lengths block, in bits, with an empty ciphertext
+        self.ghash.pad();
+        self.ghash.update(&(self.data_len * 8).to_be_bytes());
+        self.ghash.update(&[0; 8]);
+        let mut tag = self.initial_counter_block;
+        self.encryptor.encrypt_block(&mut tag);
+        for (byte, hash) in tag.iter_mut().zip(self.ghash.finalize().iter()) {
+            *byte ^= hash;
+        }
+        tag
+    }
+
+    /**
+     * Checks the full TAG_LEN bytes tag of the data. The comparison takes the
+     * same time wherever the first difference is.
+     */
+    pub fn verify(self, tag: &[u8]) -> bool {
+        self.verify_truncated(tag, TAG_LEN)
+    }
+
+    /**
+     * Checks a tag truncated to its first len bytes. len comes from the
+     * application, not from the received tag, and must be one of the lengths
+     * of SP 800-38D: 12 to 16 bytes, or 8 or 4 bytes for the applications
+     * that the standard allows to use them. Any other length fails.
+     */
+    pub fn verify_truncated(self, tag: &[u8], len: usize) -> bool {
+        let expected = self.finalize();
+        matches!(len, 4 | 8 | 12..=16)
+            && tag.len() == len
+            && Math::constant_time_eq(&expected[..len], tag)
+    }
+}
+
+/**
+ * J0 of the standard: the IV followed by a 32 bits counter set to 1 if the IV
+ * is 12 bytes long, otherwise the GHASH of the padded IV and of its length
+ */
+pub(crate) fn initial_counter_block(ghash: &Ghash, iv: &[u8]) -> [u8; 16] {
+    let mut block = [0; 16];
+    if iv.len() == 12 {
+        block[..12].copy_from_slice(iv);
+        block[15] = 1;
+    } else {
+        let mut ghash = ghash.clone();
+        ghash.update(iv);
+        ghash.pad();
+        ghash.update(&[0; 8]);
+        ghash.update(&(iv.len() as u64 * 8).to_be_bytes());
+        block = ghash.finalize();
+    }
+    block
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod hmac_test {
+    use crate::hmac::HmacSha256;
+
+    fn first_half(tag: [u8; 32]) -> u128 {
+        let mut half = [0; 16];
+        half.copy_from_slice(&tag[..16]);
+        u128::from_be_bytes(half)
+    }
+
+    /**
+     * Test cases 1, 2 and 6 of RFC 4231
+     */
+    #[test]
+    fn it_matches_rfc_4231() {
+        assert_eq!(
+            HmacSha256::mac(&[0x0b; 20], b"Hi There"),
+            [
+                0xb0, 0x34, 0x4c, 0x61, 0xd8, 0xdb, 0x38, 0x53, 0x5c, 0xa8, 0xaf, 0xce, 0xaf, 0x0b,
+                0xf1, 0x2b, 0x88, 0x1d, 0xc2, 0x00, 0xc9, 0x83, 0x3d, 0xa7, 0x26, 0xe9, 0x37, 0x6c,
+                0x2e, 0x32, 0xcf, 0xf7
+            ]
+        );
+        assert_eq!(
+            first_half(HmacSha256::mac(b"Jefe", b"what do ya want for nothing?")),
+            0x5bdcc146bf60754e6a042426089575c7
+        );
+        assert_eq!(
+            first_half(HmacSha256::mac(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            0x60e431591ee0b67f0d8a26aacbf5b77f
+        );
+    }
+
+    #[test]
+    fn it_verifies_in_pieces() {
+        let tag = HmacSha256::mac(b"Jefe", b"what do ya want for nothing?");
+        let mut hmac = HmacSha256::new(b"Jefe");
+        hmac.update(b"what do ya ");
+        let copy = hmac.clone();
+        hmac.update(b"want for nothing?");
+        assert!(hmac.clone().verify(&tag));
+        assert!(!hmac.clone().verify(&tag[..16]));
+        assert!(!hmac.clone().verify(&[]));
+        assert!(hmac.clone().verify_truncated(&tag[..16], 16));
+        assert!(!hmac.clone().verify_truncated(&tag[..16], 20));
+        assert!(!hmac.clone().verify_truncated(&tag[..15], 15));
+        assert!(!copy.verify(&tag));
+    }
+}
@@ -0,0 +1,100 @@
+/*!
+ * HMAC of RFC 2104 and FIPS 198-1 with SHA-256, the pseudo random function
+ * of PBKDF2.
+ *
+ * The key is padded or hashed to a block, and the message is hashed twice:
+ * once after the key xored with ipad and once, hashed, after the key xored
+ * with opad.
+ */
+use crate::math::Math;
+use crate::sha256::{Sha256, BLOCK_LEN, DIGEST_LEN};
+
+mod hmac_test;
+
+/**
+ * Length in bytes of a full tag
+ */
+pub const TAG_LEN: usize = DIGEST_LEN;
+
+/**
+ * Length in bytes of the shortest tag accepted by verify_truncated, half of
+ * a full one
+ */
+pub const MIN_TRUNCATED_TAG_LEN: usize = 16;
+
+/**
+ * The state of an HMAC computation. Cloning it after new gives a copy keyed
+ * with the same key without hashing the key again.
+ */
+#[derive(Clone)]
+pub struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    /**
+     * A key longer than a block is replaced by its digest
+     */
+    pub fn new(key: &[u8]) -> HmacSha256 {
+        let mut block = [0; BLOCK_LEN];
+        if key.len() > BLOCK_LEN {
+            block[..DIGEST_LEN].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let mut inner = Sha256::new();
+        let mut outer = Sha256::new();
+        for byte in block.iter_mut() {
+            *byte ^= 0x36;
+        }
+        inner.update(&block);
+        for byte in block.iter_mut() {
+            *byte ^= 0x36 ^ 0x5c;
+        }
+        outer.update(&block);
+        HmacSha256 { inner, outer }
+    }
+
+    /**
+     * The tag of a whole message
+     */
+    pub fn mac(key: &[u8], data: &[u8]) -> [u8; TAG_LEN] {
+        let mut hmac = HmacSha256::new(key);
+        hmac.update(data);
+        hmac.finalize()
+    }
+
+    /**
+     * Adds data to the message
+     */
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; TAG_LEN] {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+
+    /**
+     * Compares the full TAG_LEN bytes tag of the message with tag in constant
+     * time
+     */
+    pub fn verify(self, tag: &[u8]) -> bool {
+        self.verify_truncated(tag, TAG_LEN)
+    }
+
+    /**
+     * Compares a tag truncated to its first len bytes, where len is what the
+     * protocol agreed on and not the length of whatever tag was received.
+     * Lengths below MIN_TRUNCATED_TAG_LEN always fail.
+     */
+    pub fn verify_truncated(self, tag: &[u8], len: usize) -> bool {
+        let expected = self.finalize();
+        (MIN_TRUNCATED_TAG_LEN..=TAG_LEN).contains(&len)
+            && tag.len() == len
+            && Math::constant_time_eq(&expected[..len], tag)
+    }
+}
@@ -0,0 +1,256 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod io_test {
+    use crate::cbc::Cbc;
+    use crate::cipher::Key;
+    use crate::ctr::Ctr;
+    use crate::error::Error;
+    use crate::gcm::Gcm;
+    use crate::io::{DecryptReader, EncryptWriter, Mode};
+    use std::io::{self, Read, Write};
+    use std::vec::Vec;
+
+    const KEY: Key = Key::Aes256([1, 2, 3, 4, 5, 6, 7, 8]);
+    const IV: [u8; 16] = [9; 16];
+    const NONCE: [u8; 12] = [3; 12];
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    fn modes() -> [Mode<'static>; 3] {
+        [
+            Mode::Ctr { iv: IV },
+            Mode::Cbc { iv: IV },
+            Mode::Gcm {
+                nonce: &NONCE,
+                aad: b"header",
+            },
+        ]
+    }
+
+    /**
+     * Writes the message in pieces of the given length
+     */
+    fn encrypt(mode: Mode, message: &[u8], piece: usize) -> Vec<u8> {
+        let mut writer = EncryptWriter::new(Vec::new(), &KEY, mode).unwrap();
+        for chunk in message.chunks(piece) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.flush().unwrap();
+        writer.finish().unwrap()
+    }
+
+    /**
+     * Reads the plaintext with a buffer of the given length
+     */
+    fn decrypt(mode: Mode, ciphertext: &[u8], piece: usize) -> Result<Vec<u8>, Error> {
+        let mut reader = DecryptReader::new(ciphertext, &KEY, mode)?;
+        let mut plaintext = Vec::new();
+        let mut buffer = [0; 100];
+        loop {
+            let length = reader.read(&mut buffer[..piece])?;
+            if length == 0 {
+                return Ok(plaintext);
+            }
+            plaintext.extend_from_slice(&buffer[..length]);
+        }
+    }
+
+    #[test]
+    fn it_matches_the_modes() {
+        let message = message(5000);
+        let mut expected = message.clone();
+        Ctr::new(&KEY, &IV).apply_keystream(&mut expected);
+        assert_eq!(encrypt(Mode::Ctr { iv: IV }, &message, 33), expected);
+
+        let mut expected = message.clone();
+        expected.resize(5008, 0);
+        Cbc::new(&KEY, &IV)
+            .encrypt_padded(&mut expected, 5000)
+            .unwrap();
+        assert_eq!(encrypt(Mode::Cbc { iv: IV }, &message, 33), expected);
+
+        let mut expected = message.clone();
+        let tag = Gcm::new(&KEY)
+            .encrypt(&NONCE, b"header", &mut expected)
+            .unwrap();
+        expected.extend_from_slice(&tag);
+        assert_eq!(encrypt(modes()[2], &message, 5000), expected);
+    }
+
+    #[test]
+    fn it_round_trips() {
+        for mode in modes().iter() {
+            for &length in [0, 1, 15, 16, 17, 4096, 4097, 9000].iter() {
+                let message = message(length);
+                let ciphertext = encrypt(*mode, &message, 1000);
+                assert_eq!(ciphertext, encrypt(*mode, &message, 7));
+                assert_eq!(decrypt(*mode, &ciphertext, 100).unwrap(), message);
+                assert_eq!(decrypt(*mode, &ciphertext, 3).unwrap(), message);
+            }
+        }
+    }
+
+    #[test]
+    fn it_releases_no_unauthenticated_plaintext() {
+        let mode = modes()[2];
+        let mut ciphertext = encrypt(mode, &message(10000), 100);
+        ciphertext[5000] ^= 1;
+        let mut reader = DecryptReader::new(&ciphertext[..], &KEY, mode).unwrap();
+        let mut plaintext = Vec::new();
+        let error = reader.read_to_end(&mut plaintext).unwrap_err();
+        assert_eq!(Error::from(error), Error::AuthenticationFailed);
+        assert!(plaintext.is_empty());
+        assert_eq!(
+            decrypt(mode, &ciphertext[..15], 10),
+            Err(Error::InvalidLength)
+        );
+        let other_aad = Mode::Gcm {
+            nonce: &NONCE,
+            aad: b"footer",
+        };
+        ciphertext[5000] ^= 1;
+        assert_eq!(
+            decrypt(other_aad, &ciphertext, 10),
+            Err(Error::AuthenticationFailed)
+        );
+    }
+
+    /**
+     * Gives its bytes a few at a time, and fails once with the given kind of
+     * error when less than 100 are left
+     */
+    struct FailingOnce<'a> {
+        bytes: &'a [u8],
+        failure: Option<io::ErrorKind>,
+    }
+
+    impl Read for FailingOnce<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.bytes.len() < 100 {
+                if let Some(kind) = self.failure.take() {
+                    return Err(kind.into());
+                }
+            }
+            let length = buffer.len().min(self.bytes.len()).min(50);
+            buffer[..length].copy_from_slice(&self.bytes[..length]);
+            self.bytes = &self.bytes[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn it_keeps_failing_after_an_error() {
+        let mode = modes()[2];
+        let ciphertext = encrypt(mode, &message(1000), 100);
+        let inner = FailingOnce {
+            bytes: &ciphertext,
+            failure: Some(io::ErrorKind::WouldBlock),
+        };
+        let mut reader = DecryptReader::new(inner, &KEY, mode).unwrap();
+        let mut buffer = [0; 100];
+        for _ in 0..2 {
+            assert_eq!(
+                reader.read(&mut buffer).unwrap_err().kind(),
+                io::ErrorKind::WouldBlock
+            );
+        }
+    }
+
+    #[test]
+    fn it_reads_again_when_interrupted() {
+        for &mode in modes().iter() {
+            let ciphertext = encrypt(mode, &message(1000), 100);
+            let inner = FailingOnce {
+                bytes: &ciphertext,
+                failure: Some(io::ErrorKind::Interrupted),
+            };
+            let mut reader = DecryptReader::new(inner, &KEY, mode).unwrap();
+            let mut plaintext = Vec::new();
+            let mut buffer = [0; 100];
+            loop {
+                // an Interrupted error would make read_to_end try again forever
+                let length = reader.read(&mut buffer).unwrap();
+                if length == 0 {
+                    break;
+                }
+                plaintext.extend_from_slice(&buffer[..length]);
+            }
+            assert_eq!(plaintext, message(1000));
+        }
+    }
+
+    /**
+     * Takes limit bytes, then fails
+     */
+    struct Full {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Full {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            if self.written.len() == self.limit {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            let length = data.len().min(self.limit - self.written.len());
+            self.written.extend_from_slice(&data[..length]);
+            Ok(length)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn it_stops_after_a_failed_write() {
+        for &mode in modes().iter() {
+            let full = Full {
+                written: Vec::new(),
+                limit: 40,
+            };
+            let mut writer = EncryptWriter::new(full, &KEY, mode).unwrap();
+            writer.write_all(&message(32)).unwrap();
+            let error = writer.write(&message(32)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+            // what was ciphered but not written can't be given again
+            assert_eq!(
+                writer.write(&message(32)).unwrap_err().kind(),
+                io::ErrorKind::WriteZero
+            );
+            assert_eq!(writer.flush().unwrap_err().kind(), io::ErrorKind::WriteZero);
+            assert_eq!(writer.get_ref().written.len(), 40);
+            assert_eq!(
+                writer.finish().err(),
+                Some(Error::Io(io::ErrorKind::WriteZero))
+            );
+        }
+    }
+
+    #[test]
+    fn it_refuses_malformed_cbc() {
+        let mode = Mode::Cbc { iv: IV };
+        let ciphertext = encrypt(mode, &message(40), 40);
+        assert_eq!(
+            decrypt(mode, &ciphertext[..40], 10),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(decrypt(mode, &[], 10), Err(Error::InvalidLength));
+        // the key of another message gives garbage in the padding
+        let mut reader = DecryptReader::new(&ciphertext[..], &Key::Aes128([0; 4]), mode).unwrap();
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(Error::from(error), Error::InvalidPadding);
+    }
+
+    #[test]
+    fn it_refuses_empty_nonces() {
+        let mode = Mode::Gcm {
+            nonce: &[],
+            aad: &[],
+        };
+        assert!(EncryptWriter::new(Vec::new(), &KEY, mode).is_err());
+        assert!(DecryptReader::new(&[][..], &KEY, mode).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+ * Encryption and decryption of streams: an EncryptWriter ciphers whatever is
+ * written to it before passing it on, a DecryptReader deciphers whatever
+ * it reads. They take care of the block boundaries, of the CBC padding and of
+ * the GCM tag, which is appended to the ciphertext. Only available with the
+ * std feature.
+ *
+ * The errors of the crate are returned inside io::Error, Error::from gets them
+ * back. After an error a reader or a writer can't be used anymore.
+ */
+use crate::cbc::{pad_block, unpad_block, Cbc};
+use crate::cipher::{Decryptor, Encryptor, Key};
+use crate::ctr::Ctr;
+use crate::error::Error;
+use crate::gcm::{Gcm, GcmEncryption, TAG_LEN};
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+mod io_test;
+
+/**
+ * Bytes ciphered at once, the writer processes at most this many bytes per write
+ * and the reader reads at most this many bytes from the underlying reader at once
+ */
+const CHUNK_LEN: usize = 4096;
+
+/**
+ * The mode of operation of a reader or a writer, with its parameters
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode<'a> {
+    Ctr {
+        iv: [u8; 16],
+    },
+    /**
+     * CBC with PKCS #7 padding
+     */
+    Cbc {
+        iv: [u8; 16],
+    },
+    /**
+     * GCM with a TAG_LEN bytes long tag after the ciphertext. The associated
+     * data is authenticated but not written.
+     */
+    Gcm {
+        nonce: &'a [u8],
+        aad: &'a [u8],
+    },
+}
+
+/**
+ * Ciphers the data written to it and writes it to inner. finish must be called
+ * at the end, to write the padding or the tag.
+ *
+ * The state of the mode moves on as soon as data is ciphered, so once inner
+ * has failed to take some ciphertext the output can't be completed: every
+ * following write, flush and finish return the same error.
+ */
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    state: EncryptState,
+    pending: [u8; 16],
+    pending_len: usize,
+    error: Option<Error>,
+}
+
+enum EncryptState {
+    Ctr(Ctr),
+    Cbc(Cbc<Encryptor>),
+    Gcm(GcmEncryption),
+}
+
+/**
+ * Reads the ciphertext from inner and deciphers it.
+ *
+ * With GCM the whole ciphertext is read and its tag checked before the first
+ * byte of plaintext is returned, so a tampered ciphertext gives an
+ * Error::AuthenticationFailed and no plaintext at all. This means that the
+ * whole message is kept in memory.
+ *
+ * After an error, including one of inner, every read returns the same error.
+ */
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    state: DecryptState,
+    output: Vec<u8>,
+    position: usize,
+    finished: bool,
+    error: Option<Error>,
+}
+
+enum DecryptState {
+    Ctr(Ctr),
+    Cbc {
+        cbc: Cbc<Decryptor>,
+        ciphertext: Vec<u8>,
+    },
+    Gcm {
+        gcm: Gcm,
+        nonce: Vec<u8>,
+        aad: Vec<u8>,
+    },
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /**
+     * Fails only if the GCM nonce is empty
+     */
+    pub fn new(inner: W, key: &Key, mode: Mode) -> Result<EncryptWriter<W>, Error> {
+        let encryptor = Encryptor::from_key(key);
+        let state = match mode {
+            Mode::Ctr { iv } => EncryptState::Ctr(Ctr::from_encryptor(encryptor, &iv)),
+            Mode::Cbc { iv } => EncryptState::Cbc(Cbc::from_cipher(encryptor, &iv)),
+            Mode::Gcm { nonce, aad } => {
+                EncryptState::Gcm(GcmEncryption::new(encryptor, nonce, aad)?)
+            }
+        };
+        Ok(EncryptWriter {
+            inner,
+            state,
+            pending: [0; 16],
+            pending_len: 0,
+            error: None,
+        })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /**
+     * Writes the last padded block for CBC or the tag for GCM, flushes and gives
+     * back the underlying writer
+     */
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        match self.state {
+            EncryptState::Ctr(_) => {}
+            EncryptState::Cbc(mut cbc) => {
+                let mut block = pad_block(&self.pending[..self.pending_len])?;
+                cbc.encrypt_blocks(&mut block)?;
+                self.inner.write_all(&block)?;
+            }
+            EncryptState::Gcm(encryption) => self.inner.write_all(&encryption.finalize())?,
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /**
+     * Ciphers at most CHUNK_LEN bytes of data and writes what can be written,
+     * returns how many bytes of data were taken
+     */
+    fn write_chunk(&mut self, data: &[u8]) -> Result<usize, Error> {
+        let taken = data.len().min(CHUNK_LEN);
+        let mut chunk = [0; CHUNK_LEN + 16];
+        let length = match &mut self.state {
+            EncryptState::Ctr(ctr) => {
+                chunk[..taken].copy_from_slice(&data[..taken]);
+                ctr.apply_keystream(&mut chunk[..taken]);
+                taken
+            }
+            EncryptState::Gcm(encryption) => {
+                chunk[..taken].copy_from_slice(&data[..taken]);
+                encryption.update(&mut chunk[..taken])?;
+                taken
+            }
+            EncryptState::Cbc(cbc) => {
+                // only whole blocks are ciphered, the rest waits for more data
+                let total = self.pending_len + taken;
+                chunk[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+                chunk[self.pending_len..total].copy_from_slice(&data[..taken]);
+                let whole = total - total % 16;
+                cbc.encrypt_blocks(&mut chunk[..whole])?;
+                self.pending_len = total - whole;
+                self.pending[..self.pending_len].copy_from_slice(&chunk[whole..total]);
+                whole
+            }
+        };
+        self.inner.write_all(&chunk[..length])?;
+        Ok(taken)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        self.write_chunk(data).map_err(|error| {
+            self.error = Some(error);
+            error.into()
+        })
+    }
+
+    /**
+     * Flushes the underlying writer. With CBC the last incomplete block is
+     * only written by finish.
+     */
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> DecryptReader<R> {
+    /**
+     * Fails only if the GCM nonce is empty
+     */
+    pub fn new(inner: R, key: &Key, mode: Mode) -> Result<DecryptReader<R>, Error> {
+        let state = match mode {
+            Mode::Ctr { iv } => DecryptState::Ctr(Ctr::new(key, &iv)),
+            Mode::Cbc { iv } => DecryptState::Cbc {
+                cbc: Cbc::from_cipher(Decryptor::from_key(key), &iv),
+                ciphertext: Vec::new(),
+            },
+            Mode::Gcm { nonce, aad } => {
+                if nonce.is_empty() {
+                    return Err(Error::InvalidLength);
+                }
+                DecryptState::Gcm {
+                    gcm: Gcm::new(key),
+                    nonce: nonce.to_vec(),
+                    aad: aad.to_vec(),
+                }
+            }
+        };
+        Ok(DecryptReader {
+            inner,
+            state,
+            output: Vec::new(),
+            position: 0,
+            finished: false,
+            error: None,
+        })
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /**
+     * Deciphers the next part of the stream into output
+     */
+    fn fill(&mut self) -> Result<(), Error> {
+        self.output.clear();
+        self.position = 0;
+        match &mut self.state {
+            DecryptState::Ctr(ctr) => {
+                let mut chunk = [0; CHUNK_LEN];
+                let length = read_retrying(&mut self.inner, &mut chunk)?;
+                ctr.apply_keystream(&mut chunk[..length]);
+                self.output.extend_from_slice(&chunk[..length]);
+                self.finished = length == 0;
+            }
+            DecryptState::Cbc { cbc, ciphertext } => {
+                let mut chunk = [0; CHUNK_LEN];
+                let length = read_retrying(&mut self.inner, &mut chunk)?;
+                if length == 0 {
+                    // the last block is the only one left and holds the padding
+                    if ciphertext.len() != 16 {
+                        return Err(Error::InvalidLength);
+                    }
+                    let mut block = [0; 16];
+                    block.copy_from_slice(ciphertext);
+                    cbc.decrypt_blocks(&mut block)?;
+                    let kept = unpad_block(&block)?;
+                    self.output.extend_from_slice(&block[..kept]);
+                    self.finished = true;
+                } else {
+                    ciphertext.extend_from_slice(&chunk[..length]);
+                    // the last whole block could be the padded one, it waits for the end
+                    let kept = match ciphertext.len() % 16 {
+                        0 => 16,
+                        remainder => remainder,
+                    };
+                    let ready = ciphertext.len() - kept;
+                    cbc.decrypt_blocks(&mut ciphertext[..ready])?;
+                    self.output.extend(ciphertext.drain(..ready));
+                }
+            }
+            DecryptState::Gcm { gcm, nonce, aad } => {
+                self.inner.read_to_end(&mut self.output)?;
+                if self.output.len() < TAG_LEN {
+                    self.output.clear();
+                    return Err(Error::InvalidLength);
+                }
+                let text_len = self.output.len() - TAG_LEN;
+                let (text, tag) = self.output.split_at_mut(text_len);
+                if let Err(error) = gcm.decrypt(nonce, aad, text, tag) {
+                    self.output.clear();
+                    return Err(error);
+                }
+                self.output.truncate(text_len);
+                self.finished = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Reads from inner like Read::read, but reads again when a signal interrupts
+ * the read, as read_to_end and io::copy do: the readers of the crate keep
+ * their errors, and an interruption must not become one.
+ */
+pub(crate) fn read_retrying<R: Read>(inner: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match inner.read(buffer) {
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            result => return result,
+        }
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        while self.position == self.output.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            if let Err(error) = self.fill() {
+                // a GCM message read in part must not come out as plaintext
+                self.output.clear();
+                self.position = 0;
+                self.error = Some(error);
+                return Err(error.into());
+            }
+        }
+        let length = buffer.len().min(self.output.len() - self.position);
+        buffer[..length].copy_from_slice(&self.output[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
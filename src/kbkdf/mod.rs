@@ -0,0 +1,227 @@
+/*!
+ * Key based key derivation functions of NIST SP 800-108 with CMAC as the PRF,
+ * in counter, feedback and double pipeline mode.
+ *
+ * Each mode calls the PRF, keyed with the master key, on the fixed input data,
+ * an optional counter and, in feedback and double pipeline mode, an iteration
+ * variable, concatenating the outputs until the requested length is reached.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key, KeySize};
+use crate::cmac::Cmac;
+use core::fmt;
+
+mod kbkdf_test;
+
+/**
+ * How the PRF outputs are chained
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode<'a> {
+    /**
+     * Each output only depends on the counter and the fixed input data,
+     * the counter is required
+     */
+    Counter,
+    /**
+     * The iteration variable is the previous output, or the IV for the first one.
+     * The IV can be empty.
+     */
+    Feedback { iv: &'a [u8] },
+    /**
+     * The iteration variable comes from a first pipeline of PRF calls that
+     * starts from the fixed input data
+     */
+    DoublePipeline,
+}
+
+/**
+ * Where the counter goes in the input of the PRF. Before the iteration variable
+ * and before the fixed input data are the same thing in counter mode.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterPosition {
+    BeforeIterationVariable,
+    BeforeFixedData,
+    AfterFixedData,
+}
+
+/**
+ * The counter, written as a big endian number of bits bits, which can
+ * be 8, 16, 24 or 32. It starts from 1.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counter {
+    pub bits: u32,
+    pub position: CounterPosition,
+}
+
+/**
+ * The fixed input data, either built as suggested by the standard or given as is
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixedInput<'a> {
+    /**
+     * Label || 0x00 || Context || L, with L the length of the output in bits
+     * written as a 32 bits big endian number
+     */
+    LabelContext { label: &'a [u8], context: &'a [u8] },
+    /**
+     * Fixed input data produced by the caller, that must include what is needed
+     * to bind it to the output length
+     */
+    Raw(&'a [u8]),
+}
+
+/**
+ * Everything that, together with the key and the output length, determines the
+ * derived keying material
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams<'a> {
+    pub mode: Mode<'a>,
+    pub counter: Option<Counter>,
+    pub fixed_input: FixedInput<'a>,
+}
+
+/**
+ * Reasons for which no keying material can be derived
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KdfError {
+    /**
+     * The counter width is not 8, 16, 24 or 32 bits
+     */
+    CounterWidth,
+    /**
+     * Counter mode was requested without a counter
+     */
+    MissingCounter,
+    /**
+     * The output needs more PRF calls than the counter can count, or its
+     * length in bits doesn't fit in 32 bits
+     */
+    OutputLength,
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::CounterWidth => write!(f, "counter width must be 8, 16, 24 or 32 bits"),
+            KdfError::MissingCounter => write!(f, "counter mode requires a counter"),
+            KdfError::OutputLength => write!(f, "output too long"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KdfError {}
+
+/**
+ * A key derivation function keyed with the master key, which can derive any
+ * number of keys
+ */
+pub struct Kbkdf<C = Encryptor> {
+    prf: Cmac<C>,
+}
+
+impl Kbkdf {
+    pub fn new(master_key: &Key) -> Kbkdf {
+        Kbkdf {
+            prf: Cmac::new(master_key),
+        }
+    }
+}
+
+impl<C: BlockEncrypt> Kbkdf<C> {
+    /**
+     * The PRF is the CMAC of the given encryptor, keyed with the master key
+     */
+    pub fn from_encryptor(encryptor: C) -> Kbkdf<C> {
+        Kbkdf {
+            prf: Cmac::from_encryptor(encryptor),
+        }
+    }
+
+    /**
+     * Fills output with keying material
+     */
+    pub fn derive(&mut self, params: &KdfParams, output: &mut [u8]) -> Result<(), KdfError> {
+        let blocks = output.len().div_ceil(16);
+        if output.len() > (u32::MAX / 8) as usize {
+            return Err(KdfError::OutputLength);
+        }
+        if let Some(counter) = params.counter {
+            if !matches!(counter.bits, 8 | 16 | 24 | 32) {
+                return Err(KdfError::CounterWidth);
+            }
+            if blocks as u64 > (1u64 << counter.bits) - 1 {
+                return Err(KdfError::OutputLength);
+            }
+        } else if params.mode == Mode::Counter {
+            return Err(KdfError::MissingCounter);
+        }
+        let length = (output.len() * 8) as u32;
+
+        let mut previous = [0; 16];
+        let mut pipeline = [0; 16];
+        for (i, chunk) in output.chunks_mut(16).enumerate() {
+            let i = i as u32 + 1;
+            let iteration_variable: &[u8] = match params.mode {
+                Mode::Counter => &[],
+                Mode::Feedback { iv } if i == 1 => iv,
+                Mode::Feedback { .. } => &previous,
+                Mode::DoublePipeline => {
+                    if i == 1 {
+                        self.absorb_fixed_input(params.fixed_input, length);
+                    } else {
+                        self.prf.update(&pipeline);
+                    }
+                    pipeline = self.prf.finalize();
+                    &pipeline
+                }
+            };
+            let position = params.counter.map(|counter| counter.position);
+            let width = params
+                .counter
+                .map_or(0, |counter| counter.bits as usize / 8);
+            let counter = i.to_be_bytes();
+            let counter = &counter[4 - width..];
+            if position == Some(CounterPosition::BeforeIterationVariable) {
+                self.prf.update(counter);
+            }
+            self.prf.update(iteration_variable);
+            if position == Some(CounterPosition::BeforeFixedData) {
+                self.prf.update(counter);
+            }
+            self.absorb_fixed_input(params.fixed_input, length);
+            if position == Some(CounterPosition::AfterFixedData) {
+                self.prf.update(counter);
+            }
+            previous = self.prf.finalize();
+            chunk.copy_from_slice(&previous[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    /**
+     * A key of the given size, ready for Cipher::from_key
+     */
+    pub fn derive_key(&mut self, params: &KdfParams, size: KeySize) -> Result<Key, KdfError> {
+        let mut bytes = [0; 32];
+        self.derive(params, &mut bytes[..size.bytes()])?;
+        Ok(Key::from_valid_bytes(&bytes[..size.bytes()]))
+    }
+
+    fn absorb_fixed_input(&mut self, fixed_input: FixedInput, length: u32) {
+        match fixed_input {
+            FixedInput::LabelContext { label, context } => {
+                self.prf.update(label);
+                self.prf.update(&[0]);
+                self.prf.update(context);
+                self.prf.update(&length.to_be_bytes());
+            }
+            FixedInput::Raw(data) => self.prf.update(data),
+        }
+    }
+}
@@ -1,15 +1,17 @@
 #[cfg(test)]
-
+#[allow(clippy::module_inception)]
 mod key_manager_test {
 
-    use crate::key_manager::KeyManager;
+    use crate::error::Error;
+    use crate::key_manager::{InvKeyManager, KeyManager};
+    use crate::math::S_BOX;
 
     #[test]
     fn it_creates_new_128() {
         let key_manager = KeyManager::new_128(&[0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c]);
         assert_eq!(
             *key_manager.key(),
-            vec![
+            [
                 0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c, 0xa0fafe17, 0x88542cb1, 0x23a33939,
                 0x2a6c7605, 0xf2c295f2, 0x7a96b943, 0x5935807a, 0x7359f67f, 0x3d80477d, 0x4716fe3e,
                 0x1e237e44, 0x6d7a883b, 0xef44a541, 0xa8525b7f, 0xb671253b, 0xdb0bad00, 0xd4d1c6f8,
@@ -28,7 +30,7 @@ mod key_manager_test {
         ]);
         assert_eq!(
             *key_manager.key(),
-            vec![
+            [
                 0x8e73b0f7, 0xda0e6452, 0xc810f32b, 0x809079e5, 0x62f8ead2, 0x522c6b7b, 0xfe0c91f7,
                 0x2402f5a5, 0xec12068e, 0x6c827f6b, 0x0e7a95b9, 0x5c56fec2, 0x4db7b4bd, 0x69b54118,
                 0x85a74796, 0xe92538fd, 0xe75fad44, 0xbb095386, 0x485af057, 0x21efb14f, 0xa448f6d9,
@@ -50,7 +52,7 @@ mod key_manager_test {
 
         assert_eq!(
             *key_manager.key(),
-            vec![
+            [
                 0x603deb10, 0x15ca71be, 0x2b73aef0, 0x857d7781, 0x1f352c07, 0x3b6108d7, 0x2d9810a3,
                 0x0914dff4, 0x9ba35411, 0x8e6925af, 0xa51a8b5f, 0x2067fcde, 0xa8b09c1a, 0x93d194cd,
                 0xbe49846e, 0xb75d5b9a, 0xd59aecb8, 0x5bf3c917, 0xfee94248, 0xde8ebe96, 0xb5a9328a,
@@ -63,4 +65,98 @@ mod key_manager_test {
             ]
         )
     }
+
+    #[test]
+    fn it_creates_inverse_128() {
+        let key_manager = KeyManager::new_128(&[0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+        let inv_key_manager = InvKeyManager::new(&key_manager);
+        let inv_key = inv_key_manager.inv_key();
+        assert_eq!(inv_key[..4], key_manager.key()[..4]);
+        assert_eq!(
+            inv_key[36..],
+            [
+                0x13aa29be, 0x9c8faff6, 0xf770f580, 0x00f7bf03, 0x13111d7f, 0xe3944a17, 0xf307a78b,
+                0x4d2b30c5
+            ]
+        );
+    }
+
+    #[test]
+    fn it_expands_only_needed_words() {
+        let key = [
+            0x603deb10, 0x15ca71be, 0x2b73aef0, 0x857d7781, 0x1f352c07, 0x3b6108d7, 0x2d9810a3,
+            0x0914dff4,
+        ];
+        let full = KeyManager::new_256(&key);
+        for rounds in 1..=14 {
+            let reduced = KeyManager::with_params_256(&key, rounds, &S_BOX).unwrap();
+            assert_eq!(reduced.rounds(), rounds);
+            assert_eq!(reduced.key(), &full.key()[..4 * (rounds + 1)]);
+        }
+    }
+
+    #[test]
+    fn it_inverts_128() {
+        let key = [0x2b7e1516, 0x28aed2a6, 0xabf71588, 0x09cf4f3c];
+        let key_manager = KeyManager::new_128(&key);
+        let expanded = key_manager.key();
+        assert_eq!(
+            KeyManager::invert_key_128(&[0xd014f9a8, 0xc9ee2589, 0xe13f0cc8, 0xb6630ca6], 40),
+            Ok(key)
+        );
+        for start in 0..=expanded.len() - 4 {
+            let mut words = [0; 4];
+            words.copy_from_slice(&expanded[start..start + 4]);
+            assert_eq!(KeyManager::invert_key_128(&words, start), Ok(key));
+        }
+    }
+
+    #[test]
+    fn it_inverts_192() {
+        let key = [
+            0x8e73b0f7, 0xda0e6452, 0xc810f32b, 0x809079e5, 0x62f8ead2, 0x522c6b7b,
+        ];
+        let key_manager = KeyManager::new_192(&key);
+        let expanded = key_manager.key();
+        for start in 0..=expanded.len() - 6 {
+            let mut words = [0; 6];
+            words.copy_from_slice(&expanded[start..start + 6]);
+            assert_eq!(KeyManager::invert_key_192(&words, start), Ok(key));
+        }
+    }
+
+    #[test]
+    fn it_inverts_256() {
+        let key = [
+            0x603deb10, 0x15ca71be, 0x2b73aef0, 0x857d7781, 0x1f352c07, 0x3b6108d7, 0x2d9810a3,
+            0x0914dff4,
+        ];
+        let key_manager = KeyManager::new_256(&key);
+        let expanded = key_manager.key();
+        for start in 0..=expanded.len() - 8 {
+            let mut words = [0; 8];
+            words.copy_from_slice(&expanded[start..start + 8]);
+            assert_eq!(KeyManager::invert_key_256(&words, start), Ok(key));
+        }
+    }
+
+    #[test]
+    fn it_refuses_words_past_the_expanded_key() {
+        assert_eq!(
+            KeyManager::invert_key_128(&[0, 0, 0, 0], 41),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            KeyManager::invert_key_256(&[0; 8], usize::MAX - 3),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn it_refuses_too_many_rounds() {
+        assert_eq!(
+            KeyManager::with_params_128(&[0, 0, 0, 0], 11, &S_BOX).err(),
+            Some(Error::InvalidRounds(10))
+        );
+    }
 }
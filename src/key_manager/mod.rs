@@ -1,5 +1,6 @@
 use crate::aes_matrix::AesMatrix;
-use crate::math::Math;
+use crate::error::Error;
+use crate::math::{Math, S_BOX};
 
 mod key_manager_test;
 
@@ -12,45 +13,109 @@ static ROUND_CONSTANT: [u32; 10] = [
 ];
 
 /**
- * Object used by the cipher to encrypt and decrypt data.
- * It holds the number of encryption rounds and the keys
- * needed to both encrypt and decrypt.
+ * Number of words in the longest expanded key, the one for 256 bits long
+ * keys: 4 words for each of the 14 rounds plus the initial one.
+ */
+pub const MAX_EXPANDED_KEY_WORDS: usize = 60;
+
+/**
+ * Object used by the cipher to encrypt data.
+ * It holds the number of encryption rounds and the expanded key.
+ *
+ * The expanded key is kept in a fixed size array big enough for the
+ * longest key so that no heap allocation is needed. Only the first
+ * 4 * (rounds + 1) words of the array are meaningful.
  */
 pub struct KeyManager {
     rounds: usize,
-    key: Vec<u32>,
-    inverse_key: Vec<u32>,
+    key: [u32; MAX_EXPANDED_KEY_WORDS],
+}
+
+/**
+ * Object used by the cipher to decrypt data with the equivalent inverse cipher.
+ * It holds the number of rounds and the expanded key modified as the
+ * equivalent inverse cipher requires. It is produced from a KeyManager only
+ * when decryption is needed, so that encryption-only users don't pay for it.
+ */
+pub struct InvKeyManager {
+    rounds: usize,
+    inverse_key: [u32; MAX_EXPANDED_KEY_WORDS],
 }
 
 impl KeyManager {
     /**
      * Initializes the object based on key length
      */
-
     pub fn new_128(in_key: &[u32; 4]) -> KeyManager {
-        let (key, inverse_key) = Self::expand_key_128(in_key);
         KeyManager {
             rounds: 10,
-            key,
-            inverse_key,
+            key: Self::expand_key_128(in_key, 10, &S_BOX),
         }
     }
 
     pub fn new_192(in_key: &[u32; 6]) -> KeyManager {
-        let (key, inverse_key) = Self::expand_key_192(in_key);
         KeyManager {
             rounds: 12,
-            key,
-            inverse_key,
+            key: Self::expand_key_192(in_key, 12, &S_BOX),
         }
     }
 
     pub fn new_256(in_key: &[u32; 8]) -> KeyManager {
-        let (key, inverse_key) = Self::expand_key_256(in_key);
         KeyManager {
             rounds: 14,
-            key,
-            inverse_key,
+            key: Self::expand_key_256(in_key, 14, &S_BOX),
+        }
+    }
+
+    /**
+     * Initializes the object for a cipher with the given number of rounds,
+     * expanding only the 4 * (rounds + 1) words that such a cipher needs.
+     * With the AES secure box the words that are produced are the same of the full
+     * key expansion. rounds must be between 1 and the standard number of rounds
+     * for the key length, otherwise the result is Error::InvalidRounds.
+     * s_box is the table used while expanding the key.
+     */
+    pub fn with_params_128(
+        in_key: &[u32; 4],
+        rounds: usize,
+        s_box: &[u8; 256],
+    ) -> Result<KeyManager, Error> {
+        Self::check_rounds(rounds, 10)?;
+        Ok(KeyManager {
+            rounds,
+            key: Self::expand_key_128(in_key, rounds, s_box),
+        })
+    }
+
+    pub fn with_params_192(
+        in_key: &[u32; 6],
+        rounds: usize,
+        s_box: &[u8; 256],
+    ) -> Result<KeyManager, Error> {
+        Self::check_rounds(rounds, 12)?;
+        Ok(KeyManager {
+            rounds,
+            key: Self::expand_key_192(in_key, rounds, s_box),
+        })
+    }
+
+    pub fn with_params_256(
+        in_key: &[u32; 8],
+        rounds: usize,
+        s_box: &[u8; 256],
+    ) -> Result<KeyManager, Error> {
+        Self::check_rounds(rounds, 14)?;
+        Ok(KeyManager {
+            rounds,
+            key: Self::expand_key_256(in_key, rounds, s_box),
+        })
+    }
+
+    fn check_rounds(rounds: usize, max_rounds: usize) -> Result<(), Error> {
+        if rounds >= 1 && rounds <= max_rounds {
+            Ok(())
+        } else {
+            Err(Error::InvalidRounds(max_rounds))
         }
     }
 
@@ -60,123 +125,190 @@ impl KeyManager {
      * I felt that the expansion function would be ugli with 4 parameters
      * plus the return type and not being able to express the length of
      * the array in input.
-     *
-     * This function generates both the encryption key and the key for the
-     * quivalent inverse cipher.
      */
-    fn expand_key_128(key: &[u32; 4]) -> (Vec<u32>, Vec<u32>) {
+    fn expand_key_128(
+        key: &[u32; 4],
+        nr: usize,
+        s_box: &[u8; 256],
+    ) -> [u32; MAX_EXPANDED_KEY_WORDS] {
         let nk = 4;
-        let nr = 10;
-        let mut expanded_key: Vec<u32> = Vec::new();
-        for i in 0..nk {
-            expanded_key.push(key[i]);
-        }
-        Self::populate_key_128_192(&mut expanded_key, nk, nr);
-        let expanded_key_inverse = Self::produce_inverse_key(&expanded_key);
-        (expanded_key, expanded_key_inverse)
+        let mut expanded_key = [0; MAX_EXPANDED_KEY_WORDS];
+        expanded_key[..nk].copy_from_slice(key);
+        Self::populate_key_128_192(&mut expanded_key, nk, nr, s_box);
+        expanded_key
     }
 
-    fn expand_key_192(key: &[u32; 6]) -> (Vec<u32>, Vec<u32>) {
+    fn expand_key_192(
+        key: &[u32; 6],
+        nr: usize,
+        s_box: &[u8; 256],
+    ) -> [u32; MAX_EXPANDED_KEY_WORDS] {
         let nk = 6;
-        let nr = 12;
-        let mut expanded_key: Vec<u32> = Vec::new();
-        for i in 0..nk {
-            expanded_key.push(key[i]);
-        }
-        Self::populate_key_128_192(&mut expanded_key, nk, nr);
-        let expanded_key_inverse = Self::produce_inverse_key(&expanded_key);
-        (expanded_key, expanded_key_inverse)
+        let mut expanded_key = [0; MAX_EXPANDED_KEY_WORDS];
+        expanded_key[..nk].copy_from_slice(key);
+        Self::populate_key_128_192(&mut expanded_key, nk, nr, s_box);
+        expanded_key
     }
 
     /**
      * The expansion procedure for the 256 bit long key is a bit different from the
      * one for the other lengths so I've kept it separate.
      */
-    fn expand_key_256(key: &[u32; 8]) -> (Vec<u32>, Vec<u32>) {
-        let mut expanded_key: Vec<u32> = Vec::new();
-        for i in 0..8 {
-            expanded_key.push(key[i]);
-        }
-        Self::populate_key_256(&mut expanded_key);
-        let expanded_key_inverse = Self::produce_inverse_key(&expanded_key);
-        (expanded_key, expanded_key_inverse)
-    }
-
-    /**
-     * The inverse key is just the original but with the
-     * inverse_mix_columns transformation applied to every
-     * word as a column. The first and last element of this
-     * key actually shouldn't be transformed but since the struct holds
-     * the non-transformed key too and this isn't a function that gets called
-     * often I decided to leave that pretty looking loop even if it transfroms
-     * the whole key
-     */
-    fn produce_inverse_key(key: &Vec<u32>) -> Vec<u32> {
-        let mut expanded_key_inverse = Vec::new();
-        for word in key {
-            let mut inv_words = [word.clone(), 0, 0, 0];
-            AesMatrix::inv_mix_column_a(&mut inv_words, 0);
-            expanded_key_inverse.push(inv_words[0]);
-        }
-        expanded_key_inverse
+    fn expand_key_256(
+        key: &[u32; 8],
+        nr: usize,
+        s_box: &[u8; 256],
+    ) -> [u32; MAX_EXPANDED_KEY_WORDS] {
+        let mut expanded_key = [0; MAX_EXPANDED_KEY_WORDS];
+        expanded_key[..8].copy_from_slice(key);
+        Self::populate_key_256(&mut expanded_key, nr, s_box);
+        expanded_key
     }
 
     /**
      * This is the actual meat of the expansion process for 128 and 192 bits
-     * long keys. key is a borrow of the vector containing only the original key words,
+     * long keys. key is a borrow of the array whose first nk words hold the original key,
      * nk is the number of words in the key (4 if 128, 6 if 192) and nr is the
      * same number of rounds that is held in the rounds attribute of the struct.
+     * s_box is the table used to substitute the bytes of the words.
      */
-    fn populate_key_128_192(key: &mut Vec<u32>, nk: usize, nr: usize) {
+    fn populate_key_128_192(
+        key: &mut [u32; MAX_EXPANDED_KEY_WORDS],
+        nk: usize,
+        nr: usize,
+        s_box: &[u8; 256],
+    ) {
         for i in nk..(4 * (nr + 1)) {
-            let mut temp = key[i - 1];
-            if i % nk == 0 {
-                Math::rot_word(&mut temp);
-                Math::substitute_bytes_word(&mut temp);
-                temp = temp ^ ROUND_CONSTANT[(i / nk) - 1];
-            }
-            let new_val = key[i - nk] ^ temp;
-            key.push(new_val);
+            key[i] = key[i - nk] ^ Self::expansion_temp(key[i - 1], i, nk, s_box);
         }
     }
 
     /**
      * This is the actual meat of the expansion process for 256 bits
-     * long keys. key is a borrow of the vector containing only the original key words,
-     * nk is the number of words in the key (4 if 128, 6 if 192) and nr is the
-     * same number of rounds that is held in the rounds attribute of the struct.
+     * long keys. key is a borrow of the array whose first 8 words hold the original key
+     * and nr is the number of rounds, s_box works as in populate_key_128_192.
      */
-    fn populate_key_256(key: &mut Vec<u32>) {
+    fn populate_key_256(key: &mut [u32; MAX_EXPANDED_KEY_WORDS], nr: usize, s_box: &[u8; 256]) {
         let nk = 8;
-        let nr = 14;
         for i in nk..(4 * (nr + 1)) {
-            let mut temp = key[i - 1];
-            if i % nk == 0 {
-                Math::rot_word(&mut temp);
-                Math::substitute_bytes_word(&mut temp);
-                temp = temp ^ ROUND_CONSTANT[(i / nk) - 1];
-            } else if i % nk == 4 {
-                Math::substitute_bytes_word(&mut temp);
-            }
-            let new_val = key[i - nk] ^ temp;
-            key.push(new_val);
+            key[i] = key[i - nk] ^ Self::expansion_temp(key[i - 1], i, nk, s_box);
+        }
+    }
+
+    /**
+     * The value that gets xored to word i - nk to produce word i of the expanded
+     * key, given the previous word. The substitution in the middle of each block
+     * of 8 words only happens for 256 bits long keys.
+     */
+    fn expansion_temp(previous: u32, i: usize, nk: usize, s_box: &[u8; 256]) -> u32 {
+        let mut temp = previous;
+        if i.is_multiple_of(nk) {
+            Math::rot_word(&mut temp);
+            Math::substitute_bytes_word_with(&mut temp, s_box);
+            temp ^= ROUND_CONSTANT[(i / nk) - 1];
+        } else if nk > 6 && i % nk == 4 {
+            Math::substitute_bytes_word_with(&mut temp, s_box);
         }
+        temp
     }
 
-    pub fn key(&self) -> &Vec<u32> {
-        &self.key
+    /**
+     * Runs the key expansion backwards to recover the original key from nk consecutive
+     * words of the expanded key, the first of which is at index start. Since each word
+     * is word i - nk xored with something that only depends on word i - 1, knowing
+     * words i - nk + 1 to i is enough to get word i - nk back.
+     *
+     * For example the last round key of AES-128 are the words from 40 to 43, so
+     * invert_key_128(&last_round_key, 40) gives back the key.
+     * Fails with Error::InvalidLength if the words would go past the end of the expanded key.
+     */
+    pub fn invert_key_128(words: &[u32; 4], start: usize) -> Result<[u32; 4], Error> {
+        let mut key = [0; 4];
+        key.copy_from_slice(&Self::invert_key(words, start, 10)?[..4]);
+        Ok(key)
     }
 
-    pub fn inv_key(&self) -> &Vec<u32> {
-        &self.inverse_key
+    pub fn invert_key_192(words: &[u32; 6], start: usize) -> Result<[u32; 6], Error> {
+        let mut key = [0; 6];
+        key.copy_from_slice(&Self::invert_key(words, start, 12)?[..6]);
+        Ok(key)
     }
 
-    pub fn next_words(&self, base: usize) -> &[u32] {
-        &self.key[base..base + 4]
+    pub fn invert_key_256(words: &[u32; 8], start: usize) -> Result<[u32; 8], Error> {
+        let mut key = [0; 8];
+        key.copy_from_slice(&Self::invert_key(words, start, 14)?[..8]);
+        Ok(key)
     }
 
-    pub fn next_words_inv(&self, base: usize) -> &[u32] {
-        &self.inverse_key[base..base + 4]
+    /**
+     * Puts the words in their place in an expanded key and fills all of the words
+     * before them, nk is the length of words.
+     */
+    fn invert_key(
+        words: &[u32],
+        start: usize,
+        nr: usize,
+    ) -> Result<[u32; MAX_EXPANDED_KEY_WORDS], Error> {
+        let nk = words.len();
+        match start.checked_add(nk) {
+            Some(end) if end <= 4 * (nr + 1) => {}
+            _ => return Err(Error::InvalidLength),
+        }
+        let mut key = [0; MAX_EXPANDED_KEY_WORDS];
+        key[start..start + nk].copy_from_slice(words);
+        for i in (nk..start + nk).rev() {
+            key[i - nk] = key[i] ^ Self::expansion_temp(key[i - 1], i, nk, &S_BOX);
+        }
+        Ok(key)
+    }
+
+    /**
+     * All of the words of the expanded key
+     */
+    pub(crate) fn key(&self) -> &[u32] {
+        &self.key[..4 * (self.rounds + 1)]
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+}
+
+impl InvKeyManager {
+    /**
+     * Produces the key for the equivalent inverse cipher from the expanded key.
+     */
+    pub fn new(key_manager: &KeyManager) -> InvKeyManager {
+        InvKeyManager {
+            rounds: key_manager.rounds,
+            inverse_key: Self::produce_inverse_key(&key_manager.key, key_manager.rounds),
+        }
+    }
+
+    /**
+     * The inverse key is just the original but with the
+     * inverse_mix_columns transformation applied to every
+     * word as a column, except for the words of the first and last round
+     * that are used as they are.
+     */
+    fn produce_inverse_key(
+        key: &[u32; MAX_EXPANDED_KEY_WORDS],
+        nr: usize,
+    ) -> [u32; MAX_EXPANDED_KEY_WORDS] {
+        let mut expanded_key_inverse = *key;
+        for word in expanded_key_inverse.iter_mut().take(4 * nr).skip(4) {
+            let mut inv_words = [*word, 0, 0, 0];
+            AesMatrix::inv_mix_column_a(&mut inv_words, 0);
+            *word = inv_words[0];
+        }
+        expanded_key_inverse
+    }
+
+    /**
+     * All of the words of the key for the equivalent inverse cipher
+     */
+    pub(crate) fn inv_key(&self) -> &[u32] {
+        &self.inverse_key[..4 * (self.rounds + 1)]
     }
 
     pub fn rounds(&self) -> usize {
@@ -0,0 +1,82 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod keywrap_test {
+    use crate::cipher::Key;
+    use crate::error::Error;
+    use crate::keywrap::KeyWrap;
+
+    const KEY_DATA: [u8; 32] = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+        0x0e, 0x0f,
+    ];
+
+    /**
+     * 4.1 and 4.6 of RFC 3394
+     */
+    #[test]
+    fn it_matches_rfc_3394() {
+        let kek = KeyWrap::new(&Key::Aes128([
+            0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f,
+        ]));
+        let mut wrapped = [0; 24];
+        kek.wrap(&KEY_DATA[..16], &mut wrapped).unwrap();
+        let mut expected = [0; 24];
+        expected[..16].copy_from_slice(&0x1fa68b0a8112b447aef34bd8fb5a7b82_u128.to_be_bytes());
+        expected[16..].copy_from_slice(&0x9d3e862371d2cfe5_u64.to_be_bytes());
+        assert_eq!(wrapped, expected);
+        let mut unwrapped = [0; 16];
+        kek.unwrap(&wrapped, &mut unwrapped).unwrap();
+        assert_eq!(unwrapped, KEY_DATA[..16]);
+
+        let kek = KeyWrap::new(&Key::Aes256([
+            0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+            0x1c1d1e1f,
+        ]));
+        let mut wrapped = [0; 40];
+        kek.wrap(&KEY_DATA, &mut wrapped).unwrap();
+        assert_eq!(
+            wrapped[..32],
+            [
+                0x28, 0xc9, 0xf4, 0x04, 0xc4, 0xb8, 0x10, 0xf4, 0xcb, 0xcc, 0xb3, 0x5c, 0xfb, 0x87,
+                0xf8, 0x26, 0x3f, 0x57, 0x86, 0xe2, 0xd8, 0x0e, 0xd3, 0x26, 0xcb, 0xc7, 0xf0, 0xe7,
+                0x1a, 0x99, 0xf4, 0x3b
+            ]
+        );
+        assert_eq!(wrapped[32..], 0xfb988b9b7a02dd21_u64.to_be_bytes());
+        let mut unwrapped = [0; 32];
+        kek.unwrap(&wrapped, &mut unwrapped).unwrap();
+        assert_eq!(unwrapped, KEY_DATA);
+    }
+
+    #[test]
+    fn it_detects_changes() {
+        let kek = KeyWrap::new(&Key::Aes192([7; 6]));
+        let mut wrapped = [0; 32];
+        kek.wrap(&KEY_DATA[..24], &mut wrapped).unwrap();
+        wrapped[20] ^= 1;
+        let mut unwrapped = [1; 24];
+        assert_eq!(
+            kek.unwrap(&wrapped, &mut unwrapped),
+            Err(Error::AuthenticationFailed)
+        );
+        assert_eq!(unwrapped, [0; 24]);
+        wrapped[20] ^= 1;
+        assert_eq!(
+            KeyWrap::new(&Key::Aes192([8; 6])).unwrap(&wrapped, &mut unwrapped),
+            Err(Error::AuthenticationFailed)
+        );
+        assert_eq!(
+            kek.wrap(&KEY_DATA[..8], &mut [0; 16]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            kek.wrap(&KEY_DATA[..20], &mut [0; 28]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            kek.unwrap(&wrapped, &mut [0; 16]),
+            Err(Error::InvalidLength)
+        );
+    }
+}
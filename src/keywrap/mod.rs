@@ -0,0 +1,120 @@
+/*!
+ * The AES key wrap of RFC 3394 (KW in NIST SP 800-38F), to store or send a
+ * key encrypted under another key, the key encryption key.
+ *
+ * The key to wrap is split in 64 bits halves of blocks that are ciphered six
+ * times each together with a running integrity check value, which is
+ * compared with the default IV of the standard when unwrapping, so a wrapped
+ * key that has been changed or a wrong key encryption key are detected.
+ */
+use crate::block::{BlockDecrypt, BlockEncrypt};
+use crate::cipher::{Cipher, Key};
+use crate::error::Error;
+use crate::math::Math;
+
+mod keywrap_test;
+
+/**
+ * The initial value of the integrity check of RFC 3394
+ */
+const DEFAULT_IV: [u8; 8] = [0xa6; 8];
+
+/**
+ * Key wrap under a key encryption key. Wrapping needs a cipher that
+ * implements BlockEncrypt, unwrapping one that implements BlockDecrypt.
+ */
+pub struct KeyWrap<C = Cipher> {
+    cipher: C,
+}
+
+impl KeyWrap {
+    pub fn new(kek: &Key) -> KeyWrap {
+        Self::from_cipher(Cipher::from_key(kek))
+    }
+}
+
+impl<C> KeyWrap<C> {
+    pub fn from_cipher(cipher: C) -> KeyWrap<C> {
+        KeyWrap { cipher }
+    }
+}
+
+impl<C: BlockEncrypt> KeyWrap<C> {
+    /**
+     * Wraps key_data, at least 16 bytes long and a multiple of 8 bytes, into
+     * output, which must be 8 bytes longer. Fails with Error::InvalidLength
+     * otherwise.
+     */
+    pub fn wrap(&self, key_data: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        if key_data.len() < 16
+            || !key_data.len().is_multiple_of(8)
+            || output.len() != key_data.len() + 8
+        {
+            return Err(Error::InvalidLength);
+        }
+        let n = key_data.len() / 8;
+        let mut a = DEFAULT_IV;
+        output[8..].copy_from_slice(key_data);
+        let mut block = [0; 16];
+        for j in 0..6 {
+            for i in 1..=n {
+                block[..8].copy_from_slice(&a);
+                block[8..].copy_from_slice(&output[i * 8..i * 8 + 8]);
+                self.cipher.encrypt_block(&mut block);
+                a.copy_from_slice(&block[..8]);
+                xor_step(&mut a, (n * j + i) as u64);
+                output[i * 8..i * 8 + 8].copy_from_slice(&block[8..]);
+            }
+        }
+        output[..8].copy_from_slice(&a);
+        Ok(())
+    }
+}
+
+impl<C: BlockDecrypt> KeyWrap<C> {
+    /**
+     * Unwraps a key wrapped by wrap into output, which must be 8 bytes
+     * shorter. Fails with Error::AuthenticationFailed if the integrity check
+     * doesn't match, and output is then zeroed.
+     */
+    pub fn unwrap(&self, wrapped: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        if wrapped.len() < 24
+            || !wrapped.len().is_multiple_of(8)
+            || output.len() + 8 != wrapped.len()
+        {
+            return Err(Error::InvalidLength);
+        }
+        let n = output.len() / 8;
+        let mut a = [0; 8];
+        a.copy_from_slice(&wrapped[..8]);
+        output.copy_from_slice(&wrapped[8..]);
+        let mut block = [0; 16];
+        for j in (0..6).rev() {
+            for i in (1..=n).rev() {
+                xor_step(&mut a, (n * j + i) as u64);
+                block[..8].copy_from_slice(&a);
+                block[8..].copy_from_slice(&output[(i - 1) * 8..i * 8]);
+                self.cipher.decrypt_block(&mut block);
+                a.copy_from_slice(&block[..8]);
+                output[(i - 1) * 8..i * 8].copy_from_slice(&block[8..]);
+            }
+        }
+        if Math::constant_time_eq(&a, &DEFAULT_IV) {
+            Ok(())
+        } else {
+            for byte in output.iter_mut() {
+                *byte = 0;
+            }
+            Err(Error::AuthenticationFailed)
+        }
+    }
+}
+
+/**
+ * A ^ t, with t the index of the step written in big endian
+ */
+fn xor_step(a: &mut [u8; 8], t: u64) {
+    for (byte, step) in a.iter_mut().zip(t.to_be_bytes().iter()) {
+        *byte ^= step;
+    }
+}
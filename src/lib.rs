@@ -1,8 +1,39 @@
+#![no_std]
+
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 mod aes_matrix;
+pub mod analysis;
+pub mod block;
+pub mod cbc;
 pub mod cipher;
-mod key_manager;
-mod math;
+pub mod cmac;
+pub mod container;
+pub mod ctr;
+pub mod drbg;
+pub mod error;
+pub mod fpe;
+pub mod gcm;
+pub mod ghash;
+pub mod gmac;
+pub mod hmac;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod kbkdf;
+pub mod key_manager;
+pub mod keywrap;
+pub mod math;
+pub mod md5;
+pub mod openssl;
+pub mod pbkdf2;
+pub mod pmac;
+#[cfg(feature = "std")]
+pub mod random;
+pub mod sha256;
+pub mod stream;
+pub mod trace;
+pub mod xts;
 
 #[cfg(test)]
-
 mod tests {}
@@ -1,8 +1,36 @@
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
 /**
  * Values of the secure box for every byte. The correct value for each byte is located
- * at S_BOX[byte]
+ * at S_BOX[byte]. The table is generated at compile time from its definition in
+ * the AES standard and checked against the published one.
+ */
+pub static S_BOX: [u8; 256] = SBoxParams::AES.s_box();
+
+/**
+ * Values of the inverse secure box for every byte. The correct value for each byte is located
+ * at INV_S_BOX[byte]
  */
-pub static S_BOX: [u8; 256] = [
+pub static INV_S_BOX: [u8; 256] = SBoxParams::AES.inv_s_box();
+
+/**
+ * The secure box and its inverse used by the AES standard
+ */
+pub static AES_S_BOX: SBox = SBox {
+    forward: &S_BOX,
+    inverse: &INV_S_BOX,
+};
+
+const _: () = assert!(tables_equal(&SBoxParams::AES.s_box(), &PUBLISHED_S_BOX));
+const _: () = assert!(tables_equal(
+    &SBoxParams::AES.inv_s_box(),
+    &PUBLISHED_INV_S_BOX
+));
+
+/**
+ * The secure box as published in the AES standard (figure 7)
+ */
+const PUBLISHED_S_BOX: [u8; 256] = [
     0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
     0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
     0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
@@ -22,10 +50,9 @@ pub static S_BOX: [u8; 256] = [
 ];
 
 /**
- * Values of the inverse secure box for every byte. The correct value for each byte is located
- * at INV_S_BOX[byte]
+ * The inverse secure box as published in the AES standard (figure 14)
  */
-pub static INV_S_BOX: [u8; 256] = [
+const PUBLISHED_INV_S_BOX: [u8; 256] = [
     0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
     0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
     0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
@@ -44,22 +71,200 @@ pub static INV_S_BOX: [u8; 256] = [
     0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
 ];
 
-pub struct Math {}
+/**
+ * Parameters that define a Rijndael-like secure box: each byte is replaced by its
+ * multiplicative inverse modulo the reduction polynomial (0 is kept as 0) and then
+ * goes through the affine transformation of the AES standard
+ *
+ * b'[i] = b[i] xor b[i + 4] xor b[i + 5] xor b[i + 6] xor b[i + 7] xor c[i]
+ *
+ * with the indexes taken modulo 8 and c being the affine constant.
+ * The polynomial must be irreducible and of degree 8, otherwise some bytes have
+ * no inverse and the table is not a permutation (see is_permutation).
+ *
+ * Tables generated with other parameters can be plugged in the ciphers built by
+ * the hazmat module through an SBox. Only the substitution changes: mix columns
+ * and the round constants of the key expansion stay the ones of the AES field.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SBoxParams {
+    pub polynomial: u16,
+    pub affine_constant: u8,
+}
 
-impl Math {
+/**
+ * A secure box and its inverse. Ciphers keep references to them so the tables
+ * must be statics, which the const functions of SBoxParams allow to build:
+ *
+ * static MY_S_BOX: [u8; 256] = MY_PARAMS.s_box();
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SBox {
+    pub forward: &'static [u8; 256],
+    pub inverse: &'static [u8; 256],
+}
+
+impl SBoxParams {
     /**
-     * This is the multiplication by 0x02 in the GF2 field as
-     * described in the AES standard.
+     * x^8 + x^4 + x^3 + x + 1 and 0x63, the parameters of the AES standard
      */
-    #[inline]
-    pub fn x_time(val: u8) -> u8 {
-        if val > 0x7f {
-            (val << 1) ^ 0x1b
-        } else {
-            val << 1
+    pub const AES: SBoxParams = SBoxParams {
+        polynomial: 0x11b,
+        affine_constant: 0x63,
+    };
+
+    pub const fn s_box(self) -> [u8; 256] {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let inverse = Self::inverse(i as u8, self.polynomial);
+            table[i] = Self::affine_transformation(inverse, self.affine_constant);
+            i += 1;
+        }
+        table
+    }
+
+    /**
+     * The inverse permutation of s_box. Meaningful only if s_box is a permutation.
+     */
+    pub const fn inv_s_box(self) -> [u8; 256] {
+        let s_box = self.s_box();
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            table[s_box[i] as usize] = i as u8;
+            i += 1;
+        }
+        table
+    }
+
+    /**
+     * Same as Gf256::ct_mul but modulo any polynomial of degree 8
+     */
+    const fn multiply(a: u8, b: u8, polynomial: u16) -> u8 {
+        let reduction = (polynomial & 0xff) as u8;
+        let mut a = a;
+        let mut b = b;
+        let mut result = 0;
+        while b != 0 {
+            if b & 1 == 1 {
+                result ^= a;
+            }
+            a = if a > 0x7f {
+                (a << 1) ^ reduction
+            } else {
+                a << 1
+            };
+            b >>= 1;
         }
+        result
     }
 
+    /**
+     * a^254 is the inverse of a since a^255 = 1 for every a != 0, and it maps 0 to 0.
+     * It is computed by square and multiply.
+     */
+    const fn inverse(a: u8, polynomial: u16) -> u8 {
+        let mut result = 1;
+        let mut base = a;
+        let mut exponent = 254;
+        while exponent != 0 {
+            if exponent & 1 == 1 {
+                result = Self::multiply(result, base, polynomial);
+            }
+            base = Self::multiply(base, base, polynomial);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /**
+     * Bit i of the rotation of b by k to the left is bit i - k of b, so
+     * xoring b with its rotations by 1 to 4 xors each bit with the 4 bits
+     * that come before it, which are b[i + 4] to b[i + 7] modulo 8.
+     */
+    const fn affine_transformation(b: u8, constant: u8) -> u8 {
+        b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ constant
+    }
+}
+
+/**
+ * Tells if every byte appears exactly once in the table
+ */
+pub const fn is_permutation(table: &[u8; 256]) -> bool {
+    let mut seen = [false; 256];
+    let mut i = 0;
+    while i < 256 {
+        if seen[table[i] as usize] {
+            return false;
+        }
+        seen[table[i] as usize] = true;
+        i += 1;
+    }
+    true
+}
+
+const fn tables_equal(a: &[u8; 256], b: &[u8; 256]) -> bool {
+    let mut i = 0;
+    while i < 256 {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/**
+ * Antilogarithms of the GF(2^8) field of the AES standard in base 0x03, which
+ * generates all of the non zero elements of the field: EXP_TABLE[i] = 0x03^i.
+ * The powers repeat every 255, the table holds them twice so that the sum of two
+ * logarithms can be used as an index without reducing it modulo 255.
+ */
+static EXP_TABLE: [u8; 510] = exp_table();
+
+/**
+ * Logarithms in base 0x03, so that EXP_TABLE[LOG_TABLE[x]] = x for every x != 0.
+ * The logarithm of 0 is undefined and is left as 0 in the table.
+ */
+static LOG_TABLE: [u8; 256] = log_table();
+
+const fn exp_table() -> [u8; 510] {
+    let mut table = [0; 510];
+    let mut value: u8 = 1;
+    let mut i = 0;
+    while i < 510 {
+        table[i] = value;
+        // value * 0x03 = value ^ x_time(value)
+        value ^= Math::x_time(value);
+        i += 1;
+    }
+    table
+}
+
+const fn log_table() -> [u8; 256] {
+    let exp = exp_table();
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+/**
+ * An element of the GF(2^8) field used by the AES standard, that is a
+ * polynomial with bits as coefficients modulo x^8 + x^4 + x^3 + x + 1 (0x11b).
+ * Addition and subtraction are a xor, multiplication and division go through the
+ * logarithm tables. ct_mul multiplies without tables nor branches that depend
+ * on the values, so its running time doesn't leak them: the cipher uses it,
+ * the operators are for the analysis and the hazmat tools.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Gf256(pub u8);
+
+impl Gf256 {
     /**
      * The multiplication by a value in the GF2 field can be obtained as such:
      * If we want to do a * b then we take each bit in b which is set to 1 and
@@ -73,52 +278,172 @@ impl Math {
      * If instead we want to multiply a by 0x0d then we must consider that 0x0d = 0000 1101, with the
      * bits in position 0, 2 and 3 set to one. The result will then be:
      * a * 0x0d = a ^ x_time(x_time(a)) ^ x_time(x_time(x_time(a)))
+     *
+     * Here the choices are made with masks instead of ifs, so that all of the
+     * 8 steps always do the same work whatever the values are.
      */
+    pub fn ct_mul(self, other: Gf256) -> Gf256 {
+        let mut a = self.0;
+        let mut b = other.0;
+        let mut result = 0;
+        for _ in 0..8 {
+            result ^= a & 0u8.wrapping_sub(b & 1);
+            let reduce = 0u8.wrapping_sub(a >> 7);
+            a = (a << 1) ^ (0x1b & reduce);
+            b >>= 1;
+        }
+        Gf256(result)
+    }
 
-    #[inline]
-    pub fn multiplication_by_03(val: u8) -> u8 {
-        (val ^ Self::x_time(val))
+    /**
+     * Multiplicative inverse. As in the definition of the secure box, 0
+     * has no inverse and is mapped to itself.
+     */
+    pub fn inv(self) -> Gf256 {
+        if self.0 == 0 {
+            Gf256(0)
+        } else {
+            Gf256(EXP_TABLE[(255 - LOG_TABLE[self.0 as usize] as usize) % 255])
+        }
     }
 
-    #[inline]
-    pub fn multiplication_by_09(val: u8) -> u8 {
-        (val ^ Self::x_time_x3(val))
+    /**
+     * Raises the element to the given power, with 0^0 = 1
+     */
+    pub fn pow(self, exponent: u32) -> Gf256 {
+        if exponent == 0 {
+            Gf256(1)
+        } else if self.0 == 0 {
+            Gf256(0)
+        } else {
+            let log = LOG_TABLE[self.0 as usize] as u64 * exponent as u64;
+            Gf256(EXP_TABLE[(log % 255) as usize])
+        }
     }
+}
 
-    #[inline]
-    pub fn multiplication_by_0e(val: u8) -> u8 {
-        Self::x_time(val) ^ Self::x_time_x2(val) ^ Self::x_time_x3(val)
+impl From<u8> for Gf256 {
+    fn from(value: u8) -> Gf256 {
+        Gf256(value)
     }
+}
 
-    #[inline]
-    pub fn multiplication_by_0d(val: u8) -> u8 {
-        (val ^ Self::x_time_x3(val)) ^ Self::x_time_x2(val)
+impl From<Gf256> for u8 {
+    fn from(value: Gf256) -> u8 {
+        value.0
     }
+}
 
-    #[inline]
-    pub fn multiplication_by_0b(val: u8) -> u8 {
-        val ^ Self::x_time(val) ^ Self::x_time(Self::x_time(Self::x_time(val)))
+impl Add for Gf256 {
+    type Output = Gf256;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Gf256) -> Gf256 {
+        Gf256(self.0 ^ rhs.0)
     }
+}
+
+impl AddAssign for Gf256 {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn add_assign(&mut self, rhs: Gf256) {
+        self.0 ^= rhs.0;
+    }
+}
+
+/**
+ * In a field of characteristic 2 every element is its own opposite,
+ * so subtracting is the same as adding.
+ */
+impl Sub for Gf256 {
+    type Output = Gf256;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Gf256) -> Gf256 {
+        Gf256(self.0 ^ rhs.0)
+    }
+}
+
+impl SubAssign for Gf256 {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn sub_assign(&mut self, rhs: Gf256) {
+        self.0 ^= rhs.0;
+    }
+}
+
+/**
+ * a * b = 0x03^(log(a) + log(b)), the sum of the logarithms is below 510
+ * so it can index EXP_TABLE directly.
+ */
+impl Mul for Gf256 {
+    type Output = Gf256;
 
     #[inline]
-    fn x_time_x3(val: u8) -> u8 {
-        Self::x_time(Self::x_time(Self::x_time(val)))
+    fn mul(self, rhs: Gf256) -> Gf256 {
+        if self.0 == 0 || rhs.0 == 0 {
+            Gf256(0)
+        } else {
+            let log = LOG_TABLE[self.0 as usize] as usize + LOG_TABLE[rhs.0 as usize] as usize;
+            Gf256(EXP_TABLE[log])
+        }
+    }
+}
+
+impl MulAssign for Gf256 {
+    fn mul_assign(&mut self, rhs: Gf256) {
+        *self = *self * rhs;
+    }
+}
+
+/**
+ * Panics when dividing by 0, like the integer division.
+ */
+impl Div for Gf256 {
+    type Output = Gf256;
+
+    fn div(self, rhs: Gf256) -> Gf256 {
+        assert!(rhs.0 != 0, "division by zero in GF(2^8)");
+        self * rhs.inv()
+    }
+}
+
+impl DivAssign for Gf256 {
+    fn div_assign(&mut self, rhs: Gf256) {
+        *self = *self / rhs;
     }
+}
 
+pub struct Math {}
+
+impl Math {
+    /**
+     * This is the multiplication by 0x02 in the GF2 field as
+     * described in the AES standard.
+     */
     #[inline]
-    fn x_time_x2(val: u8) -> u8 {
-        Self::x_time(Self::x_time(val))
+    pub const fn x_time(val: u8) -> u8 {
+        if val > 0x7f {
+            (val << 1) ^ 0x1b
+        } else {
+            val << 1
+        }
     }
 
     /**
      * Substitutes each byte in a word with the corresponding byte in the secure box
      */
     pub fn substitute_bytes_word(word: &mut u32) {
+        Self::substitute_bytes_word_with(word, &S_BOX);
+    }
+
+    /**
+     * Substitutes each byte in a word with the corresponding byte in the given table
+     */
+    pub fn substitute_bytes_word_with(word: &mut u32, table: &[u8; 256]) {
         let mut new_val: u32 = 0x00;
-        new_val = new_val | ((S_BOX[((*word >> 24) & 0xff) as usize] as u32) << 24);
-        new_val = new_val | ((S_BOX[((*word >> 16) & 0xff) as usize] as u32) << 16);
-        new_val = new_val | ((S_BOX[((*word >> 8) & 0xff) as usize] as u32) << 8);
-        new_val = new_val | (S_BOX[(*word & 0xff) as usize] as u32);
+        new_val |= (table[((*word >> 24) & 0xff) as usize] as u32) << 24;
+        new_val |= (table[((*word >> 16) & 0xff) as usize] as u32) << 16;
+        new_val |= (table[((*word >> 8) & 0xff) as usize] as u32) << 8;
+        new_val |= table[(*word & 0xff) as usize] as u32;
         *word = new_val;
     }
 
@@ -129,11 +454,24 @@ impl Math {
         let carry: u32 = (*word & 0xff000000) >> 24;
         *word = (*word << 8) | carry;
     }
+
+    /**
+     * Compares two byte strings of the same length looking at all of the bytes,
+     * so that the time taken doesn't tell where the first difference is.
+     * Used to check authentication tags.
+     */
+    pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b.iter())
+                .fold(0, |difference, (x, y)| difference | (x ^ y))
+                == 0
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::math::Math;
+    use crate::math::{is_permutation, Gf256, Math, SBoxParams, INV_S_BOX, S_BOX};
     #[test]
     fn it_does_xtime() {
         assert_eq!(Math::x_time(0x57), 0xae);
@@ -142,10 +480,70 @@ mod tests {
         assert_eq!(Math::x_time(0x8e), 0x07);
         assert_eq!(Math::x_time(0x7f), 0xfe);
         assert_eq!(Math::x_time(0x80), 0x1b);
-        assert_eq!(Math::multiplication_by_03(0x80), 0x9b);
-        assert_eq!(Math::multiplication_by_0e(0x24), 0xe3);
-        assert_eq!(Math::multiplication_by_0b(0x24), 0x57);
-        assert_eq!(Math::multiplication_by_0d(0x24), 0x8f);
+        assert_eq!(Gf256(0x80) * Gf256(0x03), Gf256(0x9b));
+        assert_eq!(Gf256(0x24) * Gf256(0x0e), Gf256(0xe3));
+        assert_eq!(Gf256(0x24) * Gf256(0x0b), Gf256(0x57));
+        assert_eq!(Gf256(0x24) * Gf256(0x0d), Gf256(0x8f));
+    }
+
+    #[test]
+    fn it_multiplies_gf256() {
+        assert_eq!(Gf256(0x57) * Gf256(0x83), Gf256(0xc1));
+        assert_eq!(Gf256(0x57) * Gf256(0x13), Gf256(0xfe));
+        assert_eq!(Gf256(0x57) + Gf256(0x83), Gf256(0xd4));
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let product = Gf256(a) * Gf256(b);
+                assert_eq!(product, Gf256(a).ct_mul(Gf256(b)));
+                if b != 0 {
+                    assert_eq!(product / Gf256(b), Gf256(a));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_inverts_gf256() {
+        assert_eq!(Gf256(0x53).inv(), Gf256(0xca));
+        assert_eq!(Gf256(0).inv(), Gf256(0));
+        for a in 1..=255u8 {
+            assert_eq!(Gf256(a) * Gf256(a).inv(), Gf256(1));
+            assert_eq!(Gf256(a).pow(254), Gf256(a).inv());
+            assert_eq!(Gf256(a).pow(255), Gf256(1));
+        }
+        assert_eq!(Gf256(0x02).pow(8), Gf256(0x1b));
+        assert_eq!(Gf256(0).pow(0), Gf256(1));
+        assert_eq!(Gf256(0).pow(3), Gf256(0));
+    }
+
+    #[test]
+    fn it_generates_s_boxes() {
+        assert_eq!(S_BOX[0x53], 0xed);
+        assert_eq!(INV_S_BOX[0xed], 0x53);
+        assert!(is_permutation(&S_BOX));
+        let params = SBoxParams {
+            polynomial: 0x11d,
+            affine_constant: 0x05,
+        };
+        let s_box = params.s_box();
+        let inv_s_box = params.inv_s_box();
+        assert!(is_permutation(&s_box));
+        assert_ne!(s_box[..], S_BOX[..]);
+        for byte in 0..=255u8 {
+            assert_eq!(inv_s_box[s_box[byte as usize] as usize], byte);
+        }
+        // x^8 + 1 = (x + 1)^8 is not irreducible
+        let reducible = SBoxParams {
+            polynomial: 0x101,
+            affine_constant: 0x63,
+        };
+        assert!(!is_permutation(&reducible.s_box()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_refuses_division_by_zero() {
+        let _ = Gf256(0x12) / Gf256(0);
     }
 }
 
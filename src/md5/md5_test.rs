@@ -0,0 +1,61 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod md5_test {
+    use crate::md5::Md5;
+
+    fn digest_of(pieces: &[&[u8]]) -> u128 {
+        let mut md5 = Md5::new();
+        for piece in pieces {
+            md5.update(piece);
+        }
+        u128::from_be_bytes(md5.finalize())
+    }
+
+    /**
+     * The test suite of RFC 1321
+     */
+    #[test]
+    fn it_matches_rfc_1321() {
+        assert_eq!(digest_of(&[b""]), 0xd41d8cd98f00b204e9800998ecf8427e);
+        assert_eq!(digest_of(&[b"a"]), 0x0cc175b9c0f1b6a831c399e269772661);
+        assert_eq!(digest_of(&[b"abc"]), 0x900150983cd24fb0d6963f7d28e17f72);
+        assert_eq!(
+            digest_of(&[b"message digest"]),
+            0xf96b697d7cb7938d525a2f31aaf161d0
+        );
+        assert_eq!(
+            digest_of(&[b"abcdefghijklmnopqrstuvwxyz"]),
+            0xc3fcd3d76192e4007dfb496cca67e13b
+        );
+        assert_eq!(
+            digest_of(&[b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"]),
+            0xd174ab98d277d9f5a5611c2c9f419d9f
+        );
+        assert_eq!(
+            Md5::digest(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ),
+            0x57edf4a22be3c955ac49da2e2107b67a_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_hashes_in_pieces() {
+        let message =
+            b"12345678901234567890123456789012345678901234567890123456789012345678901234567890";
+        assert_eq!(
+            digest_of(&[
+                &message[..1],
+                &message[1..56],
+                &message[56..64],
+                &message[64..]
+            ]),
+            0x57edf4a22be3c955ac49da2e2107b67a
+        );
+        let million = [b'a'; 1000];
+        assert_eq!(
+            digest_of(&[&million[..]; 1000]),
+            0x7707d6ae4e027c70eea2a935c2296f21
+        );
+    }
+}
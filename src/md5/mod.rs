@@ -0,0 +1,143 @@
+/*!
+ * MD5 of RFC 1321, only for EVP_BytesToKey in the openssl module, which is
+ * how OpenSSL derived keys from passwords before 1.1.0. MD5 is broken: don't
+ * use it for anything new.
+ *
+ * The API is the one of the sha256 module.
+ */
+mod md5_test;
+
+/**
+ * Length in bytes of a digest
+ */
+pub const DIGEST_LEN: usize = 16;
+
+/**
+ * Length in bytes of the blocks the message is processed in
+ */
+pub const BLOCK_LEN: usize = 64;
+
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/**
+ * The integer part of 2^32 * |sin(i + 1)|
+ */
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/**
+ * Left rotations of each step, four per round
+ */
+const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+/**
+ * The state of an MD5 computation
+ */
+#[derive(Clone)]
+pub struct Md5 {
+    state: [u32; 4],
+    block: [u8; BLOCK_LEN],
+    filled: usize,
+    length: u64,
+}
+
+impl Default for Md5 {
+    fn default() -> Md5 {
+        Md5::new()
+    }
+}
+
+impl Md5 {
+    pub fn new() -> Md5 {
+        Md5 {
+            state: INITIAL_STATE,
+            block: [0; BLOCK_LEN],
+            filled: 0,
+            length: 0,
+        }
+    }
+
+    /**
+     * The digest of a whole message
+     */
+    pub fn digest(data: &[u8]) -> [u8; DIGEST_LEN] {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finalize()
+    }
+
+    /**
+     * Adds data to the message
+     */
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let taken = data.len().min(BLOCK_LEN - self.filled);
+            self.block[self.filled..self.filled + taken].copy_from_slice(&data[..taken]);
+            self.filled += taken;
+            data = &data[taken..];
+            if self.filled == BLOCK_LEN {
+                compress(&mut self.state, &self.block);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /**
+     * Pads the message like SHA-256 does, but with the length in little endian
+     */
+    pub fn finalize(mut self) -> [u8; DIGEST_LEN] {
+        let bits = self.length.wrapping_mul(8);
+        self.block[self.filled] = 0x80;
+        for byte in self.block[self.filled + 1..].iter_mut() {
+            *byte = 0;
+        }
+        if self.filled >= BLOCK_LEN - 8 {
+            compress(&mut self.state, &self.block);
+            self.block = [0; BLOCK_LEN];
+        }
+        self.block[BLOCK_LEN - 8..].copy_from_slice(&bits.to_le_bytes());
+        compress(&mut self.state, &self.block);
+        let mut digest = [0; DIGEST_LEN];
+        for (chunk, word) in digest.chunks_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+}
+
+fn compress(state: &mut [u32; 4], block: &[u8; BLOCK_LEN]) {
+    let mut m = [0_u32; 16];
+    for (word, chunk) in m.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a
+            .wrapping_add(f)
+            .wrapping_add(K[i])
+            .wrapping_add(m[g])
+            .rotate_left(SHIFTS[i / 16 * 4 + i % 4]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(rotated);
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d].iter()) {
+        *word = word.wrapping_add(*value);
+    }
+}
@@ -0,0 +1,246 @@
+/*!
+ * The format of `openssl enc -aes-256-cbc` (and of its 128 and 192 bits
+ * variants) with a password, to exchange files with the OpenSSL command line.
+ *
+ * A file is the 8 bytes "Salted__", a random salt of SALT_LEN bytes and the
+ * CBC ciphertext with PKCS #7 padding. The key and the IV both come from the
+ * password and the salt, with one of the derivations of KeyDerivation, which
+ * is not written in the file: the reader must know which one the writer used,
+ * like `openssl enc -d` must be given the same -md or -pbkdf2.
+ *
+ * The format has no authentication. A wrong password or a changed file
+ * usually give Error::InvalidPadding, but they may also give garbage.
+ * Prefer the container module for anything that doesn't need OpenSSL.
+ */
+use crate::cipher::{Key, KeySize};
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::io::{DecryptReader, EncryptWriter, Mode};
+use crate::md5::Md5;
+use crate::pbkdf2::pbkdf2_hmac_sha256;
+use crate::sha256::Sha256;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+mod openssl_test;
+
+pub const MAGIC: [u8; 8] = *b"Salted__";
+
+pub const SALT_LEN: usize = 8;
+
+/**
+ * The magic bytes followed by the salt
+ */
+pub const HEADER_LEN: usize = 16;
+
+/**
+ * The iterations of `openssl enc -pbkdf2` without -iter
+ */
+pub const DEFAULT_ITERATIONS: u32 = 10_000;
+
+/**
+ * How the key and the IV are derived from the password, one after the other
+ * in the output of the function
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyDerivation {
+    /**
+     * EVP_BytesToKey with MD5 and one iteration, `-md md5`, the default
+     * before OpenSSL 1.1.0
+     */
+    Md5,
+    /**
+     * EVP_BytesToKey with SHA-256 and one iteration, `-md sha256`, the
+     * default since OpenSSL 1.1.0 without -pbkdf2
+     */
+    Sha256,
+    /**
+     * PBKDF2-HMAC-SHA256, `-pbkdf2 -iter iterations`
+     */
+    Pbkdf2 { iterations: u32 },
+}
+
+/**
+ * Everything that the reader and the writer of a file must agree on
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    pub key_size: KeySize,
+    pub derivation: KeyDerivation,
+}
+
+impl Default for Params {
+    /**
+     * `openssl enc -aes-256-cbc -pbkdf2`
+     */
+    fn default() -> Params {
+        Params {
+            key_size: KeySize::Aes256,
+            derivation: KeyDerivation::Pbkdf2 {
+                iterations: DEFAULT_ITERATIONS,
+            },
+        }
+    }
+}
+
+pub fn encode_header(salt: &[u8; SALT_LEN]) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(&MAGIC);
+    header[8..].copy_from_slice(salt);
+    header
+}
+
+/**
+ * The salt of a file from its first HEADER_LEN bytes. Fails with
+ * Error::InvalidLength if there are less, and with Error::UnknownFormat if
+ * they don't start with MAGIC, like the files of `openssl enc -nosalt`.
+ */
+pub fn decode_header(bytes: &[u8]) -> Result<[u8; SALT_LEN], Error> {
+    if bytes.len() < HEADER_LEN {
+        return Err(Error::InvalidLength);
+    }
+    if bytes[..8] != MAGIC {
+        return Err(Error::UnknownFormat);
+    }
+    let mut salt = [0; SALT_LEN];
+    salt.copy_from_slice(&bytes[8..HEADER_LEN]);
+    Ok(salt)
+}
+
+/**
+ * The key and the IV for a password and a salt. Fails only if PBKDF2 is
+ * given zero iterations.
+ */
+pub fn derive_key_iv(
+    password: &[u8],
+    salt: &[u8; SALT_LEN],
+    params: &Params,
+) -> Result<(Key, [u8; 16]), Error> {
+    let key_len = params.key_size.bytes();
+    let mut bytes = [0; 48];
+    let output = &mut bytes[..key_len + 16];
+    match params.derivation {
+        KeyDerivation::Md5 => bytes_to_key(
+            |data| {
+                let mut md5 = Md5::new();
+                data.iter().for_each(|piece| md5.update(piece));
+                let mut digest = [0; 32];
+                digest[..16].copy_from_slice(&md5.finalize());
+                (digest, 16)
+            },
+            password,
+            salt,
+            output,
+        ),
+        KeyDerivation::Sha256 => bytes_to_key(
+            |data| {
+                let mut sha = Sha256::new();
+                data.iter().for_each(|piece| sha.update(piece));
+                (sha.finalize(), 32)
+            },
+            password,
+            salt,
+            output,
+        ),
+        KeyDerivation::Pbkdf2 { iterations } => {
+            pbkdf2_hmac_sha256(password, salt, iterations, output)?
+        }
+    }
+    let mut iv = [0; 16];
+    iv.copy_from_slice(&bytes[key_len..key_len + 16]);
+    Ok((Key::from_valid_bytes(&bytes[..key_len]), iv))
+}
+
+/**
+ * EVP_BytesToKey with one iteration: the output is D_1 || D_2 || ..., with
+ * D_i = hash(D_(i-1) || password || salt) and D_0 empty. hash returns its
+ * digest at the start of 32 bytes, followed by its length.
+ */
+fn bytes_to_key<H>(hash: H, password: &[u8], salt: &[u8], output: &mut [u8])
+where
+    H: Fn(&[&[u8]]) -> ([u8; 32], usize),
+{
+    let mut previous = [0; 32];
+    let mut previous_len = 0;
+    let mut filled = 0;
+    while filled < output.len() {
+        let (digest, digest_len) = hash(&[&previous[..previous_len], password, salt]);
+        let taken = digest_len.min(output.len() - filled);
+        output[filled..filled + taken].copy_from_slice(&digest[..taken]);
+        filled += taken;
+        previous = digest;
+        previous_len = digest_len;
+    }
+}
+
+/**
+ * Writes the header with a random salt and returns a writer that encrypts
+ * what is written to it. finish must be called at the end to write the
+ * padding.
+ */
+#[cfg(feature = "std")]
+pub fn writer<W: Write>(
+    inner: W,
+    password: &[u8],
+    params: &Params,
+) -> Result<EncryptWriter<W>, Error> {
+    let mut salt = [0; SALT_LEN];
+    crate::random::fill(&mut salt)?;
+    writer_with_salt(inner, password, &salt, params)
+}
+
+/**
+ * Same as writer with a given salt, which must never be used twice with the
+ * same password
+ */
+#[cfg(feature = "std")]
+pub fn writer_with_salt<W: Write>(
+    mut inner: W,
+    password: &[u8],
+    salt: &[u8; SALT_LEN],
+    params: &Params,
+) -> Result<EncryptWriter<W>, Error> {
+    let (key, iv) = derive_key_iv(password, salt, params)?;
+    inner.write_all(&encode_header(salt))?;
+    EncryptWriter::new(inner, &key, Mode::Cbc { iv })
+}
+
+/**
+ * Reads the header and returns a reader of the plaintext
+ */
+#[cfg(feature = "std")]
+pub fn reader<R: Read>(
+    mut inner: R,
+    password: &[u8],
+    params: &Params,
+) -> Result<DecryptReader<R>, Error> {
+    let mut header = [0; HEADER_LEN];
+    inner.read_exact(&mut header).map_err(|error| {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            Error::InvalidLength
+        } else {
+            error.into()
+        }
+    })?;
+    let (key, iv) = derive_key_iv(password, &decode_header(&header)?, params)?;
+    DecryptReader::new(inner, &key, Mode::Cbc { iv })
+}
+
+/**
+ * Encrypts a whole message into a file, with a random salt
+ */
+#[cfg(feature = "std")]
+pub fn encrypt(password: &[u8], params: &Params, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut writer = writer(Vec::new(), password, params)?;
+    writer.write_all(plaintext)?;
+    writer.finish()
+}
+
+#[cfg(feature = "std")]
+pub fn decrypt(password: &[u8], params: &Params, file: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut plaintext = Vec::new();
+    reader(file, password, params)?.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
@@ -0,0 +1,226 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod openssl_test {
+    use crate::cipher::{Key, KeySize};
+    use crate::error::Error;
+    use crate::openssl::{decode_header, derive_key_iv, encode_header, KeyDerivation, Params};
+    use crate::pbkdf2::Pbkdf2Error;
+
+    const PASSWORD: &[u8] = b"correct horse battery staple";
+
+    const SALT: [u8; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+
+    fn params(key_size: KeySize, derivation: KeyDerivation) -> Params {
+        Params {
+            key_size,
+            derivation,
+        }
+    }
+
+    fn key_iv(key: u128, key_tail: u128, iv: u128, size: KeySize) -> (Key, [u8; 16]) {
+        let mut bytes = [0; 32];
+        bytes[..16].copy_from_slice(&key.to_be_bytes());
+        bytes[16..].copy_from_slice(&key_tail.to_be_bytes());
+        (
+            Key::from_bytes(&bytes[..size.bytes()]).unwrap(),
+            iv.to_be_bytes(),
+        )
+    }
+
+    /**
+     * The keys and IVs printed by `openssl enc -P -S 0001020304050607`
+     */
+    #[test]
+    fn it_derives_like_openssl() {
+        assert_eq!(
+            derive_key_iv(
+                PASSWORD,
+                &SALT,
+                &params(KeySize::Aes256, KeyDerivation::Md5)
+            ),
+            Ok(key_iv(
+                0x33ba19105fb54511369a58bed8733d63,
+                0xc7d262891c556325f033d2421adb9d78,
+                0x7f30fa59d3122564e08c6c68dba03c71,
+                KeySize::Aes256
+            ))
+        );
+        assert_eq!(
+            derive_key_iv(
+                PASSWORD,
+                &SALT,
+                &params(KeySize::Aes256, KeyDerivation::Sha256)
+            ),
+            Ok(key_iv(
+                0xf647ffdc3ef52b19874fd3dbe6690b54,
+                0x3b0ad1a2f49d7b3f0401fd16fa2361f8,
+                0x76be9cbd32b5fb9f41dd533e027fd7ce,
+                KeySize::Aes256
+            ))
+        );
+        assert_eq!(
+            derive_key_iv(
+                PASSWORD,
+                &SALT,
+                &params(KeySize::Aes192, KeyDerivation::Pbkdf2 { iterations: 1 })
+            ),
+            Ok(key_iv(
+                0x4283ec6f71ddeb209243cdd95def0bae,
+                0x08ede3ad7bab76640000000000000000,
+                0x49e0795c0cab7622cbda9068224037b4,
+                KeySize::Aes192
+            ))
+        );
+        assert_eq!(
+            derive_key_iv(
+                PASSWORD,
+                &SALT,
+                &params(KeySize::Aes128, KeyDerivation::Pbkdf2 { iterations: 0 })
+            ),
+            Err(Error::Pbkdf2(Pbkdf2Error::ZeroIterations))
+        );
+    }
+
+    #[test]
+    fn it_parses_headers() {
+        let header = encode_header(&SALT);
+        assert_eq!(&header[..8], b"Salted__");
+        assert_eq!(decode_header(&header), Ok(SALT));
+        assert_eq!(decode_header(&header[..15]), Err(Error::InvalidLength));
+        let mut other = header;
+        other[0] = b's';
+        assert_eq!(decode_header(&other), Err(Error::UnknownFormat));
+    }
+
+    #[cfg(feature = "std")]
+    mod files {
+        use super::{params, PASSWORD};
+        use crate::cipher::KeySize;
+        use crate::error::Error;
+        use crate::openssl::{
+            decrypt, encrypt, reader, writer_with_salt, KeyDerivation, Params, DEFAULT_ITERATIONS,
+            HEADER_LEN,
+        };
+        use std::io::Write;
+        use std::vec::Vec;
+
+        /**
+         * Files encrypted by OpenSSL 3.5 with
+         * `openssl enc -pass "pass:correct horse battery staple" -in plaintext.txt`
+         * and the options of FIXTURES, each with a random salt
+         */
+        const PLAINTEXT: &[u8] = include_bytes!("fixtures/plaintext.txt");
+
+        const FIXTURES: [(&[u8], KeySize, KeyDerivation); 4] = [
+            (
+                // -aes-256-cbc -md md5
+                include_bytes!("fixtures/aes256_md5.bin"),
+                KeySize::Aes256,
+                KeyDerivation::Md5,
+            ),
+            (
+                // -aes-256-cbc -md sha256
+                include_bytes!("fixtures/aes256_sha256.bin"),
+                KeySize::Aes256,
+                KeyDerivation::Sha256,
+            ),
+            (
+                // -aes-256-cbc -pbkdf2
+                include_bytes!("fixtures/aes256_pbkdf2.bin"),
+                KeySize::Aes256,
+                KeyDerivation::Pbkdf2 {
+                    iterations: DEFAULT_ITERATIONS,
+                },
+            ),
+            (
+                // -aes-128-cbc -pbkdf2 -iter 1000
+                include_bytes!("fixtures/aes128_pbkdf2_1000.bin"),
+                KeySize::Aes128,
+                KeyDerivation::Pbkdf2 { iterations: 1000 },
+            ),
+        ];
+
+        /**
+         * -aes-256-cbc -pbkdf2 of an empty file, a single block of padding
+         */
+        const EMPTY: &[u8] = include_bytes!("fixtures/aes256_pbkdf2_empty.bin");
+
+        #[test]
+        fn it_decrypts_openssl_files() {
+            for &(file, key_size, derivation) in FIXTURES.iter() {
+                let params = params(key_size, derivation);
+                assert_eq!(decrypt(PASSWORD, &params, file).unwrap(), PLAINTEXT);
+            }
+            assert_eq!(
+                decrypt(PASSWORD, &Params::default(), EMPTY).unwrap(),
+                Vec::<u8>::new()
+            );
+        }
+
+        /**
+         * With the salt of the fixture the output must be the same, byte for
+         * byte, which is what OpenSSL needs to read it
+         */
+        #[test]
+        fn it_writes_like_openssl() {
+            for &(file, key_size, derivation) in FIXTURES.iter() {
+                let mut salt = [0; 8];
+                salt.copy_from_slice(&file[8..HEADER_LEN]);
+                let params = params(key_size, derivation);
+                let mut writer = writer_with_salt(Vec::new(), PASSWORD, &salt, &params).unwrap();
+                // in pieces that don't follow the blocks
+                for piece in PLAINTEXT.chunks(7) {
+                    writer.write_all(piece).unwrap();
+                }
+                assert_eq!(writer.finish().unwrap(), file);
+            }
+        }
+
+        #[test]
+        fn it_round_trips() {
+            let message: Vec<u8> = (0..100).collect();
+            for &derivation in [
+                KeyDerivation::Md5,
+                KeyDerivation::Sha256,
+                KeyDerivation::Pbkdf2 { iterations: 3 },
+            ]
+            .iter()
+            {
+                for &key_size in [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256].iter() {
+                    let params = params(key_size, derivation);
+                    for &length in [0, 15, 16, 17, 100].iter() {
+                        let file = encrypt(PASSWORD, &params, &message[..length]).unwrap();
+                        assert_eq!(file.len(), HEADER_LEN + (length / 16 + 1) * 16);
+                        assert_eq!(
+                            decrypt(PASSWORD, &params, &file).unwrap(),
+                            &message[..length]
+                        );
+                    }
+                }
+            }
+            let first = encrypt(PASSWORD, &Params::default(), b"").unwrap();
+            let second = encrypt(PASSWORD, &Params::default(), b"").unwrap();
+            assert_ne!(first[8..HEADER_LEN], second[8..HEADER_LEN]);
+        }
+
+        #[test]
+        fn it_refuses_bad_files() {
+            let (file, key_size, derivation) = FIXTURES[1];
+            let params = params(key_size, derivation);
+            assert!(decrypt(b"wrong password", &params, file).is_err());
+            assert_eq!(
+                decrypt(PASSWORD, &params, &file[..file.len() - 1]),
+                Err(Error::InvalidLength)
+            );
+            assert_eq!(
+                decrypt(PASSWORD, &params, &file[..10]),
+                Err(Error::InvalidLength)
+            );
+            assert_eq!(
+                decrypt(PASSWORD, &params, &file[HEADER_LEN..]),
+                Err(Error::UnknownFormat)
+            );
+            assert!(reader(&file[..HEADER_LEN], PASSWORD, &params).is_ok());
+        }
+    }
+}
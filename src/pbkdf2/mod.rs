@@ -0,0 +1,123 @@
+/*!
+ * PBKDF2 of RFC 8018 and NIST SP 800-132 with HMAC-SHA256, to turn a password
+ * into a key for the ciphers of the crate.
+ *
+ * Every block of output is the xor of iterations HMACs chained one into the
+ * other, the first of the salt and the index of the block, all keyed with the
+ * password. The iterations make guessing the password slow, the salt makes
+ * the same password give different keys, so it must be random and stored
+ * next to what was encrypted, see generate_salt.
+ */
+use crate::cipher::{Key, KeySize};
+use crate::hmac::{HmacSha256, TAG_LEN};
+#[cfg(feature = "std")]
+use crate::random::{self, RandomError};
+use core::fmt;
+
+mod pbkdf2_test;
+
+/**
+ * The shortest salt derive_key accepts, 128 bits as SP 800-132 asks
+ */
+pub const MIN_SALT_LEN: usize = 16;
+
+/**
+ * The iterations recommended by OWASP for PBKDF2-HMAC-SHA256 in 2023
+ */
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+/**
+ * Reasons for which no key can be derived
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbkdf2Error {
+    ZeroIterations,
+    /**
+     * The salt is shorter than MIN_SALT_LEN
+     */
+    ShortSalt,
+    /**
+     * The output is empty or longer than 2^32 - 1 blocks
+     */
+    OutputLength,
+}
+
+impl fmt::Display for Pbkdf2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pbkdf2Error::ZeroIterations => write!(f, "at least one iteration is needed"),
+            Pbkdf2Error::ShortSalt => {
+                write!(f, "the salt must be at least {} bytes long", MIN_SALT_LEN)
+            }
+            Pbkdf2Error::OutputLength => write!(f, "invalid output length"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Pbkdf2Error {}
+
+/**
+ * Fills output with the key derived from password and salt. Any salt is
+ * accepted here, to be compatible with other formats; derive_key checks it.
+ */
+pub fn pbkdf2_hmac_sha256(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output: &mut [u8],
+) -> Result<(), Pbkdf2Error> {
+    if iterations == 0 {
+        return Err(Pbkdf2Error::ZeroIterations);
+    }
+    if output.is_empty() || (output.len() - 1) / TAG_LEN >= u32::MAX as usize {
+        return Err(Pbkdf2Error::OutputLength);
+    }
+    let prf = HmacSha256::new(password);
+    for (index, chunk) in output.chunks_mut(TAG_LEN).enumerate() {
+        let mut hmac = prf.clone();
+        hmac.update(salt);
+        hmac.update(&(index as u32 + 1).to_be_bytes());
+        let mut u = hmac.finalize();
+        let mut block = u;
+        for _ in 1..iterations {
+            let mut hmac = prf.clone();
+            hmac.update(&u);
+            u = hmac.finalize();
+            for (byte, value) in block.iter_mut().zip(u.iter()) {
+                *byte ^= value;
+            }
+        }
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    Ok(())
+}
+
+/**
+ * A key of the given size, ready for Cipher::from_key or, through
+ * Key::words, for Cipher::new_128/192/256. The salt must be at least
+ * MIN_SALT_LEN bytes long.
+ */
+pub fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    size: KeySize,
+) -> Result<Key, Pbkdf2Error> {
+    if salt.len() < MIN_SALT_LEN {
+        return Err(Pbkdf2Error::ShortSalt);
+    }
+    let mut bytes = [0; 32];
+    pbkdf2_hmac_sha256(password, salt, iterations, &mut bytes[..size.bytes()])?;
+    Ok(Key::from_valid_bytes(&bytes[..size.bytes()]))
+}
+
+/**
+ * A random salt of MIN_SALT_LEN bytes
+ */
+#[cfg(feature = "std")]
+pub fn generate_salt() -> Result<[u8; MIN_SALT_LEN], RandomError> {
+    let mut salt = [0; MIN_SALT_LEN];
+    random::fill(&mut salt)?;
+    Ok(salt)
+}
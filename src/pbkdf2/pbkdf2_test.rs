@@ -0,0 +1,111 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod pbkdf2_test {
+    use crate::cipher::{Cipher, Key, KeySize};
+    use crate::pbkdf2::{derive_key, pbkdf2_hmac_sha256, Pbkdf2Error};
+
+    fn derive(password: &[u8], salt: &[u8], iterations: u32, output: &mut [u8]) {
+        pbkdf2_hmac_sha256(password, salt, iterations, output).unwrap();
+    }
+
+    /**
+     * Section 11 of RFC 7914
+     */
+    #[test]
+    fn it_matches_rfc_7914() {
+        let mut output = [0; 64];
+        derive(b"passwd", b"salt", 1, &mut output);
+        assert_eq!(
+            output[..32],
+            [
+                0x55, 0xac, 0x04, 0x6e, 0x56, 0xe3, 0x08, 0x9f, 0xec, 0x16, 0x91, 0xc2, 0x25, 0x44,
+                0xb6, 0x05, 0xf9, 0x41, 0x85, 0x21, 0x6d, 0xde, 0x04, 0x65, 0xe6, 0x8b, 0x9d, 0x57,
+                0xc2, 0x0d, 0xac, 0xbc
+            ]
+        );
+        assert_eq!(
+            output[32..],
+            [
+                0x49, 0xca, 0x9c, 0xcc, 0xf1, 0x79, 0xb6, 0x45, 0x99, 0x16, 0x64, 0xb3, 0x9d, 0x77,
+                0xef, 0x31, 0x7c, 0x71, 0xb8, 0x45, 0xb1, 0xe3, 0x0b, 0xd5, 0x09, 0x11, 0x20, 0x41,
+                0xd3, 0xa1, 0x97, 0x83
+            ]
+        );
+        derive(b"Password", b"NaCl", 80000, &mut output);
+        assert_eq!(
+            output[..16],
+            0x4ddcd8f60b98be21830cee5ef22701f9_u128.to_be_bytes()
+        );
+        assert_eq!(
+            output[48..],
+            0x6a272bdebba1d078478f62b397f33c8d_u128.to_be_bytes()
+        );
+    }
+
+    /**
+     * The cases of RFC 6070 with SHA-256 in place of SHA-1, including a
+     * truncated block and NUL bytes
+     */
+    #[test]
+    fn it_matches_rfc_6070_with_sha_256() {
+        let mut output = [0; 32];
+        for &(iterations, first_half) in [
+            (1, 0x120fb6cffcf8b32c43e7225256c4f837_u128),
+            (2, 0xae4d0c95af6b46d32d0adff928f06dd0),
+            (4096, 0xc5e478d59288c841aa530db6845c4c8d),
+        ]
+        .iter()
+        {
+            derive(b"password", b"salt", iterations, &mut output);
+            assert_eq!(output[..16], first_half.to_be_bytes());
+        }
+        let mut output = [0; 40];
+        derive(
+            b"passwordPASSWORDpassword",
+            b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+            4096,
+            &mut output,
+        );
+        assert_eq!(output[32..], 0xc635518c7dac47e9_u64.to_be_bytes());
+        let mut output = [0; 16];
+        derive(b"pass\0word", b"sa\0lt", 4096, &mut output);
+        assert_eq!(
+            output,
+            0x89b69d0516f829893c696226650a8687_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_derives_cipher_keys() {
+        let salt = [9; 16];
+        let key = derive_key(b"password", &salt, 1000, KeySize::Aes192).unwrap();
+        let mut bytes = [0; 24];
+        derive(b"password", &salt, 1000, &mut bytes);
+        assert_eq!(key, Key::from_bytes(&bytes).unwrap());
+        let mut block = [0; 16];
+        Cipher::from_key(&key).cipher(&mut block);
+        let words = match key {
+            Key::Aes192(words) => words,
+            _ => unreachable!(),
+        };
+        let mut other = [0; 16];
+        Cipher::new_192(&words).cipher(&mut other);
+        assert_eq!(block, other);
+    }
+
+    #[test]
+    fn it_refuses_bad_parameters() {
+        assert_eq!(
+            derive_key(b"password", b"salt", 1000, KeySize::Aes128),
+            Err(Pbkdf2Error::ShortSalt)
+        );
+        assert_eq!(
+            derive_key(b"password", &[0; 16], 0, KeySize::Aes128),
+            Err(Pbkdf2Error::ZeroIterations)
+        );
+        assert_eq!(
+            pbkdf2_hmac_sha256(b"password", b"salt", 1, &mut []),
+            Err(Pbkdf2Error::OutputLength)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+ * PMAC, the parallelizable message authentication code of Black and Rogaway
+ * (PMAC1, the version also used by PMAC-SIV).
+ *
+ * Each block but the last is xored with an offset and ciphered on its own, the
+ * results are xored together. The offset of block i is the combination of the
+ * values L(j) = L * x^j, with L = CIPH(0), selected by the Gray code of i, the
+ * same table used by OCB. Any range of blocks can then be processed without
+ * looking at the others, which is what mac_parallel does.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+use crate::math::Math;
+
+mod pmac_test;
+
+/**
+ * Length in bytes of a full tag
+ */
+pub const TAG_LEN: usize = 16;
+
+/**
+ * Length in bytes of the shortest tag accepted by verify_truncated
+ */
+pub const MIN_TRUNCATED_TAG_LEN: usize = 8;
+
+/**
+ * Number of precomputed L(j): the block index is a u64, so the Gray code
+ * of a block index never has more than 64 bits
+ */
+const L_TABLE_LEN: usize = 64;
+
+/**
+ * The state of a PMAC computation: the key dependent table of offsets and
+ * the sum of the blocks processed so far. The last block is kept aside,
+ * since a full last block is treated differently from the other ones.
+ */
+pub struct Pmac<C = Encryptor> {
+    encryptor: C,
+    l_table: [[u8; 16]; L_TABLE_LEN],
+    l_inverse: [u8; 16],
+    offset: [u8; 16],
+    sum: [u8; 16],
+    blocks: u64,
+    last_block: [u8; 16],
+    filled: usize,
+}
+
+impl Pmac {
+    pub fn new(key: &Key) -> Pmac {
+        Self::from_encryptor(Encryptor::from_key(key))
+    }
+}
+
+impl<C: BlockEncrypt> Pmac<C> {
+    /**
+     * Precomputes L(j) for every j and L(-1) = L * x^-1
+     */
+    pub fn from_encryptor(encryptor: C) -> Pmac<C> {
+        let mut l = [0; 16];
+        encryptor.encrypt_block(&mut l);
+        let mut l_table = [[0; 16]; L_TABLE_LEN];
+        let mut value = u128::from_be_bytes(l);
+        for entry in l_table.iter_mut() {
+            *entry = value.to_be_bytes();
+            value = (value << 1) ^ (0x87 & (value >> 127).wrapping_neg());
+        }
+        let value = u128::from_be_bytes(l);
+        let l_inverse = (value >> 1) ^ ((1 << 127 | 0x43) & (value & 1).wrapping_neg());
+        Pmac {
+            encryptor,
+            l_table,
+            l_inverse: l_inverse.to_be_bytes(),
+            offset: [0; 16],
+            sum: [0; 16],
+            blocks: 0,
+            last_block: [0; 16],
+            filled: 0,
+        }
+    }
+
+    /**
+     * Adds data to the message
+     */
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            if self.filled == 16 {
+                self.blocks += 1;
+                xor(
+                    &mut self.offset,
+                    &self.l_table[self.blocks.trailing_zeros() as usize],
+                );
+                let mut block = self.last_block;
+                xor(&mut block, &self.offset);
+                self.encryptor.encrypt_block(&mut block);
+                xor(&mut self.sum, &block);
+                self.filled = 0;
+            }
+            self.last_block[self.filled] = *byte;
+            self.filled += 1;
+        }
+    }
+
+    /**
+     * Returns the tag of the message and resets the state for the next one
+     */
+    pub fn finalize(&mut self) -> [u8; TAG_LEN] {
+        let tag = self.tag(self.sum, &self.last_block[..self.filled]);
+        self.offset = [0; 16];
+        self.sum = [0; 16];
+        self.blocks = 0;
+        self.filled = 0;
+        tag
+    }
+
+    /**
+     * Tag of a whole message
+     */
+    pub fn mac(&mut self, message: &[u8]) -> [u8; TAG_LEN] {
+        self.update(message);
+        self.finalize()
+    }
+
+    /**
+     * Checks the full TAG_LEN bytes tag of the message. The comparison takes
+     * the same time wherever the first difference is.
+     */
+    pub fn verify(&mut self, tag: &[u8]) -> bool {
+        self.verify_truncated(tag, TAG_LEN)
+    }
+
+    /**
+     * Checks a tag truncated to its first len bytes. len comes from the
+     * protocol, not from the received tag, and must be at least
+     * MIN_TRUNCATED_TAG_LEN: shorter tags always fail.
+     */
+    pub fn verify_truncated(&mut self, tag: &[u8], len: usize) -> bool {
+        let expected = self.finalize();
+        (MIN_TRUNCATED_TAG_LEN..=TAG_LEN).contains(&len)
+            && tag.len() == len
+            && Math::constant_time_eq(&expected[..len], tag)
+    }
+
+    /**
+     * Tag of a whole message computed by the given number of threads, each of
+     * which processes a contiguous range of blocks. The result is the same of mac.
+     * It doesn't touch the state of incremental computations.
+     */
+    #[cfg(feature = "std")]
+    pub fn mac_parallel(&self, message: &[u8], threads: usize) -> [u8; TAG_LEN]
+    where
+        C: Sync,
+    {
+        let (blocks, last_block) = split_last_block(message);
+        let block_count = blocks.len() / 16;
+        let blocks_per_thread = block_count.div_ceil(threads.max(1)).max(1);
+        let sum = std::thread::scope(|scope| {
+            let handles: std::vec::Vec<_> = blocks
+                .chunks(blocks_per_thread * 16)
+                .enumerate()
+                .map(|(i, range)| {
+                    scope.spawn(move || self.sum_blocks((i * blocks_per_thread) as u64, range))
+                })
+                .collect();
+            handles.into_iter().fold([0; 16], |mut sum, handle| {
+                xor(&mut sum, &handle.join().unwrap());
+                sum
+            })
+        });
+        self.tag(sum, last_block)
+    }
+
+    /**
+     * Sum of the ciphered blocks of a range that starts after the given number
+     * of blocks. The starting offset is computed directly from the Gray code.
+     */
+    #[cfg(feature = "std")]
+    fn sum_blocks(&self, preceding_blocks: u64, blocks: &[u8]) -> [u8; 16] {
+        let mut offset = [0; 16];
+        let gray_code = preceding_blocks ^ (preceding_blocks >> 1);
+        for (j, l) in self.l_table.iter().enumerate() {
+            if gray_code >> j & 1 == 1 {
+                xor(&mut offset, l);
+            }
+        }
+        let mut sum = [0; 16];
+        for (i, chunk) in blocks.chunks(16).enumerate() {
+            let index = preceding_blocks + i as u64 + 1;
+            xor(&mut offset, &self.l_table[index.trailing_zeros() as usize]);
+            let mut block = [0; 16];
+            block.copy_from_slice(chunk);
+            xor(&mut block, &offset);
+            self.encryptor.encrypt_block(&mut block);
+            xor(&mut sum, &block);
+        }
+        sum
+    }
+
+    /**
+     * Adds the last block to the sum and ciphers it. A full last block is
+     * xored with L(-1), a shorter one is padded with a one bit and zeros.
+     */
+    fn tag(&self, mut sum: [u8; 16], last_block: &[u8]) -> [u8; TAG_LEN] {
+        let mut block = [0; 16];
+        block[..last_block.len()].copy_from_slice(last_block);
+        if last_block.len() == 16 {
+            xor(&mut block, &self.l_inverse);
+        } else {
+            block[last_block.len()] = 0x80;
+        }
+        xor(&mut sum, &block);
+        self.encryptor.encrypt_block(&mut sum);
+        sum
+    }
+}
+
+/**
+ * The full blocks that are summed and the last block, which is never empty
+ * unless the message is
+ */
+#[cfg(feature = "std")]
+fn split_last_block(message: &[u8]) -> (&[u8], &[u8]) {
+    let last_len = match message.len() % 16 {
+        0 => message.len().min(16),
+        remainder => remainder,
+    };
+    message.split_at(message.len() - last_len)
+}
+
+fn xor(target: &mut [u8; 16], data: &[u8; 16]) {
+    for (byte, other) in target.iter_mut().zip(data.iter()) {
+        *byte ^= other;
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod pmac_test {
+    use crate::cipher::Key;
+    use crate::pmac::Pmac;
+
+    const KEY_128: Key = Key::Aes128([0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+    const KEY_256: Key = Key::Aes256([
+        0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+        0x1c1d1e1f,
+    ]);
+
+    /**
+     * The messages of the known answers: the first bytes of 00 01 02 ...
+     */
+    fn message(length: usize) -> [u8; 34] {
+        let mut message = [0; 34];
+        for (i, byte) in message[..length].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        message
+    }
+
+    /**
+     * The PMAC-AES known answers shared by the PMAC1 implementations, among
+     * which the ones of PMAC-SIV
+     */
+    #[test]
+    fn it_matches_known_answers() {
+        let cases = [
+            (KEY_128, 0, 0x4399572cd6ea5341b8d35876a7098af7_u128),
+            (KEY_128, 3, 0x256ba5193c1b991b4df0c51f388a9e27),
+            (KEY_128, 16, 0xebbd822fa458daf6dfdad7c27da76338),
+            (KEY_128, 20, 0x0412ca150bbf79058d8c75a58c993f55),
+            (KEY_128, 32, 0xe97ac04e9e5e3399ce5355cd7407bc75),
+            (KEY_128, 34, 0x5cba7d5eb24f7c86ccc54604e53d5512),
+            (KEY_256, 0, 0xe620f52fe75bbe87ab758c0624943d8b),
+            (KEY_256, 3, 0xffe124cc152cfb2bf1ef5409333c1c9a),
+            (KEY_256, 34, 0xedd8a05f4b66761f9eee4feb4ed0c3a1),
+        ];
+        for (key, length, tag) in cases.iter() {
+            let mut pmac = Pmac::new(key);
+            assert_eq!(pmac.mac(&message(*length)[..*length]), tag.to_be_bytes());
+        }
+        let mut pmac = Pmac::new(&KEY_128);
+        assert_eq!(
+            pmac.mac(&[0; 1000]),
+            0xc2c9fa1d9985f6f0d2aff915a0e8d910_u128.to_be_bytes()
+        );
+        let mut pmac = Pmac::new(&KEY_256);
+        assert_eq!(
+            pmac.mac(&[0; 1000]),
+            0x69aa77f231eb0cdff960f5561d29a96e_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn it_macs_in_pieces() {
+        let mut pmac = Pmac::new(&KEY_128);
+        for piece in message(34).chunks(5) {
+            pmac.update(piece);
+        }
+        let tag = 0x5cba7d5eb24f7c86ccc54604e53d5512_u128.to_be_bytes();
+        assert_eq!(pmac.finalize(), tag);
+        pmac.update(&message(34));
+        assert!(pmac.verify(&tag));
+        pmac.update(&message(34));
+        assert!(!pmac.verify(&tag[..8]));
+        pmac.update(&message(34));
+        assert!(pmac.verify_truncated(&tag[..8], 8));
+        pmac.update(&message(34));
+        assert!(!pmac.verify_truncated(&tag[..8], 12));
+        pmac.update(&message(34));
+        assert!(!pmac.verify_truncated(&tag[..4], 4));
+        pmac.update(&message(33)[..33]);
+        assert!(!pmac.verify(&tag));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_macs_in_parallel() {
+        let mut message = [0u8; 16 * 100 + 7];
+        for (i, byte) in message.iter_mut().enumerate() {
+            *byte = (i * 31 % 256) as u8;
+        }
+        let mut pmac = Pmac::new(&KEY_256);
+        for length in [0, 7, 16, 32, 16 * 100, 16 * 100 + 7].iter() {
+            let expected = pmac.mac(&message[..*length]);
+            for threads in 1..=9 {
+                assert_eq!(pmac.mac_parallel(&message[..*length], threads), expected);
+            }
+        }
+        assert_eq!(
+            pmac.mac_parallel(&[0; 1000], 4),
+            0x69aa77f231eb0cdff960f5561d29a96e_u128.to_be_bytes()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+/*!
+ * Random bytes from the operating system, for fresh keys, IVs and nonces.
+ *
+ * On Linux the bytes come from the getrandom system call, made through the
+ * generic syscall function of the C library, so that the binary also links
+ * against C libraries older than their getrandom wrapper. When the kernel is
+ * too old to have the call, or on other systems, they are read from
+ * /dev/urandom. Only available with the std feature.
+ *
+ * What each mode needs:
+ *
+ * - CBC and CTR: generate_iv
+ * - GCM: generate_nonce_96
+ * - STREAM: stream::generate_prefix
+ * - the salts of the KDFs: fill, they are just random bytes of the length
+ *   given by the KDF
+ * - XTS needs nothing random: its tweak is the number of the data unit, which
+ *   must be the same every time the unit is encrypted or decrypted
+ * - the key wrap needs nothing random either, it is deterministic by design
+ *   and its IV is the constant of RFC 3394
+ */
+use std::fs::File;
+use std::io::{self, Read};
+#[cfg(target_os = "linux")]
+use std::os::raw::c_long;
+use std::{error, fmt};
+
+mod random_test;
+
+/**
+ * Reasons for which no random bytes could be produced
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomError {
+    /**
+     * The getrandom system call failed, with the given errno
+     */
+    Os(i32),
+    /**
+     * /dev/urandom could not be opened or read
+     */
+    Device(io::ErrorKind),
+}
+
+impl fmt::Display for RandomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomError::Os(errno) => write!(f, "getrandom failed with errno {}", errno),
+            RandomError::Device(kind) => write!(f, "could not read /dev/urandom: {:?}", kind),
+        }
+    }
+}
+
+impl error::Error for RandomError {}
+
+/**
+ * Fills bytes with random bytes from the operating system
+ */
+pub fn fill(bytes: &mut [u8]) -> Result<(), RandomError> {
+    #[cfg(target_os = "linux")]
+    {
+        if fill_from_getrandom(bytes)? {
+            return Ok(());
+        }
+    }
+    fill_from_device(bytes)
+}
+
+/**
+ * A random initialization vector as long as a block, as used by CBC, CFB and OFB,
+ * or a random initial counter block for CTR
+ */
+pub fn generate_iv() -> Result<[u8; 16], RandomError> {
+    let mut iv = [0; 16];
+    fill(&mut iv)?;
+    Ok(iv)
+}
+
+/**
+ * A random 96 bits nonce, the length recommended for GCM. With random nonces
+ * a GCM key must not be used for more than 2^32 messages.
+ */
+pub fn generate_nonce_96() -> Result<[u8; 12], RandomError> {
+    let mut nonce = [0; 12];
+    fill(&mut nonce)?;
+    Ok(nonce)
+}
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn syscall(number: c_long, ...) -> c_long;
+}
+
+/**
+ * Number of the getrandom system call, which changes with the architecture.
+ * None where it is not known here, /dev/urandom is used there.
+ */
+#[cfg(target_os = "linux")]
+const SYS_GETRANDOM: Option<c_long> = if cfg!(target_arch = "x86_64") {
+    Some(318)
+} else if cfg!(target_arch = "x86") {
+    Some(355)
+} else if cfg!(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "riscv32",
+    target_arch = "loongarch64"
+)) {
+    Some(278)
+} else if cfg!(target_arch = "arm") {
+    Some(384)
+} else if cfg!(any(target_arch = "powerpc", target_arch = "powerpc64")) {
+    Some(359)
+} else if cfg!(target_arch = "s390x") {
+    Some(349)
+} else if cfg!(target_arch = "mips") {
+    Some(4353)
+} else if cfg!(target_arch = "mips64") {
+    Some(5313)
+} else {
+    None
+};
+
+/**
+ * The errno of a missing system call, which MIPS numbers differently
+ */
+#[cfg(target_os = "linux")]
+const ENOSYS: i32 = if cfg!(any(target_arch = "mips", target_arch = "mips64")) {
+    89
+} else {
+    38
+};
+
+/**
+ * Returns false if the system call doesn't exist, so that the caller can
+ * fall back to the device. The call can return fewer bytes than requested
+ * or be interrupted by a signal, in both cases it is simply repeated.
+ */
+#[cfg(target_os = "linux")]
+fn fill_from_getrandom(bytes: &mut [u8]) -> Result<bool, RandomError> {
+    let number = match SYS_GETRANDOM {
+        Some(number) => number,
+        None => return Ok(false),
+    };
+    let mut filled = 0;
+    while filled < bytes.len() {
+        let rest = &mut bytes[filled..];
+        // SAFETY: getrandom writes at most rest.len() bytes to rest, which is
+        // a valid and exclusively borrowed buffer of that length, and the
+        // arguments have the types of its C signature.
+        let result = unsafe {
+            syscall(
+                number,
+                rest.as_mut_ptr(),
+                rest.len(),
+                0 as std::os::raw::c_uint,
+            )
+        };
+        if result >= 0 {
+            filled += result as usize;
+            continue;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() == io::ErrorKind::Interrupted {
+            continue;
+        }
+        return match error.raw_os_error() {
+            Some(ENOSYS) => Ok(false),
+            errno => Err(RandomError::Os(errno.unwrap_or(0))),
+        };
+    }
+    Ok(true)
+}
+
+fn fill_from_device(bytes: &mut [u8]) -> Result<(), RandomError> {
+    File::open("/dev/urandom")
+        .and_then(|mut device| device.read_exact(bytes))
+        .map_err(|error| RandomError::Device(error.kind()))
+}
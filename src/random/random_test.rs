@@ -0,0 +1,55 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod random_test {
+    use crate::cipher::{Cipher, Key, KeySize};
+    use crate::random::{self, fill_from_device};
+
+    #[test]
+    fn it_fills_buffers() {
+        // two draws of 64 bytes are equal with probability 2^-512
+        let (mut first, mut second) = ([0u8; 64], [0u8; 64]);
+        random::fill(&mut first).unwrap();
+        random::fill(&mut second).unwrap();
+        assert_ne!(first, second);
+        fill_from_device(&mut first).unwrap();
+        assert_ne!(first, second);
+        // the system call itself works, without falling back to the device
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        {
+            assert_eq!(random::fill_from_getrandom(&mut first), Ok(true));
+            assert_ne!(first, second);
+        }
+        random::fill(&mut []).unwrap();
+    }
+
+    #[test]
+    fn it_generates_keys() {
+        for size in [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256].iter() {
+            let key = Cipher::generate_key(*size).unwrap();
+            assert_eq!(key.size(), *size);
+            assert_eq!(key.words().len(), size.words());
+            assert_ne!(key, Cipher::generate_key(*size).unwrap());
+        }
+        let key = Cipher::generate_key(KeySize::Aes256).unwrap();
+        assert!(matches!(key, Key::Aes256(_)));
+        let cipher = Cipher::from_key(&key);
+        let mut content = random::generate_iv().unwrap();
+        let original = content;
+        cipher.cipher(&mut content);
+        assert_ne!(content, original);
+        cipher.decipher(&mut content);
+        assert_eq!(content, original);
+    }
+
+    #[test]
+    fn it_generates_nonces() {
+        assert_ne!(
+            random::generate_nonce_96().unwrap(),
+            random::generate_nonce_96().unwrap()
+        );
+        assert_ne!(
+            random::generate_iv().unwrap(),
+            random::generate_iv().unwrap()
+        );
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod sha256_test {
+    use crate::sha256::Sha256;
+
+    fn digest_of(pieces: &[&[u8]]) -> u128 {
+        let mut sha = Sha256::new();
+        for piece in pieces {
+            sha.update(piece);
+        }
+        let digest = sha.finalize();
+        // the first half is enough to tell digests apart in the asserts
+        let mut half = [0; 16];
+        half.copy_from_slice(&digest[..16]);
+        u128::from_be_bytes(half)
+    }
+
+    /**
+     * The examples of FIPS 180-4
+     */
+    #[test]
+    fn it_matches_fips_180() {
+        assert_eq!(
+            Sha256::digest(b"abc"),
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad
+            ]
+        );
+        assert_eq!(digest_of(&[b""]), 0xe3b0c44298fc1c149afbf4c8996fb924);
+        assert_eq!(
+            digest_of(&[b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"]),
+            0x248d6a61d20638b8e5c026930c3e6039
+        );
+    }
+
+    #[test]
+    fn it_hashes_in_pieces() {
+        let million = [b'a'; 1000];
+        let pieces = [&million[..]; 1000];
+        assert_eq!(digest_of(&pieces), 0xcdc76e5c9914fb9281a1c7e284d73e67);
+        let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(
+            digest_of(&[&message[..1], &message[1..55], &message[55..]]),
+            0x248d6a61d20638b8e5c026930c3e6039
+        );
+    }
+}
@@ -0,0 +1,436 @@
+/*!
+ * The STREAM construction of Hoang, Reyhanitabar, Rogaway and Vizár over GCM:
+ * a long message is cut in chunks and every chunk is encrypted and
+ * authenticated on its own, so it can be checked and released as soon as it
+ * is read instead of at the end of the whole message.
+ *
+ * The nonce of a chunk is made of a prefix chosen for the stream, the index of
+ * the chunk and a flag that is set only on the last chunk:
+ *
+ * | prefix (7 bytes) | index (4 bytes, big endian) | last (1 byte) |
+ *
+ * so a chunk that is moved, duplicated or dropped doesn't authenticate in its
+ * new position, and a stream cut after a chunk that isn't the last is
+ * detected because that chunk wasn't flagged. A prefix must never be used for
+ * two streams under the same key.
+ */
+use crate::block::BlockEncrypt;
+use crate::cipher::{Encryptor, Key};
+use crate::error::Error;
+use crate::gcm::{Gcm, TAG_LEN};
+#[cfg(feature = "std")]
+use crate::io::read_retrying;
+#[cfg(feature = "std")]
+use crate::random::RandomError;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+mod stream_test;
+
+/**
+ * Length in bytes of the prefix of the nonces
+ */
+pub const NONCE_PREFIX_LEN: usize = 7;
+
+/**
+ * The length of the plaintext of the chunks used by StreamWriter and
+ * StreamReader unless told otherwise
+ */
+pub const DEFAULT_CHUNK_LEN: usize = 64 * 1024;
+
+/**
+ * A random nonce prefix for a new stream. With random prefixes a key must not
+ * be used for more than 2^20 streams, derive a key per stream (as the
+ * container does) to go past that.
+ */
+#[cfg(feature = "std")]
+pub fn generate_prefix() -> Result<[u8; NONCE_PREFIX_LEN], RandomError> {
+    let mut prefix = [0; NONCE_PREFIX_LEN];
+    crate::random::fill(&mut prefix)?;
+    Ok(prefix)
+}
+
+/**
+ * Encrypts a stream one chunk at a time. The chunks must be decrypted in the
+ * same order by a StreamDecryptor.
+ */
+pub struct StreamEncryptor<C = Encryptor> {
+    nonces: Nonces,
+    gcm: Gcm<C>,
+}
+
+/**
+ * Decrypts a stream one chunk at a time, in the order they were encrypted.
+ * A stream is complete only once decrypt_last has succeeded.
+ */
+pub struct StreamDecryptor<C = Encryptor> {
+    nonces: Nonces,
+    gcm: Gcm<C>,
+}
+
+struct Nonces {
+    prefix: [u8; NONCE_PREFIX_LEN],
+    index: u64,
+}
+
+impl Nonces {
+    /**
+     * The nonce of the next chunk. Fails with Error::NonceReuse once the 2^32
+     * indexes have all been used.
+     */
+    fn next(&mut self, last: bool) -> Result<[u8; 12], Error> {
+        if self.index > u64::from(u32::MAX) {
+            return Err(Error::NonceReuse);
+        }
+        let mut nonce = [0; 12];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.prefix);
+        nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&(self.index as u32).to_be_bytes());
+        nonce[11] = last as u8;
+        self.index += 1;
+        Ok(nonce)
+    }
+}
+
+impl StreamEncryptor {
+    pub fn new(key: &Key, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamEncryptor {
+        Self::from_encryptor(Encryptor::from_key(key), prefix)
+    }
+}
+
+impl<C: BlockEncrypt> StreamEncryptor<C> {
+    pub fn from_encryptor(encryptor: C, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamEncryptor<C> {
+        StreamEncryptor {
+            nonces: Nonces {
+                prefix: *prefix,
+                index: 0,
+            },
+            gcm: Gcm::from_encryptor(encryptor),
+        }
+    }
+
+    /**
+     * Encrypts a chunk that is not the last one in place and returns its tag
+     */
+    pub fn encrypt_next(&mut self, aad: &[u8], buffer: &mut [u8]) -> Result<[u8; TAG_LEN], Error> {
+        let nonce = self.nonces.next(false)?;
+        self.gcm.encrypt(&nonce, aad, buffer)
+    }
+
+    /**
+     * Encrypts the last chunk, which can be empty, and ends the stream
+     */
+    pub fn encrypt_last(mut self, aad: &[u8], buffer: &mut [u8]) -> Result<[u8; TAG_LEN], Error> {
+        let nonce = self.nonces.next(true)?;
+        self.gcm.encrypt(&nonce, aad, buffer)
+    }
+}
+
+impl StreamDecryptor {
+    pub fn new(key: &Key, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamDecryptor {
+        Self::from_encryptor(Encryptor::from_key(key), prefix)
+    }
+}
+
+impl<C: BlockEncrypt> StreamDecryptor<C> {
+    pub fn from_encryptor(encryptor: C, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamDecryptor<C> {
+        StreamDecryptor {
+            nonces: Nonces {
+                prefix: *prefix,
+                index: 0,
+            },
+            gcm: Gcm::from_encryptor(encryptor),
+        }
+    }
+
+    /**
+     * Checks the tag of a chunk that is not the last one and only then decrypts
+     * it in place, see Gcm::decrypt: the tag must be the full TAG_LEN bytes.
+     * A chunk that fails is not counted, so the next call expects the same
+     * chunk again.
+     */
+    pub fn decrypt_next(&mut self, aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> Result<(), Error> {
+        let nonce = self.nonces.next(false)?;
+        let result = self.gcm.decrypt(&nonce, aad, buffer, tag);
+        if result.is_err() {
+            self.nonces.index -= 1;
+        }
+        result
+    }
+
+    /**
+     * Checks and decrypts the last chunk. Fails with Error::AuthenticationFailed
+     * if the chunk was not encrypted as the last one, for example because the
+     * stream has been cut.
+     */
+    pub fn decrypt_last(mut self, aad: &[u8], buffer: &mut [u8], tag: &[u8]) -> Result<(), Error> {
+        let nonce = self.nonces.next(true)?;
+        self.gcm.decrypt(&nonce, aad, buffer, tag)
+    }
+}
+
+/**
+ * Encrypts what is written to it in chunks of chunk_len bytes, each followed by
+ * its tag, and writes them to inner. finish must be called at the end to write
+ * the last chunk.
+ *
+ * If inner fails to take a chunk, the stream is broken: every following write
+ * and finish return the same error.
+ */
+#[cfg(feature = "std")]
+pub struct StreamWriter<W: Write> {
+    inner: W,
+    encryptor: StreamEncryptor,
+    aad: Vec<u8>,
+    chunk: Vec<u8>,
+    chunk_len: usize,
+    error: Option<Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> StreamWriter<W> {
+    /**
+     * Uses chunks of DEFAULT_CHUNK_LEN bytes
+     */
+    pub fn new(inner: W, key: &Key, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamWriter<W> {
+        StreamWriter {
+            inner,
+            encryptor: StreamEncryptor::new(key, prefix),
+            aad: Vec::new(),
+            chunk: Vec::with_capacity(DEFAULT_CHUNK_LEN + TAG_LEN),
+            chunk_len: DEFAULT_CHUNK_LEN,
+            error: None,
+        }
+    }
+
+    /**
+     * Uses chunks of chunk_len bytes, which can't be 0. The reader must be
+     * told the same length.
+     */
+    pub fn with_chunk_len(
+        inner: W,
+        key: &Key,
+        prefix: &[u8; NONCE_PREFIX_LEN],
+        chunk_len: usize,
+    ) -> Result<StreamWriter<W>, Error> {
+        Self::with_aad(inner, key, prefix, chunk_len, &[])
+    }
+
+    /**
+     * Like with_chunk_len, and every chunk also authenticates aad, for example
+     * a header written before the stream
+     */
+    pub fn with_aad(
+        inner: W,
+        key: &Key,
+        prefix: &[u8; NONCE_PREFIX_LEN],
+        chunk_len: usize,
+        aad: &[u8],
+    ) -> Result<StreamWriter<W>, Error> {
+        if chunk_len == 0 {
+            return Err(Error::InvalidLength);
+        }
+        Ok(StreamWriter {
+            inner,
+            encryptor: StreamEncryptor::new(key, prefix),
+            aad: aad.to_vec(),
+            chunk: Vec::with_capacity(chunk_len + TAG_LEN),
+            chunk_len,
+            error: None,
+        })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /**
+     * Writes what is left as the last chunk, flushes and gives back the
+     * underlying writer
+     */
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let tag = self.encryptor.encrypt_last(&self.aad, &mut self.chunk)?;
+        self.inner.write_all(&self.chunk)?;
+        self.inner.write_all(&tag)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        // a full chunk is written only once more data shows that it isn't the last
+        if self.chunk.len() == self.chunk_len && !data.is_empty() {
+            let tag = self.encryptor.encrypt_next(&self.aad, &mut self.chunk)?;
+            self.chunk.extend_from_slice(&tag);
+            let written = self.inner.write_all(&self.chunk);
+            // the chunk is encrypted now, it can't be kept as plaintext
+            self.chunk.clear();
+            if let Err(error) = written {
+                let error = Error::from(error);
+                self.error = Some(error);
+                return Err(error.into());
+            }
+        }
+        let taken = data.len().min(self.chunk_len - self.chunk.len());
+        self.chunk.extend_from_slice(&data[..taken]);
+        Ok(taken)
+    }
+
+    /**
+     * Flushes the underlying writer. The chunk being filled is only written
+     * once it is complete or by finish.
+     */
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/**
+ * Reads a stream written by a StreamWriter and decrypts it a chunk at a time.
+ * Every chunk is authenticated before any of its plaintext is returned, and a
+ * stream that ends without its last chunk gives an error instead of the end
+ * of file.
+ */
+#[cfg(feature = "std")]
+pub struct StreamReader<R: Read> {
+    inner: R,
+    decryptor: Option<StreamDecryptor>,
+    aad: Vec<u8>,
+    chunk_len: usize,
+    // the ciphertext read so far, then the plaintext of the chunk
+    buffer: Vec<u8>,
+    output_len: usize,
+    position: usize,
+    error: Option<Error>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> StreamReader<R> {
+    /**
+     * Expects chunks of DEFAULT_CHUNK_LEN bytes
+     */
+    pub fn new(inner: R, key: &Key, prefix: &[u8; NONCE_PREFIX_LEN]) -> StreamReader<R> {
+        StreamReader {
+            inner,
+            decryptor: Some(StreamDecryptor::new(key, prefix)),
+            aad: Vec::new(),
+            chunk_len: DEFAULT_CHUNK_LEN,
+            buffer: Vec::with_capacity(DEFAULT_CHUNK_LEN + TAG_LEN + 1),
+            output_len: 0,
+            position: 0,
+            error: None,
+        }
+    }
+
+    /**
+     * Expects chunks of chunk_len bytes, which can't be 0
+     */
+    pub fn with_chunk_len(
+        inner: R,
+        key: &Key,
+        prefix: &[u8; NONCE_PREFIX_LEN],
+        chunk_len: usize,
+    ) -> Result<StreamReader<R>, Error> {
+        Self::with_aad(inner, key, prefix, chunk_len, &[])
+    }
+
+    /**
+     * Expects chunks of chunk_len bytes that also authenticate aad
+     */
+    pub fn with_aad(
+        inner: R,
+        key: &Key,
+        prefix: &[u8; NONCE_PREFIX_LEN],
+        chunk_len: usize,
+        aad: &[u8],
+    ) -> Result<StreamReader<R>, Error> {
+        if chunk_len == 0 {
+            return Err(Error::InvalidLength);
+        }
+        let mut reader = Self::new(inner, key, prefix);
+        reader.aad = aad.to_vec();
+        reader.chunk_len = chunk_len;
+        reader.buffer = Vec::with_capacity(chunk_len + TAG_LEN + 1);
+        Ok(reader)
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /**
+     * Reads and decrypts the next chunk into buffer. One byte past the chunk
+     * is read too, to know whether the chunk is the last one.
+     */
+    fn fill(&mut self) -> Result<(), Error> {
+        // the byte read past the previous chunk
+        self.buffer.drain(..self.output_len);
+        self.output_len = 0;
+        self.position = 0;
+        let wanted = self.chunk_len + TAG_LEN + 1;
+        let mut read = [0; 4096];
+        while self.buffer.len() < wanted {
+            let length = (wanted - self.buffer.len()).min(read.len());
+            let length = read_retrying(&mut self.inner, &mut read[..length])?;
+            if length == 0 {
+                break;
+            }
+            self.buffer.extend_from_slice(&read[..length]);
+        }
+        if self.buffer.len() == wanted {
+            let (text, tag) = self.buffer[..wanted - 1].split_at_mut(self.chunk_len);
+            if let Some(decryptor) = self.decryptor.as_mut() {
+                decryptor.decrypt_next(&self.aad, text, tag)?;
+            }
+            // the tag goes, the next byte stays
+            self.buffer.drain(self.chunk_len..wanted - 1);
+            self.output_len = self.chunk_len;
+        } else {
+            if self.buffer.len() < TAG_LEN {
+                return Err(Error::InvalidLength);
+            }
+            let text_len = self.buffer.len() - TAG_LEN;
+            let (text, tag) = self.buffer.split_at_mut(text_len);
+            if let Some(decryptor) = self.decryptor.take() {
+                decryptor.decrypt_last(&self.aad, text, tag)?;
+            }
+            self.buffer.truncate(text_len);
+            self.output_len = text_len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        while self.position == self.output_len {
+            if self.decryptor.is_none() {
+                return Ok(0);
+            }
+            if let Err(error) = self.fill() {
+                self.error = Some(error);
+                return Err(error.into());
+            }
+        }
+        let length = buffer.len().min(self.output_len - self.position);
+        buffer[..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
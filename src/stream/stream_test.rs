@@ -0,0 +1,304 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod stream_test {
+    use crate::cipher::Key;
+    use crate::error::Error;
+    use crate::gcm::Gcm;
+    use crate::stream::{StreamDecryptor, StreamEncryptor, NONCE_PREFIX_LEN};
+
+    const KEY: Key = Key::Aes128([0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f]);
+    const PREFIX: [u8; NONCE_PREFIX_LEN] = [7, 6, 5, 4, 3, 2, 1];
+
+    /**
+     * Three chunks of 16 bytes and an empty last one, with their tags
+     */
+    fn encrypt() -> ([[u8; 16]; 3], [[u8; 16]; 4]) {
+        let mut chunks = [[1; 16], [2; 16], [3; 16]];
+        let mut tags = [[0; 16]; 4];
+        let mut encryptor = StreamEncryptor::new(&KEY, &PREFIX);
+        for (chunk, tag) in chunks.iter_mut().zip(tags.iter_mut()) {
+            *tag = encryptor.encrypt_next(b"aad", chunk).unwrap();
+        }
+        tags[3] = encryptor.encrypt_last(b"aad", &mut []).unwrap();
+        (chunks, tags)
+    }
+
+    #[test]
+    fn it_builds_the_nonces() {
+        let (chunks, tags) = encrypt();
+        let gcm = Gcm::new(&KEY);
+        let mut nonce = [7, 6, 5, 4, 3, 2, 1, 0, 0, 0, 2, 0];
+        let mut buffer = [3; 16];
+        assert_eq!(gcm.encrypt(&nonce, b"aad", &mut buffer), Ok(tags[2]));
+        assert_eq!(buffer, chunks[2]);
+        nonce[10] = 3;
+        nonce[11] = 1;
+        assert_eq!(gcm.encrypt(&nonce, b"aad", &mut []), Ok(tags[3]));
+    }
+
+    #[test]
+    fn it_round_trips_chunks() {
+        let (mut chunks, tags) = encrypt();
+        let mut decryptor = StreamDecryptor::new(&KEY, &PREFIX);
+        for (i, chunk) in chunks.iter_mut().enumerate() {
+            decryptor.decrypt_next(b"aad", chunk, &tags[i]).unwrap();
+            assert_eq!(*chunk, [i as u8 + 1; 16]);
+        }
+        decryptor.decrypt_last(b"aad", &mut [], &tags[3]).unwrap();
+    }
+
+    #[test]
+    fn it_detects_reordering_duplication_and_truncation() {
+        let (mut chunks, tags) = encrypt();
+        let mut decryptor = StreamDecryptor::new(&KEY, &PREFIX);
+        // cut tag
+        assert_eq!(
+            decryptor.decrypt_next(b"aad", &mut chunks[0], &tags[0][..12]),
+            Err(Error::InvalidLength)
+        );
+        // reordered
+        assert_eq!(
+            decryptor.decrypt_next(b"aad", &mut chunks[1], &tags[1]),
+            Err(Error::AuthenticationFailed)
+        );
+        decryptor
+            .decrypt_next(b"aad", &mut chunks[0], &tags[0])
+            .unwrap();
+        // duplicated
+        let mut copy = encrypt().0[0];
+        assert_eq!(
+            decryptor.decrypt_next(b"aad", &mut copy, &tags[0]),
+            Err(Error::AuthenticationFailed)
+        );
+        decryptor
+            .decrypt_next(b"aad", &mut chunks[1], &tags[1])
+            .unwrap();
+        // truncated after the third chunk
+        assert_eq!(
+            decryptor.decrypt_last(b"aad", &mut chunks[2], &tags[2]),
+            Err(Error::AuthenticationFailed)
+        );
+        // the last chunk can't be passed as any other
+        let mut decryptor = StreamDecryptor::new(&KEY, &PREFIX);
+        for (chunk, tag) in encrypt().0.iter_mut().zip(tags.iter()) {
+            decryptor.decrypt_next(b"aad", chunk, tag).unwrap();
+        }
+        assert_eq!(
+            decryptor.decrypt_next(b"aad", &mut [], &tags[3]),
+            Err(Error::AuthenticationFailed)
+        );
+        assert_eq!(
+            StreamDecryptor::new(&KEY, &[0; NONCE_PREFIX_LEN]).decrypt_next(
+                b"aad",
+                &mut encrypt().0[0],
+                &tags[0]
+            ),
+            Err(Error::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn it_runs_out_of_nonces() {
+        let mut encryptor = StreamEncryptor::new(&KEY, &PREFIX);
+        encryptor.nonces.index = u64::from(u32::MAX);
+        encryptor.encrypt_next(&[], &mut [0; 16]).unwrap();
+        assert_eq!(
+            encryptor.encrypt_next(&[], &mut [0; 16]),
+            Err(Error::NonceReuse)
+        );
+        assert_eq!(encryptor.encrypt_last(&[], &mut []), Err(Error::NonceReuse));
+    }
+
+    #[cfg(feature = "std")]
+    mod io {
+        use super::{KEY, PREFIX};
+        use crate::error::Error;
+        use crate::stream::{generate_prefix, StreamReader, StreamWriter, TAG_LEN};
+        use std::io::{self, Read, Write};
+        use std::vec::Vec;
+
+        const CHUNK_LEN: usize = 32;
+
+        fn message(length: usize) -> Vec<u8> {
+            (0..length).map(|i| (i * 11 % 256) as u8).collect()
+        }
+
+        fn encrypt(message: &[u8]) -> Vec<u8> {
+            let mut writer =
+                StreamWriter::with_chunk_len(Vec::new(), &KEY, &PREFIX, CHUNK_LEN).unwrap();
+            for piece in message.chunks(5) {
+                writer.write_all(piece).unwrap();
+            }
+            writer.finish().unwrap()
+        }
+
+        /**
+         * The plaintext released before the first error, if any
+         */
+        fn decrypt(ciphertext: &[u8]) -> (Vec<u8>, Option<Error>) {
+            let mut reader =
+                StreamReader::with_chunk_len(ciphertext, &KEY, &PREFIX, CHUNK_LEN).unwrap();
+            let mut plaintext = Vec::new();
+            let mut buffer = [0; 7];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => return (plaintext, None),
+                    Ok(length) => plaintext.extend_from_slice(&buffer[..length]),
+                    Err(error) => return (plaintext, Some(error.into())),
+                }
+            }
+        }
+
+        #[test]
+        fn it_round_trips() {
+            for &length in [0, 1, 31, 32, 33, 64, 100].iter() {
+                let message = message(length);
+                let ciphertext = encrypt(&message);
+                let chunks = length.div_ceil(CHUNK_LEN).max(1);
+                assert_eq!(ciphertext.len(), length + chunks * TAG_LEN);
+                assert_eq!(decrypt(&ciphertext), (message, None));
+            }
+            let mut writer = StreamWriter::new(Vec::new(), &KEY, &PREFIX);
+            writer.write_all(&message(100_000)).unwrap();
+            let ciphertext = writer.finish().unwrap();
+            let mut plaintext = Vec::new();
+            StreamReader::new(&ciphertext[..], &KEY, &PREFIX)
+                .read_to_end(&mut plaintext)
+                .unwrap();
+            assert_eq!(plaintext, message(100_000));
+            assert_ne!(generate_prefix().unwrap(), generate_prefix().unwrap());
+        }
+
+        #[test]
+        fn it_detects_attacks_on_the_stream() {
+            let message = message(100);
+            let ciphertext = encrypt(&message);
+            let chunk = CHUNK_LEN + TAG_LEN;
+            let failure = Some(Error::AuthenticationFailed);
+
+            // cut at a chunk boundary: what came before is released, then the error
+            assert_eq!(
+                decrypt(&ciphertext[..2 * chunk]),
+                (message[..CHUNK_LEN].to_vec(), failure)
+            );
+            assert_eq!(
+                decrypt(&ciphertext[..3 * chunk + 5]),
+                (message[..96].to_vec(), Some(Error::InvalidLength))
+            );
+            // a chunk dropped
+            let mut dropped = ciphertext[..chunk].to_vec();
+            dropped.extend_from_slice(&ciphertext[2 * chunk..]);
+            assert_eq!(decrypt(&dropped), (message[..CHUNK_LEN].to_vec(), failure));
+            // a chunk duplicated
+            let mut duplicated = ciphertext[..chunk].to_vec();
+            duplicated.extend_from_slice(&ciphertext);
+            assert_eq!(
+                decrypt(&duplicated),
+                (message[..CHUNK_LEN].to_vec(), failure)
+            );
+            // two chunks swapped
+            let mut swapped = ciphertext[chunk..2 * chunk].to_vec();
+            swapped.extend_from_slice(&ciphertext[..chunk]);
+            swapped.extend_from_slice(&ciphertext[2 * chunk..]);
+            assert_eq!(decrypt(&swapped), (Vec::new(), failure));
+            // data after the last chunk
+            let mut extended = ciphertext.clone();
+            extended.extend_from_slice(&ciphertext[..chunk]);
+            assert_eq!(decrypt(&extended), (message[..96].to_vec(), failure));
+            // the error stays
+            let mut reader =
+                StreamReader::with_chunk_len(&swapped[..], &KEY, &PREFIX, CHUNK_LEN).unwrap();
+            assert!(reader.read(&mut [0; 10]).is_err());
+            assert!(reader.read(&mut [0; 10]).is_err());
+            assert!(StreamReader::with_chunk_len(&[][..], &KEY, &PREFIX, 0).is_err());
+        }
+
+        /**
+         * Gives its bytes a few at a time, and is interrupted by a signal
+         * once, in the middle
+         */
+        struct Interrupted<'a> {
+            bytes: &'a [u8],
+            interrupted: bool,
+        }
+
+        impl Read for Interrupted<'_> {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                if self.bytes.len() < 60 && !self.interrupted {
+                    self.interrupted = true;
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                let length = buffer.len().min(self.bytes.len()).min(20);
+                buffer[..length].copy_from_slice(&self.bytes[..length]);
+                self.bytes = &self.bytes[length..];
+                Ok(length)
+            }
+        }
+
+        #[test]
+        fn it_reads_again_when_interrupted() {
+            let message = message(100);
+            let ciphertext = encrypt(&message);
+            let inner = Interrupted {
+                bytes: &ciphertext,
+                interrupted: false,
+            };
+            let mut reader = StreamReader::with_chunk_len(inner, &KEY, &PREFIX, CHUNK_LEN).unwrap();
+            let mut plaintext = Vec::new();
+            let mut buffer = [0; 7];
+            loop {
+                // an Interrupted error would make read_to_end try again forever
+                let length = reader.read(&mut buffer).unwrap();
+                if length == 0 {
+                    break;
+                }
+                plaintext.extend_from_slice(&buffer[..length]);
+            }
+            assert_eq!(plaintext, message);
+        }
+
+        /**
+         * Takes limit bytes, then fails
+         */
+        struct Full {
+            written: Vec<u8>,
+            limit: usize,
+        }
+
+        impl Write for Full {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                if self.written.len() == self.limit {
+                    return Err(io::ErrorKind::WriteZero.into());
+                }
+                let length = data.len().min(self.limit - self.written.len());
+                self.written.extend_from_slice(&data[..length]);
+                Ok(length)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn it_stops_after_a_failed_write() {
+            let full = Full {
+                written: Vec::new(),
+                limit: 10,
+            };
+            let mut writer = StreamWriter::with_chunk_len(full, &KEY, &PREFIX, CHUNK_LEN).unwrap();
+            writer.write_all(&message(CHUNK_LEN)).unwrap();
+            for _ in 0..2 {
+                assert_eq!(
+                    writer.write(&[1]).unwrap_err().kind(),
+                    io::ErrorKind::WriteZero
+                );
+            }
+            assert_eq!(writer.get_ref().written.len(), 10);
+            assert_eq!(
+                writer.finish().err(),
+                Some(Error::Io(io::ErrorKind::WriteZero))
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use core::fmt;
+
+mod trace_test;
+
+/**
+ * Maximum number of entries in a trace: the input and the first round key,
+ * then 5 entries for each of the 14 rounds of AES-256 and the output. The
+ * last round has no mix columns step unless the cipher was built with the
+ * hazmat module to keep it.
+ */
+pub const MAX_TRACE_ENTRIES: usize = 5 * 14 + 3;
+
+/**
+ * Which of the procedures described in the AES standard produced the trace
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceKind {
+    Cipher,
+    InverseCipher,
+    EquivalentInverseCipher,
+}
+
+/**
+ * The step of a round after which a state was recorded. The names follow
+ * the ones used in Appendix C of the AES standard.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Input,
+    Start,
+    SBox,
+    SRow,
+    MCol,
+    KSch,
+    KAdd,
+    Output,
+}
+
+/**
+ * A single line of the trace. For the KSch step the bytes are the ones of
+ * the round key used in that round instead of the state.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub round: usize,
+    pub step: Step,
+    pub state: [u8; 16],
+}
+
+/**
+ * The states recorded while ciphering or deciphering a block, in the order
+ * in which they were produced. It can be printed with the same format used
+ * in Appendix C of the AES standard, so the two can be compared line by line.
+ */
+#[derive(Clone, Debug)]
+pub struct Trace {
+    kind: TraceKind,
+    entries: [TraceEntry; MAX_TRACE_ENTRIES],
+    len: usize,
+}
+
+impl Step {
+    /**
+     * Name of the step as written in Appendix C of the AES standard
+     */
+    pub fn name(self) -> &'static str {
+        match self {
+            Step::Input => "input",
+            Step::Start => "start",
+            Step::SBox => "s_box",
+            Step::SRow => "s_row",
+            Step::MCol => "m_col",
+            Step::KSch => "k_sch",
+            Step::KAdd => "k_add",
+            Step::Output => "output",
+        }
+    }
+}
+
+impl TraceKind {
+    /**
+     * Title of the corresponding section in Appendix C of the AES standard
+     */
+    pub fn title(self) -> &'static str {
+        match self {
+            TraceKind::Cipher => "CIPHER (ENCRYPT):",
+            TraceKind::InverseCipher => "INVERSE CIPHER (DECRYPT):",
+            TraceKind::EquivalentInverseCipher => "EQUIVALENT INVERSE CIPHER (DECRYPT):",
+        }
+    }
+}
+
+impl Trace {
+    pub(crate) fn new(kind: TraceKind) -> Trace {
+        Trace {
+            kind,
+            entries: [TraceEntry {
+                round: 0,
+                step: Step::Input,
+                state: [0; 16],
+            }; MAX_TRACE_ENTRIES],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, round: usize, step: Step, state: &[u8; 16]) {
+        self.entries[self.len] = TraceEntry {
+            round,
+            step,
+            state: *state,
+        };
+        self.len += 1;
+    }
+
+    pub fn kind(&self) -> TraceKind {
+        self.kind
+    }
+
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries[..self.len]
+    }
+}
+
+/**
+ * Prints the section title followed by a line for each entry, like
+ * round[ 1].s_box    63cab7040953d051cd60e0e7ba70e18c
+ * The steps of the inverse ciphers get the 'i' prefix as in the standard.
+ */
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = match self.kind {
+            TraceKind::Cipher => "",
+            _ => "i",
+        };
+        writeln!(f, "{}", self.kind.title())?;
+        for entry in self.entries() {
+            write!(
+                f,
+                "round[{:2}].{}{:<width$}",
+                entry.round,
+                prefix,
+                entry.step.name(),
+                width = 10 - prefix.len()
+            )?;
+            for byte in entry.state.iter() {
+                write!(f, "{:02x}", byte)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod trace_test {
+    use crate::cipher::Cipher;
+    use crate::trace::{Step, TraceKind};
+    use std::format;
+    use std::string::String;
+    use std::vec::Vec;
+
+    fn fips_197_cipher() -> Cipher {
+        Cipher::new_128(&[0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f])
+    }
+
+    /**
+     * The first 7 and the last 5 lines of each trace, taken from Appendix C.1 of the AES standard
+     */
+    fn assert_lines(printed: String, first: &[&str], last: &[&str]) {
+        let lines: Vec<&str> = printed.lines().collect();
+        assert_eq!(lines.len(), 5 * 10 + 3);
+        assert_eq!(lines[..first.len()], *first);
+        assert_eq!(lines[lines.len() - last.len()..], *last);
+    }
+
+    #[test]
+    fn it_traces_cipher() {
+        let mut content: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let trace = fips_197_cipher().cipher_traced(&mut content);
+        assert_eq!(trace.kind(), TraceKind::Cipher);
+        assert_eq!(trace.entries()[4].round, 1);
+        assert_eq!(trace.entries()[4].step, Step::SRow);
+        assert_eq!(trace.entries().last().unwrap().state, content);
+        assert_lines(
+            format!("{}", trace),
+            &[
+                "CIPHER (ENCRYPT):",
+                "round[ 0].input     00112233445566778899aabbccddeeff",
+                "round[ 0].k_sch     000102030405060708090a0b0c0d0e0f",
+                "round[ 1].start     00102030405060708090a0b0c0d0e0f0",
+                "round[ 1].s_box     63cab7040953d051cd60e0e7ba70e18c",
+                "round[ 1].s_row     6353e08c0960e104cd70b751bacad0e7",
+                "round[ 1].m_col     5f72641557f5bc92f7be3b291db9f91a",
+            ],
+            &[
+                "round[10].start     bd6e7c3df2b5779e0b61216e8b10b689",
+                "round[10].s_box     7a9f102789d5f50b2beffd9f3dca4ea7",
+                "round[10].s_row     7ad5fda789ef4e272bca100b3d9ff59f",
+                "round[10].k_sch     13111d7fe3944a17f307a78b4d2b30c5",
+                "round[10].output    69c4e0d86a7b0430d8cdb78070b4c55a",
+            ],
+        );
+    }
+
+    #[test]
+    fn it_traces_inverse_cipher() {
+        let mut content: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let trace = fips_197_cipher().decipher_straight_traced(&mut content);
+        assert_eq!(trace.kind(), TraceKind::InverseCipher);
+        assert_lines(
+            format!("{}", trace),
+            &[
+                "INVERSE CIPHER (DECRYPT):",
+                "round[ 0].iinput    69c4e0d86a7b0430d8cdb78070b4c55a",
+                "round[ 0].ik_sch    13111d7fe3944a17f307a78b4d2b30c5",
+                "round[ 1].istart    7ad5fda789ef4e272bca100b3d9ff59f",
+                "round[ 1].is_row    7a9f102789d5f50b2beffd9f3dca4ea7",
+                "round[ 1].is_box    bd6e7c3df2b5779e0b61216e8b10b689",
+                "round[ 1].ik_sch    549932d1f08557681093ed9cbe2c974e",
+            ],
+            &[
+                "round[10].istart    6353e08c0960e104cd70b751bacad0e7",
+                "round[10].is_row    63cab7040953d051cd60e0e7ba70e18c",
+                "round[10].is_box    00102030405060708090a0b0c0d0e0f0",
+                "round[10].ik_sch    000102030405060708090a0b0c0d0e0f",
+                "round[10].ioutput   00112233445566778899aabbccddeeff",
+            ],
+        );
+    }
+
+    #[test]
+    fn it_traces_equivalent_inverse_cipher() {
+        let mut content: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let trace = fips_197_cipher().decipher_traced(&mut content);
+        assert_eq!(trace.kind(), TraceKind::EquivalentInverseCipher);
+        assert_lines(
+            format!("{}", trace),
+            &[
+                "EQUIVALENT INVERSE CIPHER (DECRYPT):",
+                "round[ 0].iinput    69c4e0d86a7b0430d8cdb78070b4c55a",
+                "round[ 0].ik_sch    13111d7fe3944a17f307a78b4d2b30c5",
+                "round[ 1].istart    7ad5fda789ef4e272bca100b3d9ff59f",
+                "round[ 1].is_box    bdb52189f261b63d0b107c9e8b6e776e",
+                "round[ 1].is_row    bd6e7c3df2b5779e0b61216e8b10b689",
+                "round[ 1].im_col    4773b91ff72f354361cb018ea1e6cf2c",
+            ],
+            &[
+                "round[10].istart    6353e08c0960e104cd70b751bacad0e7",
+                "round[10].is_box    0050a0f04090e03080d02070c01060b0",
+                "round[10].is_row    00102030405060708090a0b0c0d0e0f0",
+                "round[10].ik_sch    000102030405060708090a0b0c0d0e0f",
+                "round[10].ioutput   00112233445566778899aabbccddeeff",
+            ],
+        );
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+ * XTS-AES of IEEE 1619 and NIST SP 800-38E, the mode for disk sectors and
+ * other data units that must be encrypted in place, without room for an IV
+ * or a tag.
+ *
+ * The key is two AES keys of the same length: the first ciphers the data,
+ * the second ciphers the number of the data unit into the tweak, which is
+ * multiplied by x in GF(2^128) for every block. A data unit whose length is
+ * not a multiple of 16 bytes uses ciphertext stealing, so the ciphertext is
+ * always as long as the plaintext, but it can't be shorter than a block.
+ * XTS doesn't authenticate anything, and the same data in the same unit
+ * always gives the same ciphertext.
+ */
+use crate::block::{BlockDecrypt, BlockEncrypt};
+use crate::cipher::{Cipher, Encryptor, Key};
+use crate::error::Error;
+use crate::math::Math;
+
+mod xts_test;
+
+/**
+ * The longest data unit, 2^20 blocks as IEEE 1619 and SP 800-38E allow
+ */
+pub const MAX_DATA_UNIT_LEN: usize = (1 << 20) * 16;
+
+/**
+ * XTS keyed with its two keys. Encrypting needs a data cipher that
+ * implements BlockEncrypt, decrypting one that implements BlockDecrypt.
+ */
+pub struct Xts<C = Cipher> {
+    cipher: C,
+    tweak_encryptor: Encryptor,
+}
+
+impl Xts {
+    /**
+     * The key of XTS as a single string of 32 or 64 bytes: the data key
+     * followed by the tweak key. Fails with Error::InvalidKeyLength for any
+     * other length, and with Error::WeakKey if the two halves are the same,
+     * which IEEE 1619-2018 and SP 800-38E forbid.
+     */
+    pub fn new(key: &[u8]) -> Result<Xts, Error> {
+        if key.len() != 32 && key.len() != 64 {
+            return Err(Error::InvalidKeyLength(key.len()));
+        }
+        let (data_key, tweak_key) = key.split_at(key.len() / 2);
+        if Math::constant_time_eq(data_key, tweak_key) {
+            return Err(Error::WeakKey);
+        }
+        Ok(Self::from_cipher(
+            Cipher::new(data_key)?,
+            &Key::from_bytes(tweak_key)?,
+        ))
+    }
+}
+
+impl<C> Xts<C> {
+    pub fn from_cipher(cipher: C, tweak_key: &Key) -> Xts<C> {
+        Xts {
+            cipher,
+            tweak_encryptor: Encryptor::from_key(tweak_key),
+        }
+    }
+
+    /**
+     * The tweak of the first block of a data unit: the cipher of its number,
+     * written in little endian
+     */
+    fn tweak(&self, data_unit: u128) -> [u8; 16] {
+        let mut tweak = data_unit.to_le_bytes();
+        self.tweak_encryptor.cipher(&mut tweak);
+        tweak
+    }
+}
+
+impl<C: BlockEncrypt> Xts<C> {
+    /**
+     * Encrypts a data unit in place. Fails with Error::InvalidLength if it is
+     * shorter than a block or longer than MAX_DATA_UNIT_LEN.
+     */
+    pub fn encrypt_data_unit(&self, data_unit: u128, data: &mut [u8]) -> Result<(), Error> {
+        if data.len() < 16 || data.len() > MAX_DATA_UNIT_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let mut tweak = self.tweak(data_unit);
+        let whole = data.len() / 16 * 16;
+        for block in data[..whole].chunks_mut(16) {
+            xor_encrypt_xor(|b| self.cipher.encrypt_block(b), &tweak, block);
+            multiply_by_x(&mut tweak);
+        }
+        let remainder = data.len() - whole;
+        if remainder > 0 {
+            // the last whole block gives its tail to the partial one and
+            // takes its place
+            let (head, partial) = data.split_at_mut(whole);
+            let last = &mut head[whole - 16..];
+            let mut block = [0; 16];
+            block.copy_from_slice(last);
+            last[..remainder].copy_from_slice(partial);
+            partial.copy_from_slice(&block[..remainder]);
+            xor_encrypt_xor(|b| self.cipher.encrypt_block(b), &tweak, last);
+        }
+        Ok(())
+    }
+}
+
+impl<C: BlockDecrypt> Xts<C> {
+    /**
+     * Decrypts a data unit in place. Fails with Error::InvalidLength if it is
+     * shorter than a block or longer than MAX_DATA_UNIT_LEN.
+     */
+    pub fn decrypt_data_unit(&self, data_unit: u128, data: &mut [u8]) -> Result<(), Error> {
+        if data.len() < 16 || data.len() > MAX_DATA_UNIT_LEN {
+            return Err(Error::InvalidLength);
+        }
+        let mut tweak = self.tweak(data_unit);
+        let remainder = data.len() % 16;
+        // with stealing the last whole block is the last one to be decrypted
+        let plain_blocks = if remainder == 0 {
+            data.len() / 16
+        } else {
+            data.len() / 16 - 1
+        };
+        for block in data[..plain_blocks * 16].chunks_mut(16) {
+            xor_encrypt_xor(|b| self.cipher.decrypt_block(b), &tweak, block);
+            multiply_by_x(&mut tweak);
+        }
+        if remainder > 0 {
+            let start = plain_blocks * 16;
+            let mut next_tweak = tweak;
+            multiply_by_x(&mut next_tweak);
+            let (last, partial) = data[start..].split_at_mut(16);
+            xor_encrypt_xor(|b| self.cipher.decrypt_block(b), &next_tweak, last);
+            let mut block = [0; 16];
+            block.copy_from_slice(last);
+            last[..remainder].copy_from_slice(partial);
+            partial.copy_from_slice(&block[..remainder]);
+            xor_encrypt_xor(|b| self.cipher.decrypt_block(b), &tweak, last);
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The XEX step applied to every block: xor with the tweak, cipher, xor again
+ */
+fn xor_encrypt_xor<F: Fn(&mut [u8; 16])>(cipher: F, tweak: &[u8; 16], data: &mut [u8]) {
+    let mut block = [0; 16];
+    for (byte, (value, mask)) in block.iter_mut().zip(data.iter().zip(tweak.iter())) {
+        *byte = value ^ mask;
+    }
+    cipher(&mut block);
+    for (byte, (value, mask)) in data.iter_mut().zip(block.iter().zip(tweak.iter())) {
+        *byte = value ^ mask;
+    }
+}
+
+/**
+ * Multiplication by x of the tweak, which XTS stores in little endian order,
+ * modulo x^128 + x^7 + x^2 + x + 1
+ */
+fn multiply_by_x(tweak: &mut [u8; 16]) {
+    let doubled = u128::from_le_bytes(*tweak);
+    // masked like the doubling of CMAC and PMAC, the tweak is secret
+    *tweak = ((doubled << 1) ^ (0x87 & (doubled >> 127).wrapping_neg())).to_le_bytes();
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod xts_test {
+    use crate::cipher::Cipher;
+    use crate::error::Error;
+    use crate::xts::{Xts, MAX_DATA_UNIT_LEN};
+
+    fn hex(text: &str) -> std::vec::Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn counting(length: usize) -> std::vec::Vec<u8> {
+        (0..length).map(|i| i as u8).collect()
+    }
+
+    /**
+     * Vector 4 of IEEE 1619, a 512 bytes sector
+     */
+    #[test]
+    fn it_matches_ieee_1619() {
+        let xts = Xts::new(&hex(
+            "2718281828459045235360287471352631415926535897932384626433832795",
+        ))
+        .unwrap();
+        let mut data = counting(256);
+        data.extend_from_slice(&counting(256));
+        xts.encrypt_data_unit(0, &mut data).unwrap();
+        assert_eq!(
+            data[..32],
+            hex("27a7479befa1d476489f308cd4cfa6e2a96e4bbe3208ff25287dd3819616e89c")[..]
+        );
+        assert_eq!(data[496..], hex("0a282df920147beabe421ee5319d0568")[..]);
+        xts.decrypt_data_unit(0, &mut data).unwrap();
+        assert_eq!(data[256..], counting(256)[..]);
+    }
+
+    /**
+     * Vector 15 of IEEE 1619 and two longer messages, checked against Python's
+     * cryptography
+     */
+    #[test]
+    fn it_steals_ciphertext() {
+        let xts = Xts::new(&hex(
+            "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+        ))
+        .unwrap();
+        for (length, expected) in [
+            (17, "6c1625db4671522d3d7599601de7ca09ed"),
+            (20, "9d84c813f719aa2c7be3f66171c7c5c2edbf9dac"),
+            (
+                31,
+                "d05bc090a8e04f1b3d3ecdd5baec0fd4edbf9dace45d6f6a7306e64be5dd82",
+            ),
+        ]
+        .iter()
+        {
+            let mut data = counting(*length);
+            xts.encrypt_data_unit(0x123456789a, &mut data).unwrap();
+            assert_eq!(data, hex(expected));
+            xts.decrypt_data_unit(0x123456789a, &mut data).unwrap();
+            assert_eq!(data, counting(*length));
+        }
+    }
+
+    #[test]
+    fn it_uses_aes_256() {
+        let key = counting(64);
+        let xts = Xts::new(&key).unwrap();
+        let mut data = counting(100);
+        xts.encrypt_data_unit(5, &mut data).unwrap();
+        assert_eq!(
+            data,
+            hex(concat!(
+                "f87ca2f29b117c1b024a6ec8e8c5994e76f7d16b43eed21e6936126969e00dab",
+                "9d57dfb999d8d20535e3b2b7a51fab52dc4f54b7080d50b48a54ca22ff3849f3",
+                "a47cd92ec842f1c18506001e0531e6c802363412e292bd9f99af9089970d0067",
+                "3b8ee819"
+            ))
+        );
+        let mut other = counting(100);
+        xts.encrypt_data_unit(6, &mut other).unwrap();
+        assert_ne!(data, other);
+    }
+
+    #[test]
+    fn it_refuses_short_units_and_wrong_keys() {
+        let xts = Xts::from_cipher(
+            Cipher::new(&[0; 16]).unwrap(),
+            &crate::cipher::Key::Aes128([1; 4]),
+        );
+        assert_eq!(
+            xts.encrypt_data_unit(0, &mut [0; 15]),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(xts.decrypt_data_unit(0, &mut []), Err(Error::InvalidLength));
+        let mut long = std::vec![0; MAX_DATA_UNIT_LEN + 1];
+        assert_eq!(
+            xts.encrypt_data_unit(0, &mut long),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            xts.decrypt_data_unit(0, &mut long),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(Xts::new(&[0; 48]).err(), Some(Error::InvalidKeyLength(48)));
+        assert_eq!(Xts::new(&[7; 32]).err(), Some(Error::WeakKey));
+        let mut key = counting(64);
+        key.copy_within(..32, 32);
+        assert_eq!(Xts::new(&key).err(), Some(Error::WeakKey));
+        key[63] ^= 1;
+        assert!(Xts::new(&key).is_ok());
+    }
+}